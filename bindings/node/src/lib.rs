@@ -0,0 +1,229 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use phoenix_types::dispatch::load_with_dispatch;
+use phoenix_types::events::{parse_log_instruction_data, MarketEvent};
+use phoenix_types::market::{LadderOrder, MarketHeader};
+
+// u64 values are returned as decimal strings, like client order ids, since a JS number only
+// holds 53 bits of integer precision and bid order sequence numbers use all 64.
+
+#[napi(object)]
+pub struct JsLadderOrder {
+    pub price_in_ticks: String,
+    pub size_in_base_lots: String,
+}
+
+impl From<&LadderOrder> for JsLadderOrder {
+    fn from(order: &LadderOrder) -> Self {
+        Self {
+            price_in_ticks: order.price_in_ticks.to_string(),
+            size_in_base_lots: order.size_in_base_lots.to_string(),
+        }
+    }
+}
+
+#[napi(object)]
+pub struct JsLadder {
+    pub bids: Vec<JsLadderOrder>,
+    pub asks: Vec<JsLadderOrder>,
+}
+
+#[napi(object)]
+pub struct JsMarketHeader {
+    pub status: String,
+    pub bids_size: String,
+    pub asks_size: String,
+    pub num_seats: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_decimals: u32,
+    pub quote_decimals: u32,
+    pub base_lot_size: String,
+    pub quote_lot_size: String,
+    pub tick_size_in_quote_atoms_per_base_unit: String,
+    pub market_sequence_number: String,
+}
+
+#[napi(object)]
+pub struct JsMarketEvent {
+    /// The event variant, e.g. "Fill" or "Place".
+    pub kind: String,
+    pub index: Option<u32>,
+    pub maker: Option<String>,
+    pub order_sequence_number: Option<String>,
+    pub client_order_id: Option<String>,
+    pub price_in_ticks: Option<String>,
+    pub base_lots: Option<String>,
+    pub base_lots_remaining: Option<String>,
+    pub quote_lots: Option<String>,
+    pub fee_in_quote_lots: Option<String>,
+    pub last_valid_slot: Option<String>,
+    pub last_valid_unix_timestamp_in_seconds: Option<String>,
+}
+
+impl JsMarketEvent {
+    fn new(kind: &str, index: u16) -> Self {
+        Self {
+            kind: kind.to_string(),
+            index: Some(index as u32),
+            maker: None,
+            order_sequence_number: None,
+            client_order_id: None,
+            price_in_ticks: None,
+            base_lots: None,
+            base_lots_remaining: None,
+            quote_lots: None,
+            fee_in_quote_lots: None,
+            last_valid_slot: None,
+            last_valid_unix_timestamp_in_seconds: None,
+        }
+    }
+}
+
+fn split_market_account(data: &[u8]) -> Result<(&MarketHeader, &[u8])> {
+    let header_size = std::mem::size_of::<MarketHeader>();
+    if data.len() < header_size {
+        return Err(Error::from_reason("Account data is too small"));
+    }
+    let (header_bytes, market_bytes) = data.split_at(header_size);
+    let header = bytemuck::try_from_bytes::<MarketHeader>(header_bytes)
+        .map_err(|_| Error::from_reason("Invalid market header"))?;
+    Ok((header, market_bytes))
+}
+
+/// Decodes the header of a market account.
+#[napi]
+pub fn decode_market_header(data: Buffer) -> Result<JsMarketHeader> {
+    let (header, _) = split_market_account(&data)?;
+    Ok(JsMarketHeader {
+        status: header.status.to_string(),
+        bids_size: header.market_size_params.bids_size.to_string(),
+        asks_size: header.market_size_params.asks_size.to_string(),
+        num_seats: header.market_size_params.num_seats.to_string(),
+        base_mint: header.base_params.mint_key.to_string(),
+        quote_mint: header.quote_params.mint_key.to_string(),
+        base_decimals: header.base_params.decimals,
+        quote_decimals: header.quote_params.decimals,
+        base_lot_size: header.get_base_lot_size().to_string(),
+        quote_lot_size: header.get_quote_lot_size().to_string(),
+        tick_size_in_quote_atoms_per_base_unit: header
+            .get_tick_size_in_quote_atoms_per_base_unit()
+            .to_string(),
+        market_sequence_number: header.market_sequence_number.to_string(),
+    })
+}
+
+/// Decodes a market account in place and returns the top `levels` levels of each side.
+#[napi]
+pub fn get_ladder(data: Buffer, levels: u32) -> Result<JsLadder> {
+    let (header, market_bytes) = split_market_account(&data)?;
+    let market = load_with_dispatch(&header.market_size_params, market_bytes)
+        .ok_or_else(|| Error::from_reason("Failed to load market"))?;
+    let ladder = market.inner.get_ladder(levels as u64);
+    Ok(JsLadder {
+        bids: ladder.bids.iter().map(JsLadderOrder::from).collect(),
+        asks: ladder.asks.iter().map(JsLadderOrder::from).collect(),
+    })
+}
+
+/// Parses the data of a Phoenix `Log` instruction into its events.
+#[napi]
+pub fn parse_log_events(data: Buffer) -> Result<Vec<JsMarketEvent>> {
+    let (_, events) = parse_log_instruction_data(&data)
+        .ok_or_else(|| Error::from_reason("Invalid log instruction data"))?;
+    Ok(events.iter().filter_map(to_js_event).collect())
+}
+
+fn to_js_event(event: &MarketEvent) -> Option<JsMarketEvent> {
+    let js_event = match *event {
+        MarketEvent::Fill {
+            index,
+            maker_id,
+            order_sequence_number,
+            price_in_ticks,
+            base_lots_filled,
+            base_lots_remaining,
+        } => JsMarketEvent {
+            maker: Some(maker_id.to_string()),
+            order_sequence_number: Some(order_sequence_number.to_string()),
+            price_in_ticks: Some(price_in_ticks.to_string()),
+            base_lots: Some(base_lots_filled.to_string()),
+            base_lots_remaining: Some(base_lots_remaining.to_string()),
+            ..JsMarketEvent::new("Fill", index)
+        },
+        MarketEvent::Place {
+            index,
+            order_sequence_number,
+            client_order_id,
+            price_in_ticks,
+            base_lots_placed,
+        } => JsMarketEvent {
+            order_sequence_number: Some(order_sequence_number.to_string()),
+            client_order_id: Some(client_order_id.to_string()),
+            price_in_ticks: Some(price_in_ticks.to_string()),
+            base_lots: Some(base_lots_placed.to_string()),
+            ..JsMarketEvent::new("Place", index)
+        },
+        MarketEvent::Reduce {
+            index,
+            order_sequence_number,
+            price_in_ticks,
+            base_lots_removed,
+            base_lots_remaining,
+        } => JsMarketEvent {
+            order_sequence_number: Some(order_sequence_number.to_string()),
+            price_in_ticks: Some(price_in_ticks.to_string()),
+            base_lots: Some(base_lots_removed.to_string()),
+            base_lots_remaining: Some(base_lots_remaining.to_string()),
+            ..JsMarketEvent::new("Reduce", index)
+        },
+        MarketEvent::Evict {
+            index,
+            maker_id,
+            order_sequence_number,
+            price_in_ticks,
+            base_lots_evicted,
+        } => JsMarketEvent {
+            maker: Some(maker_id.to_string()),
+            order_sequence_number: Some(order_sequence_number.to_string()),
+            price_in_ticks: Some(price_in_ticks.to_string()),
+            base_lots: Some(base_lots_evicted.to_string()),
+            ..JsMarketEvent::new("Evict", index)
+        },
+        MarketEvent::FillSummary {
+            index,
+            client_order_id,
+            total_base_lots_filled,
+            total_quote_lots_filled,
+            total_fee_in_quote_lots,
+        } => JsMarketEvent {
+            client_order_id: Some(client_order_id.to_string()),
+            base_lots: Some(total_base_lots_filled.to_string()),
+            quote_lots: Some(total_quote_lots_filled.to_string()),
+            fee_in_quote_lots: Some(total_fee_in_quote_lots.to_string()),
+            ..JsMarketEvent::new("FillSummary", index)
+        },
+        MarketEvent::Fee {
+            index,
+            fees_collected_in_quote_lots,
+        } => JsMarketEvent {
+            fee_in_quote_lots: Some(fees_collected_in_quote_lots.to_string()),
+            ..JsMarketEvent::new("Fee", index)
+        },
+        MarketEvent::TimeInForce {
+            index,
+            order_sequence_number,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+        } => JsMarketEvent {
+            order_sequence_number: Some(order_sequence_number.to_string()),
+            last_valid_slot: Some(last_valid_slot.to_string()),
+            last_valid_unix_timestamp_in_seconds: Some(
+                last_valid_unix_timestamp_in_seconds.to_string(),
+            ),
+            ..JsMarketEvent::new("TimeInForce", index)
+        },
+        MarketEvent::Uninitialized | MarketEvent::Header { .. } => return None,
+    };
+    Some(js_event)
+}
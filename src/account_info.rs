@@ -0,0 +1,144 @@
+//! Loading of Phoenix markets from `AccountInfo`s, for programs that read order books on chain.
+
+use crate::accounts::MARKET_HEADER_DISCRIMINANT;
+use crate::dispatch::{
+    load_with_dispatch, load_with_dispatch_mut, MarketWrapper, MarketWrapperMut,
+};
+use crate::market::MarketHeader;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::program_error::ProgramError;
+use std::cell::{Ref, RefMut};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum MarketAccountError {
+    #[error("Market account is not owned by the Phoenix program")]
+    InvalidOwner,
+    #[error("Market account data is already borrowed")]
+    AlreadyBorrowed,
+    #[error("Market account data is too small or misaligned for a market header")]
+    InvalidHeader,
+    #[error("Account discriminant does not match a market")]
+    InvalidDiscriminant,
+    #[error("Market size params are not supported or do not match the account data")]
+    InvalidMarket,
+}
+
+impl From<MarketAccountError> for ProgramError {
+    fn from(error: MarketAccountError) -> Self {
+        match error {
+            MarketAccountError::InvalidOwner => ProgramError::IllegalOwner,
+            MarketAccountError::AlreadyBorrowed => ProgramError::AccountBorrowFailed,
+            MarketAccountError::InvalidHeader
+            | MarketAccountError::InvalidDiscriminant
+            | MarketAccountError::InvalidMarket => ProgramError::InvalidAccountData,
+        }
+    }
+}
+
+/// The borrowed data of a market account, checked to be a Phoenix market.
+pub struct MarketAccount<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> MarketAccount<'a> {
+    /// Borrows the data of `info` after checking that it is owned by the Phoenix program, has the
+    /// market discriminant and supported size params.
+    pub fn load(info: &'a AccountInfo) -> Result<Self, MarketAccountError> {
+        check_owner(info)?;
+        let data = Ref::map(
+            info.try_borrow_data()
+                .map_err(|_| MarketAccountError::AlreadyBorrowed)?,
+            |data| &**data,
+        );
+        split_market(&data)?;
+        Ok(Self { data })
+    }
+
+    /// Returns the header and the market.
+    pub fn market(&self) -> (&MarketHeader, MarketWrapper<'_>) {
+        split_market(&self.data).expect("Market data is validated when the account is loaded")
+    }
+}
+
+/// The mutably borrowed data of a market account, checked to be a Phoenix market.
+pub struct MarketAccountMut<'a> {
+    data: RefMut<'a, [u8]>,
+}
+
+impl<'a> MarketAccountMut<'a> {
+    /// Mutably borrows the data of `info`, with the checks of `MarketAccount::load`.
+    pub fn load(info: &'a AccountInfo) -> Result<Self, MarketAccountError> {
+        check_owner(info)?;
+        let mut data = RefMut::map(
+            info.try_borrow_mut_data()
+                .map_err(|_| MarketAccountError::AlreadyBorrowed)?,
+            |data| &mut **data,
+        );
+        split_market_mut(&mut data)?;
+        Ok(Self { data })
+    }
+
+    /// Returns the header and the market.
+    pub fn market(&self) -> (&MarketHeader, MarketWrapper<'_>) {
+        split_market(&self.data).expect("Market data is validated when the account is loaded")
+    }
+
+    /// Returns the header and the mutable market.
+    pub fn market_mut(&mut self) -> (&MarketHeader, MarketWrapperMut<'_>) {
+        split_market_mut(&mut self.data)
+            .expect("Market data is validated when the account is loaded")
+    }
+}
+
+fn check_owner(info: &AccountInfo) -> Result<(), MarketAccountError> {
+    if *info.owner != crate::id() {
+        return Err(MarketAccountError::InvalidOwner);
+    }
+    Ok(())
+}
+
+fn load_header(header_bytes: &[u8]) -> Result<&MarketHeader, MarketAccountError> {
+    let header = bytemuck::try_from_bytes::<MarketHeader>(header_bytes)
+        .map_err(|_| MarketAccountError::InvalidHeader)?;
+    if header.discriminant != MARKET_HEADER_DISCRIMINANT {
+        return Err(MarketAccountError::InvalidDiscriminant);
+    }
+    Ok(header)
+}
+
+fn split_market(data: &[u8]) -> Result<(&MarketHeader, MarketWrapper<'_>), MarketAccountError> {
+    let header_size = std::mem::size_of::<MarketHeader>();
+    if data.len() < header_size {
+        return Err(MarketAccountError::InvalidHeader);
+    }
+    let (header_bytes, market_bytes) = data.split_at(header_size);
+    let header = load_header(header_bytes)?;
+    let market = load_with_dispatch(&header.market_size_params, market_bytes)
+        .ok_or(MarketAccountError::InvalidMarket)?;
+    Ok((header, market))
+}
+
+fn split_market_mut(
+    data: &mut [u8],
+) -> Result<(&MarketHeader, MarketWrapperMut<'_>), MarketAccountError> {
+    let header_size = std::mem::size_of::<MarketHeader>();
+    if data.len() < header_size {
+        return Err(MarketAccountError::InvalidHeader);
+    }
+    let (header_bytes, market_bytes) = data.split_at_mut(header_size);
+    let header = load_header(header_bytes)?;
+    let market = load_with_dispatch_mut(&header.market_size_params, market_bytes)
+        .ok_or(MarketAccountError::InvalidMarket)?;
+    Ok((header, market))
+}
+
+/// Runs `f` on the header and market of `info`, after the checks of `MarketAccount::load`.
+pub fn with_market<T>(
+    info: &AccountInfo,
+    f: impl FnOnce(&MarketHeader, MarketWrapper) -> T,
+) -> Result<T, MarketAccountError> {
+    let account = MarketAccount::load(info)?;
+    let (header, market) = account.market();
+    Ok(f(header, market))
+}
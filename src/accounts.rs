@@ -0,0 +1,122 @@
+use crate::market::{MarketHeader, Seat};
+use solana_sdk::{account::Account, keccak, pubkey::Pubkey};
+
+/// The discriminant stored in the first 8 bytes of every market account.
+pub const MARKET_HEADER_DISCRIMINANT: u64 = 5486421722818059155;
+
+/// The discriminant stored in the first 8 bytes of every seat account.
+pub const SEAT_DISCRIMINANT: u64 = 11792425028094311033;
+
+/// An account type that the Phoenix program tags with a discriminant.
+pub trait Discriminated {
+    /// The fully qualified name of the type in the Phoenix program, from which its discriminant
+    /// is derived.
+    const PROGRAM_TYPE_NAME: &'static str;
+
+    /// The discriminant stored in the first 8 bytes of accounts of this type.
+    const DISCRIMINANT: u64;
+}
+
+impl Discriminated for MarketHeader {
+    const PROGRAM_TYPE_NAME: &'static str = "phoenix::program::accounts::MarketHeader";
+    const DISCRIMINANT: u64 = MARKET_HEADER_DISCRIMINANT;
+}
+
+impl Discriminated for Seat {
+    const PROGRAM_TYPE_NAME: &'static str = "phoenix::program::accounts::Seat";
+    const DISCRIMINANT: u64 = SEAT_DISCRIMINANT;
+}
+
+/// Derives the discriminant of a program type the way the program does: the first 8 bytes,
+/// little-endian, of the keccak hash of the program id followed by the type name.
+pub fn get_discriminant(program_type_name: &str) -> u64 {
+    let hash = keccak::hashv(&[crate::id().as_ref(), program_type_name.as_bytes()]);
+    u64::from_le_bytes(hash.as_ref()[..8].try_into().unwrap())
+}
+
+/// Derives the discriminant of `T` from its program type name. This always equals
+/// `T::DISCRIMINANT`.
+pub fn compute_discriminant<T: Discriminated>() -> u64 {
+    get_discriminant(T::PROGRAM_TYPE_NAME)
+}
+
+/// A Phoenix program account, classified by its discriminant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhoenixAccountKind {
+    Market(Box<MarketHeader>),
+    Seat(Seat),
+    /// An account not owned by the Phoenix program, or whose discriminant or size does not match
+    /// any known account type.
+    Unknown,
+}
+
+impl PhoenixAccountKind {
+    /// Classifies an account by its owner and discriminant.
+    pub fn from_account(account: &Account) -> Self {
+        if account.owner != crate::id() {
+            return Self::Unknown;
+        }
+        Self::from_account_data(&account.data)
+    }
+
+    /// Classifies the data of an account owned by the Phoenix program by its discriminant.
+    pub fn from_account_data(data: &[u8]) -> Self {
+        let discriminant = match data.get(..8) {
+            Some(bytes) => u64::from_le_bytes(bytes.try_into().unwrap()),
+            None => return Self::Unknown,
+        };
+        match discriminant {
+            MARKET_HEADER_DISCRIMINANT => data
+                .get(..std::mem::size_of::<MarketHeader>())
+                .and_then(|bytes| bytemuck::try_pod_read_unaligned(bytes).ok())
+                .map_or(Self::Unknown, |header| Self::Market(Box::new(header))),
+            SEAT_DISCRIMINANT => data
+                .get(..std::mem::size_of::<Seat>())
+                .and_then(|bytes| bytemuck::try_pod_read_unaligned(bytes).ok())
+                .map_or(Self::Unknown, Self::Seat),
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The accounts of a `getProgramAccounts` response for the Phoenix program, partitioned by type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassifiedAccounts {
+    /// Market accounts and their headers. The full account data is needed to load the book.
+    pub markets: Vec<(Pubkey, MarketHeader)>,
+    pub seats: Vec<(Pubkey, Seat)>,
+    pub unknown: Vec<Pubkey>,
+}
+
+impl ClassifiedAccounts {
+    pub fn from_program_accounts(accounts: &[(Pubkey, Account)]) -> Self {
+        let mut classified = Self::default();
+        for (pubkey, kind) in classify_program_accounts(accounts) {
+            match kind {
+                PhoenixAccountKind::Market(header) => classified.markets.push((pubkey, *header)),
+                PhoenixAccountKind::Seat(seat) => classified.seats.push((pubkey, seat)),
+                PhoenixAccountKind::Unknown => classified.unknown.push(pubkey),
+            }
+        }
+        classified
+    }
+
+    /// Returns the seats of `market`.
+    pub fn seats_for_market<'a>(
+        &'a self,
+        market: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a (Pubkey, Seat)> + 'a {
+        self.seats
+            .iter()
+            .filter(move |(_, seat)| seat.market == *market)
+    }
+}
+
+/// Classifies each `(Pubkey, Account)` pair of a `getProgramAccounts` response.
+pub fn classify_program_accounts(
+    accounts: &[(Pubkey, Account)],
+) -> impl Iterator<Item = (Pubkey, PhoenixAccountKind)> + '_ {
+    accounts
+        .iter()
+        .map(|(pubkey, account)| (*pubkey, PhoenixAccountKind::from_account(account)))
+}
@@ -0,0 +1,103 @@
+use crate::market::{Ladder, MarketHeader, MarketStatus};
+use serde::{Deserialize, Serialize};
+
+/// The limits beyond which a book is reported as unhealthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookHealthThresholds {
+    /// The most slots the book may lag behind the current slot.
+    pub max_slot_age: u64,
+
+    /// The widest spread allowed, in basis points of the mid price.
+    pub max_spread_bps: u64,
+}
+
+impl Default for BookHealthThresholds {
+    /// About a minute of slots and a 10% spread.
+    fn default() -> Self {
+        Self {
+            max_slot_age: 150,
+            max_spread_bps: 1000,
+        }
+    }
+}
+
+/// A reason not to trust a book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookWarning {
+    /// The best bid is at or above the best ask.
+    Crossed {
+        best_bid_in_ticks: u64,
+        best_ask_in_ticks: u64,
+    },
+    EmptyBids,
+    EmptyAsks,
+    /// The book was observed `slot_age` slots before the current slot.
+    Stale {
+        slot_age: u64,
+    },
+    /// The spread is wider than the threshold.
+    WideSpread {
+        spread_bps: u64,
+    },
+    /// The market does not accept taker orders. Holds the raw status of the header.
+    NotTradable {
+        status: u64,
+    },
+}
+
+/// The warnings raised for a book, in the order they were checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookHealth {
+    pub warnings: Vec<BookWarning>,
+}
+
+impl BookHealth {
+    /// Checks a book observed at `ladder_slot` against `thresholds`, as of `now_slot`.
+    pub fn evaluate(
+        ladder: &Ladder,
+        header: &MarketHeader,
+        ladder_slot: u64,
+        now_slot: u64,
+        thresholds: &BookHealthThresholds,
+    ) -> Self {
+        let mut warnings = vec![];
+        if header.status != MarketStatus::Active as u64 {
+            warnings.push(BookWarning::NotTradable {
+                status: header.status,
+            });
+        }
+        let slot_age = now_slot.saturating_sub(ladder_slot);
+        if slot_age > thresholds.max_slot_age {
+            warnings.push(BookWarning::Stale { slot_age });
+        }
+        match (ladder.bids.first(), ladder.asks.first()) {
+            (None, None) => warnings.extend([BookWarning::EmptyBids, BookWarning::EmptyAsks]),
+            (None, Some(_)) => warnings.push(BookWarning::EmptyBids),
+            (Some(_), None) => warnings.push(BookWarning::EmptyAsks),
+            (Some(bid), Some(ask)) if bid.price_in_ticks >= ask.price_in_ticks => {
+                warnings.push(BookWarning::Crossed {
+                    best_bid_in_ticks: bid.price_in_ticks,
+                    best_ask_in_ticks: ask.price_in_ticks,
+                })
+            }
+            (Some(bid), Some(ask)) => {
+                let spread_bps = spread_bps(bid.price_in_ticks, ask.price_in_ticks);
+                if spread_bps > thresholds.max_spread_bps {
+                    warnings.push(BookWarning::WideSpread { spread_bps });
+                }
+            }
+        }
+        Self { warnings }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Returns the spread between an uncrossed best bid and best ask, in basis points of the mid
+/// price, rounded down.
+pub(crate) fn spread_bps(best_bid_in_ticks: u64, best_ask_in_ticks: u64) -> u64 {
+    let spread = (best_ask_in_ticks - best_bid_in_ticks) as u128;
+    (spread * 20_000 / (best_ask_in_ticks as u128 + best_bid_in_ticks as u128)) as u64
+}
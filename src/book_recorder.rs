@@ -0,0 +1,71 @@
+use crate::market::Ladder;
+use std::collections::VecDeque;
+
+/// Keeps the most recent `capacity` ladders of a market, keyed by the slot they were observed at.
+#[derive(Debug, Clone)]
+pub struct BookRecorder {
+    capacity: usize,
+    entries: VecDeque<(u64, Ladder)>,
+}
+
+impl BookRecorder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    /// Records the ladder observed at `slot`, evicting the oldest entry if the recorder is full.
+    /// A ladder for a slot that was already recorded replaces it, and ladders older than the
+    /// newest entry are ignored.
+    pub fn record(&mut self, slot: u64, ladder: Ladder) {
+        match self.entries.back_mut() {
+            Some((last_slot, last)) if *last_slot == slot => {
+                *last = ladder;
+                return;
+            }
+            Some((last_slot, _)) if *last_slot > slot => return,
+            _ => {}
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((slot, ladder));
+    }
+
+    /// Returns the book as of `slot`: the most recent ladder recorded at or before it. Returns
+    /// None if `slot` is older than every recorded ladder.
+    pub fn get_at_slot(&self, slot: u64) -> Option<(u64, &Ladder)> {
+        let index = self.entries.partition_point(|(s, _)| *s <= slot);
+        index
+            .checked_sub(1)
+            .and_then(|i| self.entries.get(i))
+            .map(|(s, ladder)| (*s, ladder))
+    }
+
+    pub fn latest(&self) -> Option<(u64, &Ladder)> {
+        self.entries.back().map(|(s, ladder)| (*s, ladder))
+    }
+
+    /// Returns the range of slots covered by the recorder, oldest first.
+    pub fn slot_range(&self) -> Option<(u64, u64)> {
+        Some((self.entries.front()?.0, self.entries.back()?.0))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &Ladder)> {
+        self.entries.iter().map(|(s, ladder)| (*s, ladder))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
@@ -0,0 +1,187 @@
+use crate::dispatch::load_with_dispatch;
+use crate::enums::Side;
+use crate::market::{
+    FIFOOrderId, FIFORestingOrder, Ladder, LadderOrder, Market, MarketHeader, TraderState,
+};
+use crate::units::{BaseLotsPerBaseUnit, QuoteLotsPerBaseUnitPerTick};
+use sokoban::node_allocator::OrderedNodeAllocatorMap;
+use solana_sdk::pubkey::Pubkey;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// An owned copy of a market account that caches the data derived from it.
+///
+/// The ladder of the deepest depth requested is cached, and shallower ladders are cut from it,
+/// and the trader index map is built once, so repeated queries between updates do not walk the
+/// book again. The caches are cleared by `update`.
+pub struct CachedMarketView {
+    header: MarketHeader,
+    data: Vec<u8>,
+    /// The deepest ladder built since the last update, with its depth.
+    ladder: RefCell<Option<(u64, Ladder)>>,
+    trader_indices: RefCell<Option<HashMap<Pubkey, u32>>>,
+    /// The buffer replaced by the last in-place update, kept so its allocation can be reused.
+    #[cfg(feature = "compression")]
+    spare: Vec<u8>,
+}
+
+impl CachedMarketView {
+    /// Creates a view of the raw data of a market account. Returns None if the data is not a
+    /// market with supported size params.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        let header = Self::load_header(&data)?;
+        Some(Self {
+            header,
+            data,
+            ladder: RefCell::new(None),
+            trader_indices: RefCell::new(None),
+            #[cfg(feature = "compression")]
+            spare: vec![],
+        })
+    }
+
+    /// Replaces the account data and clears the caches. Returns None, leaving the view
+    /// unchanged, if the data is not a market with supported size params.
+    pub fn update(&mut self, data: Vec<u8>) -> Option<()> {
+        self.header = Self::load_header(&data)?;
+        self.data = data;
+        self.invalidate();
+        Some(())
+    }
+
+    /// The buffer that the next in-place update is written to.
+    #[cfg(feature = "compression")]
+    pub(crate) fn spare_buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.spare
+    }
+
+    /// Replaces the account data with the contents of the spare buffer, which then holds the
+    /// previous data. Returns None, leaving the account data unchanged, if the spare buffer is
+    /// not a market with supported size params.
+    #[cfg(feature = "compression")]
+    pub(crate) fn swap_in_spare_buffer(&mut self) -> Option<()> {
+        self.header = Self::load_header(&self.spare)?;
+        std::mem::swap(&mut self.data, &mut self.spare);
+        self.invalidate();
+        Some(())
+    }
+
+    /// Clears the caches without changing the account data.
+    pub fn invalidate(&mut self) {
+        *self.ladder.get_mut() = None;
+        *self.trader_indices.get_mut() = None;
+    }
+
+    pub fn header(&self) -> &MarketHeader {
+        &self.header
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the best bid, from the cached top of book.
+    pub fn best_bid(&self) -> Option<LadderOrder> {
+        self.get_ladder(1).bids.first().copied()
+    }
+
+    /// Returns the best ask, from the cached top of book.
+    pub fn best_ask(&self) -> Option<LadderOrder> {
+        self.get_ladder(1).asks.first().copied()
+    }
+
+    fn load_header(data: &[u8]) -> Option<MarketHeader> {
+        let header_size = std::mem::size_of::<MarketHeader>();
+        if data.len() < header_size {
+            return None;
+        }
+        let (header_bytes, market_bytes) = data.split_at(header_size);
+        let header = bytemuck::try_pod_read_unaligned::<MarketHeader>(header_bytes).ok()?;
+        load_with_dispatch(&header.market_size_params, market_bytes)?;
+        Some(header)
+    }
+
+    fn market(&self) -> &dyn Market {
+        load_with_dispatch(
+            &self.header.market_size_params,
+            &self.data[std::mem::size_of::<MarketHeader>()..],
+        )
+        .expect("Market data is validated when the view is created")
+        .inner
+    }
+}
+
+impl Market for CachedMarketView {
+    fn get_ladder(&self, levels: u64) -> Ladder {
+        let mut cached = self.ladder.borrow_mut();
+        if cached.as_ref().is_none_or(|(depth, _)| *depth < levels) {
+            *cached = Some((levels, self.market().get_ladder(levels)));
+        }
+        let (_, ladder) = cached.as_ref().unwrap();
+        let levels = usize::try_from(levels).unwrap_or(usize::MAX);
+        Ladder {
+            bids: ladder.bids.iter().take(levels).copied().collect(),
+            asks: ladder.asks.iter().take(levels).copied().collect(),
+        }
+    }
+
+    fn get_registered_traders(&self) -> &dyn OrderedNodeAllocatorMap<Pubkey, TraderState> {
+        self.market().get_registered_traders()
+    }
+
+    fn get_taker_bps(&self) -> u16 {
+        self.market().get_taker_bps()
+    }
+
+    fn get_base_lots_per_base_unit(&self) -> BaseLotsPerBaseUnit {
+        self.market().get_base_lots_per_base_unit()
+    }
+
+    fn get_tick_size_in_quote_lots_per_base_unit(&self) -> QuoteLotsPerBaseUnitPerTick {
+        self.market().get_tick_size_in_quote_lots_per_base_unit()
+    }
+
+    fn get_order_sequence_number(&self) -> u64 {
+        self.market().get_order_sequence_number()
+    }
+
+    fn get_trader_address(&self, trader: &Pubkey) -> Option<u32> {
+        self.market().get_trader_address(trader)
+    }
+
+    fn get_trader_state(&self, trader: &Pubkey) -> Option<&TraderState> {
+        self.market().get_trader_state(trader)
+    }
+
+    fn get_trader_index(&self, trader_id: &Pubkey) -> Option<u32> {
+        self.trader_indices
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                let market = self.market();
+                market
+                    .get_registered_traders()
+                    .iter()
+                    .filter_map(|(trader, _)| Some((*trader, market.get_trader_index(trader)?)))
+                    .collect()
+            })
+            .get(trader_id)
+            .copied()
+    }
+
+    fn get_trader_id_from_index(&self, trader_index: u32) -> Pubkey {
+        self.market().get_trader_id_from_index(trader_index)
+    }
+
+    fn get_book(&self, side: Side) -> &dyn OrderedNodeAllocatorMap<FIFOOrderId, FIFORestingOrder> {
+        self.market().get_book(side)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,247 @@
+//! A canonical JSON encoding for books and snapshots, so that hashes and diffs computed by
+//! different services agree byte for byte.
+//!
+//! The encoding has no whitespace, object keys are sorted by their UTF-8 bytes, integers are
+//! written in plain decimal, and pubkeys are written as base58 strings. Arrays keep the order of
+//! the encoded value, which for books is book order.
+
+use crate::market::{
+    FIFOOrderId, FIFORestingOrder, Ladder, LadderOrder, MarketHeader, MarketSizeParams,
+    TokenParams, TraderState,
+};
+use crate::snapshot::{MarketSnapshot, SnapshotTrader};
+use crate::units::{BaseLots, QuoteLots};
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write;
+
+pub trait CanonicalJson {
+    fn write_canonical_json(&self, out: &mut String);
+
+    fn to_canonical_json(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical_json(&mut out);
+        out
+    }
+
+    /// The SHA-256 hash of the canonical encoding.
+    fn canonical_hash(&self) -> Hash {
+        hash(self.to_canonical_json().as_bytes())
+    }
+}
+
+macro_rules! canonical_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl CanonicalJson for $ty {
+                fn write_canonical_json(&self, out: &mut String) {
+                    write!(out, "{}", self).unwrap();
+                }
+            }
+        )*
+    };
+}
+
+canonical_integer!(u16, u32, u64, BaseLots, QuoteLots);
+
+impl CanonicalJson for str {
+    fn write_canonical_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl CanonicalJson for Pubkey {
+    fn write_canonical_json(&self, out: &mut String) {
+        self.to_string().write_canonical_json(out)
+    }
+}
+
+impl<T: CanonicalJson> CanonicalJson for [T] {
+    fn write_canonical_json(&self, out: &mut String) {
+        out.push('[');
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            item.write_canonical_json(out);
+        }
+        out.push(']');
+    }
+}
+
+impl<T: CanonicalJson> CanonicalJson for Vec<T> {
+    fn write_canonical_json(&self, out: &mut String) {
+        self.as_slice().write_canonical_json(out)
+    }
+}
+
+impl<A: CanonicalJson, B: CanonicalJson> CanonicalJson for (A, B) {
+    fn write_canonical_json(&self, out: &mut String) {
+        out.push('[');
+        self.0.write_canonical_json(out);
+        out.push(',');
+        self.1.write_canonical_json(out);
+        out.push(']');
+    }
+}
+
+/// Collects the fields of an object and writes them sorted by key.
+struct Object<'a> {
+    fields: Vec<(&'static str, &'a dyn CanonicalJson)>,
+}
+
+impl<'a> Object<'a> {
+    fn new() -> Self {
+        Self { fields: vec![] }
+    }
+
+    fn field(mut self, key: &'static str, value: &'a dyn CanonicalJson) -> Self {
+        self.fields.push((key, value));
+        self
+    }
+
+    fn write(mut self, out: &mut String) {
+        self.fields.sort_by_key(|(key, _)| *key);
+        out.push('{');
+        for (i, (key, value)) in self.fields.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            key.write_canonical_json(out);
+            out.push(':');
+            value.write_canonical_json(out);
+        }
+        out.push('}');
+    }
+}
+
+impl CanonicalJson for LadderOrder {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("price_in_ticks", &self.price_in_ticks)
+            .field("size_in_base_lots", &self.size_in_base_lots)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for Ladder {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("bids", &self.bids)
+            .field("asks", &self.asks)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for FIFOOrderId {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("price_in_ticks", &self.price_in_ticks)
+            .field("order_sequence_number", &self.order_sequence_number)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for FIFORestingOrder {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("trader_index", &self.trader_index)
+            .field("num_base_lots", &self.num_base_lots)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for TraderState {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("quote_lots_locked", &self.quote_lots_locked)
+            .field("quote_lots_free", &self.quote_lots_free)
+            .field("base_lots_locked", &self.base_lots_locked)
+            .field("base_lots_free", &self.base_lots_free)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for SnapshotTrader {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("trader", &self.trader)
+            .field("trader_index", &self.trader_index)
+            .field("state", &self.state)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for MarketSizeParams {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("bids_size", &self.bids_size)
+            .field("asks_size", &self.asks_size)
+            .field("num_seats", &self.num_seats)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for TokenParams {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("decimals", &self.decimals)
+            .field("vault_bump", &self.vault_bump)
+            .field("mint_key", &self.mint_key)
+            .field("vault_key", &self.vault_key)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for MarketHeader {
+    fn write_canonical_json(&self, out: &mut String) {
+        let base_lot_size = self.get_base_lot_size();
+        let quote_lot_size = self.get_quote_lot_size();
+        let tick_size = self.get_tick_size_in_quote_atoms_per_base_unit();
+        Object::new()
+            .field("discriminant", &self.discriminant)
+            .field("status", &self.status)
+            .field("market_size_params", &self.market_size_params)
+            .field("base_params", &self.base_params)
+            .field("base_lot_size", &base_lot_size)
+            .field("quote_params", &self.quote_params)
+            .field("quote_lot_size", &quote_lot_size)
+            .field("tick_size_in_quote_atoms_per_base_unit", &tick_size)
+            .field("authority", &self.authority)
+            .field("fee_destination", &self.fee_destination)
+            .field("market_sequence_number", &self.market_sequence_number)
+            .field("successor", &self.successor)
+            .write(out)
+    }
+}
+
+impl CanonicalJson for MarketSnapshot {
+    fn write_canonical_json(&self, out: &mut String) {
+        Object::new()
+            .field("slot", &self.slot)
+            .field("header", &self.header)
+            .field("base_lots_per_base_unit", &self.base_lots_per_base_unit)
+            .field(
+                "tick_size_in_quote_lots_per_base_unit",
+                &self.tick_size_in_quote_lots_per_base_unit,
+            )
+            .field("taker_fee_bps", &self.taker_fee_bps)
+            .field("order_sequence_number", &self.order_sequence_number)
+            .field("bids", &self.bids)
+            .field("asks", &self.asks)
+            .field("traders", &self.traders)
+            .write(out)
+    }
+}
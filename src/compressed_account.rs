@@ -0,0 +1,146 @@
+//! Decoding of market accounts in the encodings RPC nodes return them in, streaming base64
+//! decoding into decompression so the account is materialized in a single buffer.
+
+use crate::cached_market::CachedMarketView;
+use crate::dispatch::supported_market_sizes;
+use crate::market::MarketHeader;
+use crate::snapshot::MarketSnapshot;
+use base64::engine::general_purpose::STANDARD;
+use base64::read::DecoderReader;
+use std::io::Read;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AccountDecodeError {
+    #[error("Unsupported account encoding {0}")]
+    UnsupportedEncoding(String),
+    #[error("Failed to decode account data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Account data is not a supported market")]
+    InvalidMarket,
+    #[error("Decoded account data exceeds the largest supported market size of {0} bytes")]
+    TooLarge(usize),
+}
+
+/// The size in bytes of the largest supported market account, header included.
+pub fn max_market_account_size() -> usize {
+    let max_market_size = supported_market_sizes()
+        .map(|(_, size)| size)
+        .max()
+        .unwrap_or_default();
+    std::mem::size_of::<MarketHeader>() + max_market_size
+}
+
+/// The encodings of account data, as named in RPC requests and responses. RPC nodes return
+/// `base64+zstd`; `base64+gzip` is for payloads stored or relayed with gzip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountDataEncoding {
+    Base64,
+    Base64Zstd,
+    Base64Gzip,
+}
+
+impl AccountDataEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountDataEncoding::Base64 => "base64",
+            AccountDataEncoding::Base64Zstd => "base64+zstd",
+            AccountDataEncoding::Base64Gzip => "base64+gzip",
+        }
+    }
+}
+
+impl FromStr for AccountDataEncoding {
+    type Err = AccountDecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(AccountDataEncoding::Base64),
+            "base64+zstd" => Ok(AccountDataEncoding::Base64Zstd),
+            "base64+gzip" => Ok(AccountDataEncoding::Base64Gzip),
+            _ => Err(AccountDecodeError::UnsupportedEncoding(s.to_string())),
+        }
+    }
+}
+
+/// Decodes encoded account data into `out`, which is cleared first so that its allocation can
+/// be reused across updates.
+///
+/// Decoding stops with `TooLarge` once the output exceeds `max_market_account_size`, so a
+/// malicious compressed payload cannot expand without bound.
+pub fn decode_account_data_into(
+    data: &str,
+    encoding: AccountDataEncoding,
+    out: &mut Vec<u8>,
+) -> Result<(), AccountDecodeError> {
+    out.clear();
+    let max_size = max_market_account_size();
+    // Read one byte past the limit to tell data that fills it from data that exceeds it.
+    let limit = max_size as u64 + 1;
+    let base64 = DecoderReader::new(data.as_bytes(), &STANDARD);
+    match encoding {
+        AccountDataEncoding::Base64 => {
+            out.reserve((data.len() / 4 * 3).min(max_size));
+            base64.take(limit).read_to_end(out)?
+        }
+        AccountDataEncoding::Base64Zstd => zstd::stream::read::Decoder::new(base64)?
+            .take(limit)
+            .read_to_end(out)?,
+        AccountDataEncoding::Base64Gzip => flate2::read::GzDecoder::new(base64)
+            .take(limit)
+            .read_to_end(out)?,
+    };
+    if out.len() > max_size {
+        out.clear();
+        return Err(AccountDecodeError::TooLarge(max_size));
+    }
+    Ok(())
+}
+
+/// Decodes encoded account data into a new buffer.
+pub fn decode_account_data(
+    data: &str,
+    encoding: AccountDataEncoding,
+) -> Result<Vec<u8>, AccountDecodeError> {
+    let mut out = vec![];
+    decode_account_data_into(data, encoding, &mut out)?;
+    Ok(out)
+}
+
+impl CachedMarketView {
+    /// Creates a view of encoded market account data, such as the `data` of an RPC response
+    /// requested with `base64+zstd` encoding.
+    pub fn from_encoded(
+        data: &str,
+        encoding: AccountDataEncoding,
+    ) -> Result<Self, AccountDecodeError> {
+        CachedMarketView::new(decode_account_data(data, encoding)?)
+            .ok_or(AccountDecodeError::InvalidMarket)
+    }
+
+    /// Replaces the account data with encoded data and clears the caches. The data is decoded
+    /// into the buffer left over from the previous update, so repeated updates do not
+    /// allocate. The view is left unchanged on error.
+    pub fn update_from_encoded(
+        &mut self,
+        data: &str,
+        encoding: AccountDataEncoding,
+    ) -> Result<(), AccountDecodeError> {
+        decode_account_data_into(data, encoding, self.spare_buffer_mut())?;
+        self.swap_in_spare_buffer()
+            .ok_or(AccountDecodeError::InvalidMarket)
+    }
+}
+
+impl MarketSnapshot {
+    /// Takes a snapshot of encoded market account data observed at `slot`.
+    pub fn from_encoded_account_data(
+        data: &str,
+        encoding: AccountDataEncoding,
+        slot: u64,
+    ) -> Result<Self, AccountDecodeError> {
+        MarketSnapshot::from_account_data(&decode_account_data(data, encoding)?, slot)
+            .ok_or(AccountDecodeError::InvalidMarket)
+    }
+}
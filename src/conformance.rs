@@ -0,0 +1,127 @@
+use crate::dispatch::load_with_dispatch;
+use crate::enums::Side;
+use crate::market::{LadderOrder, MarketHeader, TraderState};
+use crate::snapshot::MarketSnapshot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// A difference between a market loaded through `load_with_dispatch` and the same market read
+/// back from an owned `MarketSnapshot`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConformanceError {
+    #[error("Account data is too short or has an invalid market header")]
+    InvalidHeader,
+    #[error("No market layout for size params {bids_size}/{asks_size}/{num_seats}")]
+    UnsupportedSizeParams {
+        bids_size: u64,
+        asks_size: u64,
+        num_seats: u64,
+    },
+    #[error("Snapshot failed to round trip: {0}")]
+    SnapshotRoundTrip(String),
+    #[error("{side:?} level {level} differs: dispatch {dispatch:?}, snapshot {snapshot:?}")]
+    LadderMismatch {
+        side: Side,
+        level: usize,
+        dispatch: Option<LadderOrder>,
+        snapshot: Option<LadderOrder>,
+    },
+    #[error("Trader {trader} differs: dispatch {dispatch:?}, snapshot {snapshot:?}")]
+    TraderStateMismatch {
+        trader: Pubkey,
+        dispatch: Option<TraderState>,
+        snapshot: Option<TraderState>,
+    },
+    #[error("Market sequence number differs: dispatch {dispatch}, snapshot {snapshot}")]
+    MarketSequenceNumberMismatch { dispatch: u64, snapshot: u64 },
+    #[error("Order sequence number differs: dispatch {dispatch}, snapshot {snapshot}")]
+    OrderSequenceNumberMismatch { dispatch: u64, snapshot: u64 },
+}
+
+/// Loads `data` both through `load_with_dispatch` and through a `MarketSnapshot` that has been
+/// written out and read back, and checks that both paths agree on the full ladder, every
+/// trader's state, and the sequence numbers.
+pub fn check_conformance(data: &[u8]) -> Result<(), Vec<ConformanceError>> {
+    let header_size = std::mem::size_of::<MarketHeader>();
+    if data.len() < header_size {
+        return Err(vec![ConformanceError::InvalidHeader]);
+    }
+    let (header_bytes, market_bytes) = data.split_at(header_size);
+    let header = bytemuck::try_from_bytes::<MarketHeader>(header_bytes)
+        .map_err(|_| vec![ConformanceError::InvalidHeader])?;
+    let market = load_with_dispatch(&header.market_size_params, market_bytes)
+        .ok_or_else(|| {
+            vec![ConformanceError::UnsupportedSizeParams {
+                bids_size: header.market_size_params.bids_size,
+                asks_size: header.market_size_params.asks_size,
+                num_seats: header.market_size_params.num_seats,
+            }]
+        })?
+        .inner;
+
+    let mut bytes = vec![];
+    let snapshot = MarketSnapshot::from_market(header, market, 0)
+        .write_to(&mut bytes)
+        .and_then(|_| MarketSnapshot::read_from(&mut bytes.as_slice()))
+        .map_err(|e| vec![ConformanceError::SnapshotRoundTrip(e.to_string())])?;
+
+    let mut errors = vec![];
+
+    if header.market_sequence_number != snapshot.header.market_sequence_number {
+        errors.push(ConformanceError::MarketSequenceNumberMismatch {
+            dispatch: header.market_sequence_number,
+            snapshot: snapshot.header.market_sequence_number,
+        });
+    }
+    if market.get_order_sequence_number() != snapshot.order_sequence_number {
+        errors.push(ConformanceError::OrderSequenceNumberMismatch {
+            dispatch: market.get_order_sequence_number(),
+            snapshot: snapshot.order_sequence_number,
+        });
+    }
+
+    let dispatch_ladder = market.get_ladder(u64::MAX);
+    let snapshot_ladder = snapshot.get_ladder(u64::MAX);
+    for (side, dispatch_levels, snapshot_levels) in [
+        (Side::Bid, &dispatch_ladder.bids, &snapshot_ladder.bids),
+        (Side::Ask, &dispatch_ladder.asks, &snapshot_ladder.asks),
+    ] {
+        for level in 0..dispatch_levels.len().max(snapshot_levels.len()) {
+            let dispatch = dispatch_levels.get(level).copied();
+            let snapshot = snapshot_levels.get(level).copied();
+            if dispatch != snapshot {
+                errors.push(ConformanceError::LadderMismatch {
+                    side,
+                    level,
+                    dispatch,
+                    snapshot,
+                });
+            }
+        }
+    }
+
+    let traders: BTreeSet<Pubkey> = market
+        .get_registered_traders()
+        .iter()
+        .map(|(trader, _)| *trader)
+        .chain(snapshot.traders.iter().map(|t| t.trader))
+        .collect();
+    for trader in traders {
+        let dispatch = market.get_trader_state(&trader).copied();
+        let snapshot = snapshot.get_trader_state(&trader).copied();
+        if dispatch != snapshot {
+            errors.push(ConformanceError::TraderStateMismatch {
+                trader,
+                dispatch,
+                snapshot,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,121 @@
+//! Finds Phoenix instructions anywhere in a transaction's instruction tree, including those
+//! invoked through CPI by routers and aggregators.
+
+use crate::events::{parse_log_instruction_data, AuditLogHeader, MarketEvent};
+use crate::instructions::{DecodedPhoenixInstruction, PhoenixInstruction};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+/// An inner instruction of a transaction with its depth in the invocation tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerInstruction {
+    pub instruction: Instruction,
+    /// The `stackHeight` reported by RPC: 1 for top level instructions, 2 for instructions they
+    /// invoke, and so on. Older nodes do not report it, in which case every inner instruction
+    /// is assumed to be invoked by the top level instruction.
+    pub stack_height: Option<u32>,
+}
+
+/// A Phoenix instruction found in a transaction, with the program that invoked it.
+#[derive(Debug, Clone)]
+pub struct PhoenixInvocation {
+    /// The index of the top level instruction the invocation belongs to.
+    pub top_level_index: usize,
+    /// The index of the invocation in the inner instructions of the top level instruction, or
+    /// None if it is the top level instruction itself.
+    pub inner_index: Option<usize>,
+    pub stack_height: u32,
+    /// The program that invoked Phoenix, or None for top level instructions.
+    pub invoking_program: Option<Pubkey>,
+    pub instruction: Instruction,
+    pub decoded: DecodedPhoenixInstruction,
+    /// The header of the first log emitted by the invocation.
+    pub header: Option<AuditLogHeader>,
+    pub events: Vec<MarketEvent>,
+}
+
+impl PhoenixInvocation {
+    pub fn is_cpi(&self) -> bool {
+        self.invoking_program.is_some()
+    }
+}
+
+/// Walks the instruction tree of a transaction and returns every Phoenix instruction, top level
+/// or invoked at any depth, along with the events it logged. Phoenix's own log instructions are
+/// attributed to the instruction that emitted them rather than returned.
+///
+/// `inner_instructions[i]` must hold the inner instructions invoked by `instructions[i]`, in
+/// execution order.
+pub fn find_phoenix_invocations(
+    instructions: &[Instruction],
+    inner_instructions: &[Vec<InnerInstruction>],
+) -> Vec<PhoenixInvocation> {
+    let mut invocations = vec![];
+    for (top_level_index, instruction) in instructions.iter().enumerate() {
+        let inner = inner_instructions
+            .get(top_level_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        // The program executing at each stack height, starting with the top level program.
+        let mut stack = vec![instruction.program_id];
+        // The index in `invocations` of the Phoenix instruction each stack height belongs to.
+        let mut phoenix_stack: Vec<Option<usize>> = vec![None];
+        if let Some(decoded) = decode_phoenix(instruction) {
+            phoenix_stack[0] = Some(invocations.len());
+            invocations.push(PhoenixInvocation {
+                top_level_index,
+                inner_index: None,
+                stack_height: 1,
+                invoking_program: None,
+                instruction: instruction.clone(),
+                decoded,
+                header: None,
+                events: vec![],
+            });
+        }
+
+        for (inner_index, inner_instruction) in inner.iter().enumerate() {
+            let stack_height = inner_instruction.stack_height.unwrap_or(2).max(2);
+            let depth = stack_height as usize - 1;
+            stack.truncate(depth);
+            phoenix_stack.truncate(depth);
+            let invoking_program = stack.last().copied();
+            let invoker = phoenix_stack.last().copied().flatten();
+            let instruction = &inner_instruction.instruction;
+
+            let mut current = None;
+            if instruction.program_id == crate::id() {
+                if let Some((header, events)) = parse_log_instruction_data(&instruction.data) {
+                    if let Some(invocation) = invoker.map(|index| &mut invocations[index]) {
+                        invocation.header.get_or_insert(header);
+                        invocation.events.extend(events);
+                    }
+                } else if let Some(decoded) = decode_phoenix(instruction) {
+                    current = Some(invocations.len());
+                    invocations.push(PhoenixInvocation {
+                        top_level_index,
+                        inner_index: Some(inner_index),
+                        stack_height,
+                        invoking_program,
+                        instruction: instruction.clone(),
+                        decoded,
+                        header: None,
+                        events: vec![],
+                    });
+                }
+            }
+            stack.push(instruction.program_id);
+            phoenix_stack.push(current);
+        }
+    }
+    invocations
+}
+
+/// Decodes a Phoenix instruction other than a log.
+fn decode_phoenix(instruction: &Instruction) -> Option<DecodedPhoenixInstruction> {
+    if instruction.program_id != crate::id() {
+        return None;
+    }
+    DecodedPhoenixInstruction::decode(&instruction.data)
+        .filter(|decoded| decoded.instruction() != PhoenixInstruction::Log)
+}
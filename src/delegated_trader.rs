@@ -0,0 +1,229 @@
+use crate::enums::Side;
+use crate::instructions::{
+    create_cancel_all_orders_instruction_with_custom_token_accounts,
+    create_cancel_multiple_orders_by_id_instruction_with_custom_token_accounts,
+    create_cancel_up_to_instruction_with_custom_token_accounts,
+    create_deposit_funds_instruction_with_custom_token_accounts,
+    create_new_multiple_order_instruction_with_custom_token_accounts,
+    create_new_order_instruction_with_custom_token_accounts,
+    create_reduce_order_instruction_with_custom_token_accounts, create_request_seat_instruction,
+    create_swap_instruction_with_min_out_with_custom_token_accounts,
+    create_withdraw_funds_with_custom_amounts_instruction_with_custom_token_accounts,
+    get_seat_address, with_trader_signer, CancelMultipleOrdersByIdParams, CancelUpToParams,
+    DepositParams, MultipleOrderPacket, ReduceOrderParams, SwapError, WithdrawParams,
+};
+use crate::market::MarketHeader;
+use crate::order_packet::OrderPacket;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+
+/// A trader account controlled by a program, such as a vault PDA trading on behalf of its
+/// users, with the token accounts it settles through.
+///
+/// The trader is marked as a signer by default, as needed for `invoke_signed`. Use
+/// `signer(false)` for instructions built off chain and forwarded to the controlling program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelegatedTrader {
+    pub trader: Pubkey,
+    pub base_account: Pubkey,
+    pub quote_account: Pubkey,
+    pub is_signer: bool,
+}
+
+impl DelegatedTrader {
+    pub fn new(trader: Pubkey, base_account: Pubkey, quote_account: Pubkey) -> Self {
+        Self {
+            trader,
+            base_account,
+            quote_account,
+            is_signer: true,
+        }
+    }
+
+    /// Settles through the trader's associated token accounts for the market's mints.
+    pub fn with_associated_token_accounts(trader: Pubkey, header: &MarketHeader) -> Self {
+        Self::new(
+            trader,
+            get_associated_token_address(&trader, &header.base_params.mint_key),
+            get_associated_token_address(&trader, &header.quote_params.mint_key),
+        )
+    }
+
+    pub fn signer(self, is_signer: bool) -> Self {
+        Self { is_signer, ..self }
+    }
+
+    pub fn seat(&self, market: &Pubkey) -> Pubkey {
+        get_seat_address(market, &self.trader).0
+    }
+
+    /// Applies the trader's signer flag to an instruction built for it.
+    pub fn apply(&self, instruction: Instruction) -> Instruction {
+        with_trader_signer(instruction, &self.trader, self.is_signer)
+    }
+
+    pub fn request_seat(&self, market: &Pubkey) -> Instruction {
+        self.apply(create_request_seat_instruction(&self.trader, market))
+    }
+
+    pub fn new_order(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        order_packet: &OrderPacket,
+    ) -> Instruction {
+        self.apply(create_new_order_instruction_with_custom_token_accounts(
+            market,
+            &self.trader,
+            &self.base_account,
+            &self.quote_account,
+            &header.base_params.mint_key,
+            &header.quote_params.mint_key,
+            order_packet,
+        ))
+    }
+
+    pub fn new_multiple_order(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        multiple_order_packet: &MultipleOrderPacket,
+    ) -> Instruction {
+        self.apply(
+            create_new_multiple_order_instruction_with_custom_token_accounts(
+                market,
+                &self.trader,
+                &self.base_account,
+                &self.quote_account,
+                &header.base_params.mint_key,
+                &header.quote_params.mint_key,
+                multiple_order_packet,
+            ),
+        )
+    }
+
+    pub fn swap_with_min_out(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        side: Side,
+        in_amount_atoms: u64,
+        min_out_atoms: u64,
+    ) -> Result<Instruction, SwapError> {
+        create_swap_instruction_with_min_out_with_custom_token_accounts(
+            market,
+            &self.trader,
+            &self.base_account,
+            &self.quote_account,
+            side,
+            in_amount_atoms,
+            min_out_atoms,
+            header,
+        )
+        .map(|instruction| self.apply(instruction))
+    }
+
+    pub fn reduce_order(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        params: &ReduceOrderParams,
+    ) -> Instruction {
+        self.apply(create_reduce_order_instruction_with_custom_token_accounts(
+            market,
+            &self.trader,
+            &self.base_account,
+            &self.quote_account,
+            &header.base_params.mint_key,
+            &header.quote_params.mint_key,
+            params,
+        ))
+    }
+
+    pub fn cancel_all_orders(&self, market: &Pubkey, header: &MarketHeader) -> Instruction {
+        self.apply(
+            create_cancel_all_orders_instruction_with_custom_token_accounts(
+                market,
+                &self.trader,
+                &self.base_account,
+                &self.quote_account,
+                &header.base_params.mint_key,
+                &header.quote_params.mint_key,
+            ),
+        )
+    }
+
+    pub fn cancel_up_to(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        params: &CancelUpToParams,
+    ) -> Instruction {
+        self.apply(create_cancel_up_to_instruction_with_custom_token_accounts(
+            market,
+            &self.trader,
+            &self.base_account,
+            &self.quote_account,
+            &header.base_params.mint_key,
+            &header.quote_params.mint_key,
+            params,
+        ))
+    }
+
+    pub fn cancel_multiple_orders_by_id(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        params: &CancelMultipleOrdersByIdParams,
+    ) -> Instruction {
+        self.apply(
+            create_cancel_multiple_orders_by_id_instruction_with_custom_token_accounts(
+                market,
+                &self.trader,
+                &self.base_account,
+                &self.quote_account,
+                &header.base_params.mint_key,
+                &header.quote_params.mint_key,
+                params,
+            ),
+        )
+    }
+
+    pub fn deposit_funds(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        params: &DepositParams,
+    ) -> Instruction {
+        self.apply(create_deposit_funds_instruction_with_custom_token_accounts(
+            market,
+            &self.trader,
+            &self.seat(market),
+            &self.base_account,
+            &self.quote_account,
+            &header.base_params.mint_key,
+            &header.quote_params.mint_key,
+            params,
+        ))
+    }
+
+    pub fn withdraw_funds(
+        &self,
+        market: &Pubkey,
+        header: &MarketHeader,
+        params: &WithdrawParams,
+    ) -> Instruction {
+        self.apply(
+            create_withdraw_funds_with_custom_amounts_instruction_with_custom_token_accounts(
+                market,
+                &self.trader,
+                &self.base_account,
+                &self.quote_account,
+                &header.base_params.mint_key,
+                &header.quote_params.mint_key,
+                params,
+            ),
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use crate::integrity::{check_market_integrity, MarketIntegrityError};
 use crate::market::{FIFOMarket, Market, MarketSizeParams};
 use sokoban::node_allocator::ZeroCopy;
 
@@ -10,6 +11,11 @@ impl<'a> MarketWrapperMut<'a> {
     pub fn new(market: &'a mut dyn Market) -> Self {
         Self { inner: market }
     }
+
+    /// Returns the concrete market if it is a `T`, e.g. `FIFOMarket<4096, 4096, 8192>`.
+    pub fn downcast_mut<T: Market + 'static>(&mut self) -> Option<&mut T> {
+        self.inner.downcast_mut::<T>()
+    }
 }
 
 /// Loads a market from a given buffer and known market params.
@@ -20,37 +26,14 @@ pub fn load_with_dispatch_mut<'a>(
     dispatch_market_mut(market_size_params, bytes)
 }
 
-fn dispatch_market_mut<'a>(
+/// Loads a market like `load_with_dispatch_mut`, after checking that its book trees are
+/// consistent with `check_market_integrity`, so that corrupted data is never mutated further.
+pub fn load_with_dispatch_mut_checked<'a>(
     market_size_params: &'a MarketSizeParams,
     bytes: &'a mut [u8],
-) -> Option<MarketWrapperMut<'a>> {
-    let market = match (
-        market_size_params.bids_size,
-        market_size_params.asks_size,
-        market_size_params.num_seats,
-    ) {
-        (512, 512, 256) => FIFOMarket::<512, 512, 256>::load_mut_bytes(bytes)? as &mut dyn Market,
-        (2048, 2048, 4096) => {
-            FIFOMarket::<2048, 2048, 4096>::load_mut_bytes(bytes)? as &mut dyn Market
-        }
-        (4096, 4096, 8192) => {
-            FIFOMarket::<4096, 4096, 8192>::load_mut_bytes(bytes)? as &mut dyn Market
-        }
-        (1024, 1024, 128) => {
-            FIFOMarket::<1024, 1024, 128>::load_mut_bytes(bytes)? as &mut dyn Market
-        }
-        (2048, 2048, 128) => {
-            FIFOMarket::<2048, 2048, 128>::load_mut_bytes(bytes)? as &mut dyn Market
-        }
-        (4096, 4096, 128) => {
-            FIFOMarket::<4096, 4096, 128>::load_mut_bytes(bytes)? as &mut dyn Market
-        }
-        _ => {
-            println!("Invalid parameters for market");
-            return None;
-        }
-    };
-    Some(MarketWrapperMut::new(market))
+) -> Result<MarketWrapperMut<'a>, MarketIntegrityError> {
+    check_market_integrity(market_size_params, bytes)?;
+    dispatch_market_mut(market_size_params, bytes).ok_or(MarketIntegrityError::InvalidLayout)
 }
 
 /// Struct that holds an object implementing the Market trait.
@@ -62,6 +45,11 @@ impl<'a> MarketWrapper<'a> {
     pub fn new(market: &'a dyn Market) -> Self {
         Self { inner: market }
     }
+
+    /// Returns the concrete market if it is a `T`, e.g. `FIFOMarket<4096, 4096, 8192>`.
+    pub fn downcast_ref<T: Market + 'static>(&self) -> Option<&'a T> {
+        self.inner.downcast_ref::<T>()
+    }
 }
 
 /// Loads a market from a given buffer and known market params.
@@ -72,43 +60,92 @@ pub fn load_with_dispatch<'a>(
     dispatch_market(market_size_params, bytes)
 }
 
-fn dispatch_market<'a>(
-    market_size_params: &'a MarketSizeParams,
-    bytes: &'a [u8],
-) -> Option<MarketWrapper<'a>> {
-    let market = match (
-        market_size_params.bids_size,
-        market_size_params.asks_size,
-        market_size_params.num_seats,
-    ) {
-        (512, 512, 256) => FIFOMarket::<512, 512, 256>::load_bytes(bytes)? as &dyn Market,
-        (2048, 2048, 4096) => FIFOMarket::<2048, 2048, 4096>::load_bytes(bytes)? as &dyn Market,
-        (4096, 4096, 8192) => FIFOMarket::<4096, 4096, 8192>::load_bytes(bytes)? as &dyn Market,
-        (1024, 1024, 128) => FIFOMarket::<1024, 1024, 128>::load_bytes(bytes)? as &dyn Market,
-        (2048, 2048, 128) => FIFOMarket::<2048, 2048, 128>::load_bytes(bytes)? as &dyn Market,
-        (4096, 4096, 128) => FIFOMarket::<4096, 4096, 128>::load_bytes(bytes)? as &dyn Market,
-        _ => {
-            println!("Invalid parameters for market");
-            return None;
+/// Generates the dispatch functions and the table of supported sizes from a single list of
+/// `(bids_size, asks_size, num_seats)`, so that they cannot disagree.
+macro_rules! market_sizes {
+    ($(($bids:literal, $asks:literal, $seats:literal)),* $(,)?) => {
+        /// Every market size that can be loaded, in the order they are matched.
+        pub const SUPPORTED_MARKET_SIZES: &[MarketSizeParams] = &[$(MarketSizeParams {
+            bids_size: $bids,
+            asks_size: $asks,
+            num_seats: $seats,
+        }),*];
+
+        fn dispatch_market_mut<'a>(
+            market_size_params: &'a MarketSizeParams,
+            bytes: &'a mut [u8],
+        ) -> Option<MarketWrapperMut<'a>> {
+            let market = match (
+                market_size_params.bids_size,
+                market_size_params.asks_size,
+                market_size_params.num_seats,
+            ) {
+                $(($bids, $asks, $seats) => {
+                    FIFOMarket::<$bids, $asks, $seats>::load_mut_bytes(bytes)? as &mut dyn Market
+                })*
+                _ => {
+                    println!("Invalid parameters for market");
+                    return None;
+                }
+            };
+            Some(MarketWrapperMut::new(market))
+        }
+
+        fn dispatch_market<'a>(
+            market_size_params: &'a MarketSizeParams,
+            bytes: &'a [u8],
+        ) -> Option<MarketWrapper<'a>> {
+            let market = match (
+                market_size_params.bids_size,
+                market_size_params.asks_size,
+                market_size_params.num_seats,
+            ) {
+                $(($bids, $asks, $seats) => {
+                    FIFOMarket::<$bids, $asks, $seats>::load_bytes(bytes)? as &dyn Market
+                })*
+                _ => {
+                    println!("Invalid parameters for market");
+                    return None;
+                }
+            };
+            Some(MarketWrapper::new(market))
+        }
+
+        /// Returns the size of a market in bytes, given the market params.
+        pub fn get_market_size(market_params: &MarketSizeParams) -> Option<usize> {
+            let size = match (
+                market_params.bids_size,
+                market_params.asks_size,
+                market_params.num_seats,
+            ) {
+                $(($bids, $asks, $seats) => std::mem::size_of::<FIFOMarket<$bids, $asks, $seats>>(),)*
+                _ => return None,
+            };
+            Some(size)
         }
     };
-    Some(MarketWrapper::new(market))
 }
 
-/// Returns the size of a market in bytes, given the market params.
-pub fn get_market_size(market_params: &MarketSizeParams) -> Option<usize> {
-    let size = match (
-        market_params.bids_size,
-        market_params.asks_size,
-        market_params.num_seats,
-    ) {
-        (512, 512, 256) => std::mem::size_of::<FIFOMarket<512, 512, 256>>(),
-        (2048, 2048, 4096) => std::mem::size_of::<FIFOMarket<2048, 2048, 4096>>(),
-        (4096, 4096, 8192) => std::mem::size_of::<FIFOMarket<4096, 4096, 8192>>(),
-        (1024, 1024, 128) => std::mem::size_of::<FIFOMarket<1024, 1024, 128>>(),
-        (2048, 2048, 128) => std::mem::size_of::<FIFOMarket<2048, 2048, 128>>(),
-        (4096, 4096, 128) => std::mem::size_of::<FIFOMarket<4096, 4096, 128>>(),
-        _ => return None,
-    };
-    Some(size)
+market_sizes!(
+    (512, 512, 256),
+    (2048, 2048, 4096),
+    (4096, 4096, 8192),
+    (1024, 1024, 128),
+    (2048, 2048, 128),
+    (4096, 4096, 128),
+);
+
+/// Returns true if markets of this size can be loaded.
+pub fn is_supported(market_size_params: &MarketSizeParams) -> bool {
+    SUPPORTED_MARKET_SIZES.contains(market_size_params)
+}
+
+/// Iterates over the supported market sizes with the size of their accounts in bytes.
+pub fn supported_market_sizes() -> impl Iterator<Item = (MarketSizeParams, usize)> {
+    SUPPORTED_MARKET_SIZES.iter().map(|params| {
+        (
+            *params,
+            get_market_size(params).expect("every supported market size has a layout"),
+        )
+    })
 }
@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "pyo3")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Options for an order's self trade behavior.
 #[cfg_attr(feature = "pyo3", pyclass)]
@@ -19,7 +20,9 @@ pub enum SelfTradeBehavior {
 
 /// Options for an order's side.
 #[cfg_attr(feature = "pyo3", pyclass)]
-#[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug,
+)]
 pub enum Side {
     Bid,
     Ask,
@@ -33,6 +36,44 @@ impl Side {
             _ => Side::Ask,
         }
     }
+
+    /// Returns true if price `a` is strictly more aggressive than price `b` for this side.
+    /// Higher prices are more aggressive for bids, lower prices are more aggressive for asks.
+    pub fn is_price_more_aggressive(&self, a_ticks: u64, b_ticks: u64) -> bool {
+        match self {
+            Side::Bid => a_ticks > b_ticks,
+            Side::Ask => a_ticks < b_ticks,
+        }
+    }
+
+    /// Returns true if price `a` is at least as aggressive as price `b` for this side.
+    pub fn is_price_at_least_as_aggressive(&self, a_ticks: u64, b_ticks: u64) -> bool {
+        a_ticks == b_ticks || self.is_price_more_aggressive(a_ticks, b_ticks)
+    }
+
+    /// Returns the more aggressive of two prices for this side.
+    pub fn more_aggressive_price(&self, a_ticks: u64, b_ticks: u64) -> u64 {
+        if self.is_price_more_aggressive(a_ticks, b_ticks) {
+            a_ticks
+        } else {
+            b_ticks
+        }
+    }
+
+    /// Returns the less aggressive of two prices for this side.
+    pub fn less_aggressive_price(&self, a_ticks: u64, b_ticks: u64) -> u64 {
+        if self.is_price_more_aggressive(a_ticks, b_ticks) {
+            b_ticks
+        } else {
+            a_ticks
+        }
+    }
+
+    /// Returns true if an order on this side at `price_in_ticks` would match a resting order on
+    /// the opposite side at `resting_price_in_ticks`.
+    pub fn crosses(&self, price_in_ticks: u64, resting_price_in_ticks: u64) -> bool {
+        self.is_price_at_least_as_aggressive(price_in_ticks, resting_price_in_ticks)
+    }
 }
 
 #[cfg_attr(feature = "pyo3", pymethods)]
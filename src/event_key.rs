@@ -0,0 +1,82 @@
+use crate::events::{AuditLogHeader, MarketEvent, PhoenixTransactionEvents};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+/// Uniquely identifies an event across all Phoenix markets, for deduplicating events that are
+/// stored or processed more than once.
+///
+/// Every instruction that modifies a market takes one market sequence number, and the events of
+/// the instruction are indexed from 0. Keys order by market, then by the order the events were
+/// emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EventKey {
+    #[serde(with = "crate::pubkey_serde")]
+    pub market: Pubkey,
+    pub market_sequence_number: u64,
+    pub event_index: u16,
+}
+
+impl EventKey {
+    pub fn new(market: Pubkey, market_sequence_number: u64, event_index: u16) -> Self {
+        Self {
+            market,
+            market_sequence_number,
+            event_index,
+        }
+    }
+
+    /// Returns the key of an event logged under `header`, or None if the event has no index.
+    pub fn from_event(header: &AuditLogHeader, event: &MarketEvent) -> Option<Self> {
+        Some(Self::new(
+            header.market,
+            header.market_sequence_number,
+            event.index()?,
+        ))
+    }
+}
+
+/// Returns the events of a single log with their keys, skipping events without an index.
+pub fn keyed_events<'a>(
+    header: &'a AuditLogHeader,
+    events: &'a [MarketEvent],
+) -> impl Iterator<Item = (EventKey, &'a MarketEvent)> + 'a {
+    events
+        .iter()
+        .filter_map(move |event| Some((EventKey::from_event(header, event)?, event)))
+}
+
+impl PhoenixTransactionEvents {
+    /// Returns the events of the instruction with their keys.
+    pub fn keyed_events(&self) -> impl Iterator<Item = (EventKey, &MarketEvent)> {
+        keyed_events(&self.header, &self.events)
+    }
+}
+
+/// An event with its key and the transaction that emitted it.
+#[derive(Debug, Clone, Copy)]
+pub struct AttributedEvent {
+    pub key: EventKey,
+    pub signature: Signature,
+    /// The index of the top level instruction that emitted the event.
+    pub instruction_index: usize,
+    pub event: MarketEvent,
+}
+
+/// Attributes the events of the batches parsed from the transaction with `signature`.
+pub fn attribute_events(
+    signature: &Signature,
+    batches: &[PhoenixTransactionEvents],
+) -> Vec<AttributedEvent> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            batch.keyed_events().map(|(key, event)| AttributedEvent {
+                key,
+                signature: *signature,
+                instruction_index: batch.instruction_index,
+                event: *event,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,321 @@
+use crate::events::{AuditLogHeader, PhoenixTransactionEvents};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+impl PhoenixTransactionEvents {
+    /// Returns the key used to order event batches: (slot, market sequence number, market).
+    ///
+    /// The market is only used to break ties deterministically between different markets that
+    /// share a slot and sequence number.
+    pub fn ordering_key(&self) -> (u64, u64, Pubkey) {
+        (
+            self.header.slot,
+            self.header.market_sequence_number,
+            self.header.market,
+        )
+    }
+}
+
+/// Sorts event batches in place by (slot, market sequence number).
+pub fn sort_event_batches(batches: &mut [PhoenixTransactionEvents]) {
+    batches.sort_by_key(|batch| batch.ordering_key());
+}
+
+/// Merges event batches collected from several sources (e.g. separate RPC backfills) into a
+/// single deterministically ordered stream.
+pub fn merge_event_batches<I>(sources: I) -> Vec<PhoenixTransactionEvents>
+where
+    I: IntoIterator<Item = Vec<PhoenixTransactionEvents>>,
+{
+    let mut merged = sources.into_iter().flatten().collect::<Vec<_>>();
+    sort_event_batches(&mut merged);
+    merged
+}
+
+/// An inconsistency found in an ordered stream of event batches for a single market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderingViolation {
+    /// The same market sequence number appeared more than once.
+    DuplicateSequenceNumber {
+        market: Pubkey,
+        market_sequence_number: u64,
+    },
+
+    /// A batch had a lower market sequence number than the batch before it.
+    SequenceNumberDecreased {
+        market: Pubkey,
+        previous_sequence_number: u64,
+        market_sequence_number: u64,
+    },
+
+    /// A batch had a higher market sequence number but an earlier slot than the batch before it.
+    SlotDecreased {
+        market: Pubkey,
+        previous_slot: u64,
+        slot: u64,
+        market_sequence_number: u64,
+    },
+}
+
+/// Checks a stream of event batches, in the order given, for ordering violations within each
+/// market. An empty result means the stream is consistently ordered.
+pub fn find_ordering_violations(batches: &[PhoenixTransactionEvents]) -> Vec<OrderingViolation> {
+    let mut last_seen: HashMap<Pubkey, (u64, u64)> = HashMap::new();
+    let mut violations = vec![];
+    for batch in batches.iter() {
+        let market = batch.header.market;
+        let slot = batch.header.slot;
+        let market_sequence_number = batch.header.market_sequence_number;
+        if let Some(&(previous_slot, previous_sequence_number)) = last_seen.get(&market) {
+            if market_sequence_number == previous_sequence_number {
+                violations.push(OrderingViolation::DuplicateSequenceNumber {
+                    market,
+                    market_sequence_number,
+                });
+                continue;
+            }
+            if market_sequence_number < previous_sequence_number {
+                violations.push(OrderingViolation::SequenceNumberDecreased {
+                    market,
+                    previous_sequence_number,
+                    market_sequence_number,
+                });
+                continue;
+            }
+            if slot < previous_slot {
+                violations.push(OrderingViolation::SlotDecreased {
+                    market,
+                    previous_slot,
+                    slot,
+                    market_sequence_number,
+                });
+            }
+        }
+        last_seen.insert(market, (slot, market_sequence_number));
+    }
+    violations
+}
+
+/// The result of feeding a market sequence number into a `SequenceTracker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceObservation {
+    /// The sequence number had not been seen before and should be processed.
+    Accepted,
+
+    /// The sequence number had not been seen before, but it skipped over the given range of
+    /// sequence numbers, which have not been received yet.
+    AcceptedWithGap { missing: RangeInclusive<u64> },
+
+    /// The sequence number was already seen (or precedes the first one tracked) and should be
+    /// dropped.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MarketSequenceState {
+    /// Every sequence number below this value has been received.
+    next_expected: u64,
+
+    /// Sequence numbers received ahead of `next_expected`.
+    received_ahead: BTreeSet<u64>,
+}
+
+impl MarketSequenceState {
+    fn new(first_sequence_number: u64) -> Self {
+        Self {
+            next_expected: first_sequence_number.saturating_add(1),
+            received_ahead: BTreeSet::new(),
+        }
+    }
+
+    fn observe(&mut self, market_sequence_number: u64) -> SequenceObservation {
+        if market_sequence_number < self.next_expected
+            || self.received_ahead.contains(&market_sequence_number)
+        {
+            return SequenceObservation::Duplicate;
+        }
+        if market_sequence_number == self.next_expected {
+            self.next_expected += 1;
+            while self.received_ahead.remove(&self.next_expected) {
+                self.next_expected += 1;
+            }
+            return SequenceObservation::Accepted;
+        }
+        let gap_start = self
+            .received_ahead
+            .range(..market_sequence_number)
+            .next_back()
+            .map(|seq| seq + 1)
+            .unwrap_or(self.next_expected);
+        self.received_ahead.insert(market_sequence_number);
+        if gap_start == market_sequence_number {
+            SequenceObservation::Accepted
+        } else {
+            SequenceObservation::AcceptedWithGap {
+                missing: gap_start..=market_sequence_number - 1,
+            }
+        }
+    }
+
+    fn missing_ranges(&self) -> Vec<RangeInclusive<u64>> {
+        let mut ranges = vec![];
+        let mut start = self.next_expected;
+        for &seq in self.received_ahead.iter() {
+            if seq > start {
+                ranges.push(start..=seq - 1);
+            }
+            start = seq + 1;
+        }
+        ranges
+    }
+}
+
+/// Tracks the market sequence numbers received for each market in order to detect gaps and
+/// duplicates in an event stream.
+///
+/// The first sequence number observed for a market is the starting point for that market.
+/// Anything below it is reported as a duplicate.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    markets: HashMap<Pubkey, MarketSequenceState>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a market from a known sequence number, e.g. the `market_sequence_number`
+    /// of a freshly loaded market header. The next expected sequence number is `last_seen + 1`.
+    pub fn start_from(&mut self, market: Pubkey, last_seen: u64) {
+        self.markets
+            .insert(market, MarketSequenceState::new(last_seen));
+    }
+
+    /// Records a sequence number for a market.
+    pub fn observe(&mut self, market: Pubkey, market_sequence_number: u64) -> SequenceObservation {
+        match self.markets.get_mut(&market) {
+            Some(state) => state.observe(market_sequence_number),
+            None => {
+                self.markets
+                    .insert(market, MarketSequenceState::new(market_sequence_number));
+                SequenceObservation::Accepted
+            }
+        }
+    }
+
+    /// Records the sequence number of a log header.
+    pub fn observe_header(&mut self, header: &AuditLogHeader) -> SequenceObservation {
+        self.observe(header.market, header.market_sequence_number)
+    }
+
+    /// Records the sequence number of an event batch.
+    pub fn observe_batch(&mut self, batch: &PhoenixTransactionEvents) -> SequenceObservation {
+        self.observe_header(&batch.header)
+    }
+
+    /// Returns the ranges of sequence numbers that are still missing for a market, in ascending
+    /// order.
+    pub fn missing_ranges(&self, market: &Pubkey) -> Vec<RangeInclusive<u64>> {
+        self.markets
+            .get(market)
+            .map(|state| state.missing_ranges())
+            .unwrap_or_default()
+    }
+
+    /// Returns the next sequence number expected for a market, if the market is tracked.
+    pub fn next_expected(&self, market: &Pubkey) -> Option<u64> {
+        self.markets.get(market).map(|state| state.next_expected)
+    }
+}
+
+/// Splits mixed event batches by market, each market's batches sorted by (slot, market sequence
+/// number).
+pub fn partition_by_market(
+    batches: impl IntoIterator<Item = PhoenixTransactionEvents>,
+) -> HashMap<Pubkey, Vec<PhoenixTransactionEvents>> {
+    let mut partitioned: HashMap<Pubkey, Vec<PhoenixTransactionEvents>> = HashMap::new();
+    for batch in batches {
+        partitioned
+            .entry(batch.header.market)
+            .or_default()
+            .push(batch);
+    }
+    for market_batches in partitioned.values_mut() {
+        sort_event_batches(market_batches);
+    }
+    partitioned
+}
+
+/// The outcome of routing a set of batches with `EventDemux::route`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DemuxStats {
+    /// Batches sent to a subscriber.
+    pub routed: usize,
+    /// Batches dropped because their market sequence number was already delivered.
+    pub duplicates: usize,
+    /// Batches dropped because no subscriber was listening to their market.
+    pub unrouted: usize,
+}
+
+/// Routes event batches from a program-wide log subscription to one channel per market.
+///
+/// Each channel receives its market's batches in market sequence number order: every call to
+/// `route` sorts the batches it is given, and batches at or below the last sequence number
+/// delivered to a market are dropped as duplicates. Subscribers whose receiver was dropped are
+/// removed.
+#[derive(Debug, Default)]
+pub struct EventDemux {
+    subscribers: HashMap<Pubkey, (Sender<PhoenixTransactionEvents>, Option<u64>)>,
+}
+
+impl EventDemux {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a receiver for the batches of `market`, replacing any previous subscriber.
+    pub fn subscribe(&mut self, market: Pubkey) -> Receiver<PhoenixTransactionEvents> {
+        let (sender, receiver) = channel();
+        self.subscribers.insert(market, (sender, None));
+        receiver
+    }
+
+    pub fn unsubscribe(&mut self, market: &Pubkey) {
+        self.subscribers.remove(market);
+    }
+
+    pub fn is_subscribed(&self, market: &Pubkey) -> bool {
+        self.subscribers.contains_key(market)
+    }
+
+    /// Sends each batch to the subscriber of its market.
+    pub fn route(
+        &mut self,
+        batches: impl IntoIterator<Item = PhoenixTransactionEvents>,
+    ) -> DemuxStats {
+        let mut stats = DemuxStats::default();
+        for (market, market_batches) in partition_by_market(batches) {
+            let Some((sender, last_delivered)) = self.subscribers.get_mut(&market) else {
+                stats.unrouted += market_batches.len();
+                continue;
+            };
+            for batch in market_batches {
+                let sequence_number = batch.header.market_sequence_number;
+                if last_delivered.is_some_and(|last| sequence_number <= last) {
+                    stats.duplicates += 1;
+                    continue;
+                }
+                if sender.send(batch).is_err() {
+                    self.subscribers.remove(&market);
+                    break;
+                }
+                *last_delivered = Some(sequence_number);
+                stats.routed += 1;
+            }
+        }
+        stats
+    }
+}
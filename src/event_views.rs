@@ -0,0 +1,157 @@
+//! Zero-copy views over the fixed-size events in a `Log` instruction.
+//!
+//! Borsh lays out each `MarketEvent` as a one byte variant tag followed by its fields with no
+//! padding, so the `Fill`, `Place` and `Reduce` variants can be read in place by casting the
+//! bytes after the tag to a packed struct. Every cast is length checked, and any event that
+//! cannot be viewed in place is decoded with Borsh instead.
+//!
+//! The views borrow from the log data rather than copying it out. No speedup over
+//! `parse_log_instruction_data` is claimed: they have not been benchmarked against Borsh
+//! decoding.
+
+use crate::events::{AuditLogHeader, MarketEvent};
+use crate::instructions::PhoenixInstruction;
+use borsh::BorshDeserialize;
+use bytemuck::{Pod, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+
+const FILL_TAG: u8 = 2;
+const PLACE_TAG: u8 = 3;
+const REDUCE_TAG: u8 = 4;
+
+/// In-place view of a `MarketEvent::Fill`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct FillEventView {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_filled: u64,
+    pub base_lots_remaining: u64,
+}
+
+/// In-place view of a `MarketEvent::Place`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct PlaceEventView {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub client_order_id: u128,
+    pub price_in_ticks: u64,
+    pub base_lots_placed: u64,
+}
+
+/// In-place view of a `MarketEvent::Reduce`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct ReduceEventView {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_removed: u64,
+    pub base_lots_remaining: u64,
+}
+
+/// An event borrowed from log data where possible, or decoded with Borsh otherwise.
+#[derive(Debug, Clone, Copy)]
+pub enum MarketEventRef<'a> {
+    Fill(&'a FillEventView),
+    Place(&'a PlaceEventView),
+    Reduce(&'a ReduceEventView),
+    Other(MarketEvent),
+}
+
+impl MarketEventRef<'_> {
+    /// Converts the view into an owned `MarketEvent`.
+    pub fn to_event(&self) -> MarketEvent {
+        match *self {
+            Self::Fill(fill) => MarketEvent::Fill {
+                index: fill.index,
+                maker_id: fill.maker_id,
+                order_sequence_number: fill.order_sequence_number,
+                price_in_ticks: fill.price_in_ticks,
+                base_lots_filled: fill.base_lots_filled,
+                base_lots_remaining: fill.base_lots_remaining,
+            },
+            Self::Place(place) => MarketEvent::Place {
+                index: place.index,
+                order_sequence_number: place.order_sequence_number,
+                client_order_id: place.client_order_id,
+                price_in_ticks: place.price_in_ticks,
+                base_lots_placed: place.base_lots_placed,
+            },
+            Self::Reduce(reduce) => MarketEvent::Reduce {
+                index: reduce.index,
+                order_sequence_number: reduce.order_sequence_number,
+                price_in_ticks: reduce.price_in_ticks,
+                base_lots_removed: reduce.base_lots_removed,
+                base_lots_remaining: reduce.base_lots_remaining,
+            },
+            Self::Other(event) => event,
+        }
+    }
+}
+
+/// Iterator over the events of a single `Log` instruction, after its header.
+///
+/// Iteration stops at the first event that fails to decode.
+#[derive(Debug, Clone)]
+pub struct MarketEventRefIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MarketEventRefIter<'a> {
+    type Item = MarketEventRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, rest) = self.data.split_first()?;
+        let borrowed = match *tag {
+            FILL_TAG => view::<FillEventView>(rest).map(|(v, r)| (MarketEventRef::Fill(v), r)),
+            PLACE_TAG => view::<PlaceEventView>(rest).map(|(v, r)| (MarketEventRef::Place(v), r)),
+            REDUCE_TAG => {
+                view::<ReduceEventView>(rest).map(|(v, r)| (MarketEventRef::Reduce(v), r))
+            }
+            _ => None,
+        };
+        match borrowed {
+            Some((event, rest)) => {
+                self.data = rest;
+                Some(event)
+            }
+            None => match MarketEvent::deserialize(&mut self.data) {
+                Ok(event) => Some(MarketEventRef::Other(event)),
+                Err(_) => {
+                    self.data = &[];
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Splits a `T` off the front of `bytes`. Only used on little-endian targets, where the in-memory
+/// layout of the packed views matches Borsh.
+fn view<T: Pod>(bytes: &[u8]) -> Option<(&T, &[u8])> {
+    if cfg!(target_endian = "big") || bytes.len() < std::mem::size_of::<T>() {
+        return None;
+    }
+    let (head, rest) = bytes.split_at(std::mem::size_of::<T>());
+    Some((bytemuck::try_from_bytes(head).ok()?, rest))
+}
+
+/// Parses the header of a `Log` instruction and returns an iterator over the events that follow
+/// it. This is the zero-copy counterpart of `parse_log_instruction_data`.
+pub fn parse_log_instruction_data_ref(
+    data: &[u8],
+) -> Option<(AuditLogHeader, MarketEventRefIter<'_>)> {
+    let (tag, mut payload) = data.split_first()?;
+    if *tag != PhoenixInstruction::Log as u8 {
+        return None;
+    }
+    let header = match MarketEvent::deserialize(&mut payload).ok()? {
+        MarketEvent::Header { header } => header,
+        _ => return None,
+    };
+    Some((header, MarketEventRefIter { data: payload }))
+}
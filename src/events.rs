@@ -1,8 +1,12 @@
+use crate::enums::Side;
+use crate::instructions::{DecodedPhoenixInstruction, PhoenixInstruction};
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 
 /// Struct representing metadata about a set of events from a single market instruction.
-#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+#[derive(
+    Debug, Copy, Clone, BorshDeserialize, BorshSerialize, serde::Serialize, serde::Deserialize,
+)]
 pub struct AuditLogHeader {
     /// The enum number value of the instruction that generated this log.
     pub instruction: u8,
@@ -17,9 +21,11 @@ pub struct AuditLogHeader {
     pub slot: u64,
 
     /// The Pubkey of the market the log is for.
+    #[serde(with = "crate::pubkey_serde")]
     pub market: Pubkey,
 
     /// The Pubkey of the account that generated the log.
+    #[serde(with = "crate::pubkey_serde")]
     pub signer: Pubkey,
 
     /// The number of events in the log.
@@ -27,7 +33,9 @@ pub struct AuditLogHeader {
 }
 
 /// Enum representing the different types of events that can be logged.
-#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+#[derive(
+    Debug, Copy, Clone, BorshDeserialize, BorshSerialize, serde::Serialize, serde::Deserialize,
+)]
 pub enum MarketEvent {
     Uninitialized,
 
@@ -42,6 +50,7 @@ pub enum MarketEvent {
         index: u16,
 
         /// The Pubkey of the maker whose order was filled.
+        #[serde(with = "crate::pubkey_serde")]
         maker_id: Pubkey,
 
         /// The order sequence number of the order that was filled.
@@ -100,6 +109,7 @@ pub enum MarketEvent {
         index: u16,
 
         /// The Pubkey of the maker whose order was evicted.
+        #[serde(with = "crate::pubkey_serde")]
         maker_id: Pubkey,
 
         /// The order sequence number of the order that was evicted.
@@ -138,4 +148,194 @@ pub enum MarketEvent {
         /// Total fees collected, in quote lots.
         fees_collected_in_quote_lots: u64,
     },
+
+    /// Represents the expiry of a limit order placed with a time in force. Logged right after
+    /// the `Place` event of the order.
+    TimeInForce {
+        /// Index of the event in the list of events.
+        index: u16,
+
+        /// The order sequence number of the order that was placed.
+        order_sequence_number: u64,
+
+        /// The last slot in which the order is valid. 0 if the order does not expire by slot.
+        last_valid_slot: u64,
+
+        /// The last unix timestamp, in seconds, at which the order is valid. 0 if the order does
+        /// not expire by time.
+        last_valid_unix_timestamp_in_seconds: u64,
+    },
+}
+
+impl MarketEvent {
+    /// Returns the index of the event in the list of events of its instruction, or None for
+    /// headers and uninitialized events.
+    pub fn index(&self) -> Option<u16> {
+        match *self {
+            MarketEvent::Uninitialized | MarketEvent::Header { .. } => None,
+            MarketEvent::Fill { index, .. }
+            | MarketEvent::Place { index, .. }
+            | MarketEvent::Reduce { index, .. }
+            | MarketEvent::Evict { index, .. }
+            | MarketEvent::FillSummary { index, .. }
+            | MarketEvent::Fee { index, .. }
+            | MarketEvent::TimeInForce { index, .. } => Some(index),
+        }
+    }
+
+    /// For a fill, returns the side of the taker: the opposite of the side of the maker's
+    /// order, which is derived from its sequence number. Returns None for other events.
+    pub fn taker_side(&self) -> Option<Side> {
+        match self {
+            MarketEvent::Fill {
+                order_sequence_number,
+                ..
+            } => Some(Side::from_order_sequence_number(*order_sequence_number).opposite()),
+            _ => None,
+        }
+    }
+
+    /// For a fill, returns the size filled in base lots, positive if the taker bought and
+    /// negative if the taker sold. Returns None for other events.
+    pub fn signed_base_lots_filled(&self) -> Option<i128> {
+        match (self, self.taker_side()?) {
+            (
+                MarketEvent::Fill {
+                    base_lots_filled, ..
+                },
+                Side::Bid,
+            ) => Some(*base_lots_filled as i128),
+            (
+                MarketEvent::Fill {
+                    base_lots_filled, ..
+                },
+                Side::Ask,
+            ) => Some(-(*base_lots_filled as i128)),
+            _ => None,
+        }
+    }
+}
+
+/// A fill attributed to the taker that initiated it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TakerFill {
+    /// The signer of the instruction that crossed the book.
+    pub taker: Pubkey,
+    pub maker: Pubkey,
+    pub taker_side: Side,
+    pub price_in_ticks: u64,
+    pub base_lots_filled: u64,
+    /// `base_lots_filled`, positive for buy-initiated fills and negative for sell-initiated fills.
+    pub signed_base_lots_filled: i128,
+}
+
+/// Returns the fills of a single log, attributed to the log's signer as the taker.
+pub fn taker_fills(header: &AuditLogHeader, events: &[MarketEvent]) -> Vec<TakerFill> {
+    events
+        .iter()
+        .filter_map(|event| match *event {
+            MarketEvent::Fill {
+                maker_id,
+                price_in_ticks,
+                base_lots_filled,
+                ..
+            } => Some(TakerFill {
+                taker: header.signer,
+                maker: maker_id,
+                taker_side: event.taker_side()?,
+                price_in_ticks,
+                base_lots_filled,
+                signed_base_lots_filled: event.signed_base_lots_filled()?,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses the data of a single `Log` instruction into its header and the events that follow it.
+///
+/// The data is the `Log` instruction tag followed by Borsh-serialized `MarketEvent`s, the first
+/// of which is always a `MarketEvent::Header`.
+pub fn parse_log_instruction_data(data: &[u8]) -> Option<(AuditLogHeader, Vec<MarketEvent>)> {
+    let (tag, mut payload) = data.split_first()?;
+    if *tag != PhoenixInstruction::Log as u8 {
+        return None;
+    }
+    let header = match MarketEvent::deserialize(&mut payload).ok()? {
+        MarketEvent::Header { header } => header,
+        _ => return None,
+    };
+    let mut events = Vec::with_capacity(header.total_events as usize);
+    while !payload.is_empty() {
+        events.push(MarketEvent::deserialize(&mut payload).ok()?);
+    }
+    Some((header, events))
+}
+
+/// All of the events emitted by a single top level Phoenix instruction in a transaction.
+#[derive(Debug, Clone)]
+pub struct PhoenixTransactionEvents {
+    /// The index of the instruction in the transaction.
+    pub instruction_index: usize,
+
+    /// The decoded instruction that emitted the events.
+    pub instruction: DecodedPhoenixInstruction,
+
+    /// The header of the first log emitted by the instruction.
+    pub header: AuditLogHeader,
+
+    /// The events emitted by the instruction, in the order they were logged.
+    pub events: Vec<MarketEvent>,
+}
+
+impl PhoenixTransactionEvents {
+    /// Collects the events for every top level Phoenix instruction in a transaction.
+    ///
+    /// `inner_instructions[i]` must hold the inner instructions invoked by `instructions[i]`;
+    /// instructions without an entry are treated as having invoked nothing. Instructions that
+    /// are not Phoenix instructions, or that emitted no logs, are skipped.
+    pub fn from_transaction(
+        instructions: &[Instruction],
+        inner_instructions: &[Vec<Instruction>],
+    ) -> Vec<Self> {
+        instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(instruction_index, instruction)| {
+                let inner = inner_instructions
+                    .get(instruction_index)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                Self::from_instruction(instruction_index, instruction, inner)
+            })
+            .collect()
+    }
+
+    /// Collects the events emitted by a single top level Phoenix instruction.
+    pub fn from_instruction(
+        instruction_index: usize,
+        instruction: &Instruction,
+        inner_instructions: &[Instruction],
+    ) -> Option<Self> {
+        if instruction.program_id != crate::id() {
+            return None;
+        }
+        let decoded = DecodedPhoenixInstruction::decode(&instruction.data)?;
+        let mut header = None;
+        let mut events = vec![];
+        for (log_header, log_events) in inner_instructions
+            .iter()
+            .filter(|ix| ix.program_id == crate::id())
+            .filter_map(|ix| parse_log_instruction_data(&ix.data))
+        {
+            header.get_or_insert(log_header);
+            events.extend(log_events);
+        }
+        Some(Self {
+            instruction_index,
+            instruction: decoded,
+            header: header?,
+            events,
+        })
+    }
 }
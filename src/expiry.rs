@@ -0,0 +1,141 @@
+use crate::events::MarketEvent;
+use crate::market::{FIFOOrderId, FIFORestingOrder};
+use crate::snapshot::MarketSnapshot;
+use std::collections::HashMap;
+
+/// When a resting order stops being valid, as logged by a `MarketEvent::TimeInForce`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderExpiry {
+    /// The last slot in which the order is valid. If `None`, the order does not expire by slot.
+    pub last_valid_slot: Option<u64>,
+
+    /// The last unix timestamp, in seconds, at which the order is valid. If `None`, the order
+    /// does not expire by time.
+    pub last_valid_unix_timestamp_in_seconds: Option<u64>,
+}
+
+impl OrderExpiry {
+    /// Creates the expiry from the fields of a `MarketEvent::TimeInForce`, where 0 means the
+    /// order does not expire.
+    pub fn from_event_fields(
+        last_valid_slot: u64,
+        last_valid_unix_timestamp_in_seconds: u64,
+    ) -> Self {
+        Self {
+            last_valid_slot: (last_valid_slot != 0).then_some(last_valid_slot),
+            last_valid_unix_timestamp_in_seconds: (last_valid_unix_timestamp_in_seconds != 0)
+                .then_some(last_valid_unix_timestamp_in_seconds),
+        }
+    }
+
+    /// Returns true if the program will no longer match the order at `slot` and
+    /// `unix_timestamp_in_seconds`.
+    pub fn is_expired(&self, slot: u64, unix_timestamp_in_seconds: u64) -> bool {
+        self.last_valid_slot.is_some_and(|last| slot > last)
+            || self
+                .last_valid_unix_timestamp_in_seconds
+                .is_some_and(|last| unix_timestamp_in_seconds > last)
+    }
+}
+
+/// Tracks the expiry of resting orders from the `TimeInForce` events of a market's logs, so that
+/// expired orders can be pruned from a locally maintained `MarketSnapshot`.
+///
+/// Expired orders stay on chain until the program removes them, but they are never matched.
+/// Feed the tracker the same events applied to the snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct OrderExpiryTracker {
+    expiries: HashMap<u64, OrderExpiry>,
+}
+
+impl OrderExpiryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the expiry of the order with `order_sequence_number`, if it has one.
+    pub fn get(&self, order_sequence_number: u64) -> Option<&OrderExpiry> {
+        self.expiries.get(&order_sequence_number)
+    }
+
+    /// Returns the number of tracked orders that have an expiry.
+    pub fn len(&self) -> usize {
+        self.expiries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expiries.is_empty()
+    }
+
+    /// Records the expiry logged by a `TimeInForce` event, and forgets orders that an event
+    /// removed from the book.
+    pub fn apply_event(&mut self, event: &MarketEvent) {
+        match *event {
+            MarketEvent::TimeInForce {
+                order_sequence_number,
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                ..
+            } => {
+                let expiry = OrderExpiry::from_event_fields(
+                    last_valid_slot,
+                    last_valid_unix_timestamp_in_seconds,
+                );
+                if expiry != OrderExpiry::default() {
+                    self.expiries.insert(order_sequence_number, expiry);
+                }
+            }
+            MarketEvent::Fill {
+                order_sequence_number,
+                base_lots_remaining: 0,
+                ..
+            }
+            | MarketEvent::Reduce {
+                order_sequence_number,
+                base_lots_remaining: 0,
+                ..
+            }
+            | MarketEvent::Evict {
+                order_sequence_number,
+                ..
+            } => {
+                self.expiries.remove(&order_sequence_number);
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies every event of a single log.
+    pub fn apply_log(&mut self, events: &[MarketEvent]) {
+        for event in events {
+            self.apply_event(event);
+        }
+    }
+
+    /// Removes the orders that are expired at `slot` and `unix_timestamp_in_seconds` from
+    /// `snapshot` and stops tracking them. Returns the removed orders in book order, bids first.
+    pub fn prune_expired(
+        &mut self,
+        snapshot: &mut MarketSnapshot,
+        slot: u64,
+        unix_timestamp_in_seconds: u64,
+    ) -> Vec<(FIFOOrderId, FIFORestingOrder)> {
+        let mut pruned = vec![];
+        for book in [&mut snapshot.bids, &mut snapshot.asks] {
+            book.retain(|&(order_id, order)| {
+                let expired = self
+                    .expiries
+                    .get(&order_id.order_sequence_number)
+                    .is_some_and(|expiry| expiry.is_expired(slot, unix_timestamp_in_seconds));
+                if expired {
+                    pruned.push((order_id, order));
+                }
+                !expired
+            });
+        }
+        for (order_id, _) in pruned.iter() {
+            self.expiries.remove(&order_id.order_sequence_number);
+        }
+        pruned
+    }
+}
@@ -0,0 +1,40 @@
+use crate::fill_math::taker_fee_quote_lots;
+use crate::market::Market;
+use serde::{Deserialize, Serialize};
+
+/// The fees charged on trades in a market.
+///
+/// Phoenix currently charges a flat taker fee and no maker fee. Fees are only exposed through
+/// methods so that maker rebates or tiered fees can be added without changing callers.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    taker_fee_bps: u16,
+}
+
+impl FeeSchedule {
+    /// A schedule that charges takers `taker_fee_bps` and makers nothing.
+    pub fn flat(taker_fee_bps: u16) -> Self {
+        Self { taker_fee_bps }
+    }
+
+    /// Returns the fee schedule of a loaded market.
+    pub fn from_market(market: &dyn Market) -> Self {
+        Self::flat(market.get_taker_bps())
+    }
+
+    /// Returns the taker fee rate, in basis points.
+    pub fn taker_fee_bps(&self) -> u16 {
+        self.taker_fee_bps
+    }
+
+    /// Returns the fee a taker pays on a trade of `quote_lots`, rounded up.
+    pub fn taker_fee(&self, quote_lots: u64) -> u64 {
+        taker_fee_quote_lots(quote_lots, self.taker_fee_bps as u64)
+    }
+
+    /// Returns the fee a maker pays on a trade of `quote_lots`. A negative value is a rebate.
+    pub fn maker_fee(&self, _quote_lots: u64) -> i64 {
+        0
+    }
+}
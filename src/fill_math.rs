@@ -0,0 +1,86 @@
+//! The fill arithmetic used by the Phoenix matching engine.
+//!
+//! Matching accumulates "adjusted quote lots" (price in ticks * tick size in quote lots per base
+//! unit * base lots) across every fill of a taker order, and only divides by the number of base
+//! lots per base unit once at the end. The division rounds against the taker: quote lots paid by
+//! a buyer are rounded up, and quote lots received by a seller are rounded down. Summing
+//! per-fill quote amounts therefore does not always reproduce the `FillSummary`.
+
+use crate::enums::Side;
+
+/// Returns the adjusted quote lots for `num_base_lots` at `price_in_ticks`.
+pub fn adjusted_quote_lots(
+    price_in_ticks: u64,
+    tick_size_in_quote_lots_per_base_unit: u64,
+    num_base_lots: u64,
+) -> u128 {
+    price_in_ticks as u128 * tick_size_in_quote_lots_per_base_unit as u128 * num_base_lots as u128
+}
+
+/// Converts adjusted quote lots to quote lots, rounding against a taker on `taker_side`:
+/// up for a bid (the buyer pays) and down for an ask (the seller receives).
+pub fn adjusted_quote_lots_to_quote_lots(
+    adjusted_quote_lots: u128,
+    base_lots_per_base_unit: u64,
+    taker_side: Side,
+) -> u64 {
+    let base_lots_per_base_unit = base_lots_per_base_unit as u128;
+    let quote_lots = match taker_side {
+        Side::Bid => adjusted_quote_lots.div_ceil(base_lots_per_base_unit),
+        Side::Ask => adjusted_quote_lots / base_lots_per_base_unit,
+    };
+    quote_lots as u64
+}
+
+/// Returns the quote lots exchanged for a single fill, rounded against a taker on `taker_side`.
+pub fn fill_quote_lots(
+    price_in_ticks: u64,
+    num_base_lots: u64,
+    tick_size_in_quote_lots_per_base_unit: u64,
+    base_lots_per_base_unit: u64,
+    taker_side: Side,
+) -> u64 {
+    adjusted_quote_lots_to_quote_lots(
+        adjusted_quote_lots(
+            price_in_ticks,
+            tick_size_in_quote_lots_per_base_unit,
+            num_base_lots,
+        ),
+        base_lots_per_base_unit,
+        taker_side,
+    )
+}
+
+/// Returns the total quote lots exchanged across the fills of one taker order, given as
+/// `(price_in_ticks, base_lots_filled)` pairs. This matches `total_quote_lots_filled` in the
+/// order's `FillSummary`.
+pub fn total_fill_quote_lots<I>(
+    fills: I,
+    tick_size_in_quote_lots_per_base_unit: u64,
+    base_lots_per_base_unit: u64,
+    taker_side: Side,
+) -> u64
+where
+    I: IntoIterator<Item = (u64, u64)>,
+{
+    let total_adjusted_quote_lots = fills
+        .into_iter()
+        .map(|(price_in_ticks, num_base_lots)| {
+            adjusted_quote_lots(
+                price_in_ticks,
+                tick_size_in_quote_lots_per_base_unit,
+                num_base_lots,
+            )
+        })
+        .sum();
+    adjusted_quote_lots_to_quote_lots(
+        total_adjusted_quote_lots,
+        base_lots_per_base_unit,
+        taker_side,
+    )
+}
+
+/// Returns the taker fee charged on `quote_lots`, rounded up.
+pub fn taker_fee_quote_lots(quote_lots: u64, taker_fee_bps: u64) -> u64 {
+    (quote_lots as u128 * taker_fee_bps as u128).div_ceil(10_000) as u64
+}
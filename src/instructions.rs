@@ -1,14 +1,19 @@
+use crate::market::{FIFOOrderId, Market, MarketHeader, MarketSizeParams};
 use crate::order_packet::OrderPacket;
+use crate::rounding::RoundingDirection;
+use crate::transaction::{estimate_transaction_size, fits_in_transaction, MAX_TRANSACTION_SIZE};
+use crate::typed_instructions::{
+    FreeFundsAccounts, RequestSeatAccounts, SeatedFreeFundsAccounts, SeatedTokenTransferAccounts,
+    TokenTransferAccounts,
+};
+use crate::units::{BaseLots, QuoteLots, Ticks};
 use crate::{enums::Side, phoenix_log_authority};
 use borsh::{BorshDeserialize, BorshSerialize};
 use num_enum::TryFromPrimitive;
 use shank::ShankInstruction;
-use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    system_program,
-};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_program};
 use spl_associated_token_account::get_associated_token_address;
+use thiserror::Error;
 
 pub fn get_vault_address(market: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"vault", market.as_ref(), mint.as_ref()], &crate::ID)
@@ -18,6 +23,61 @@ pub fn get_seat_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"seat", market.as_ref(), trader.as_ref()], &crate::ID)
 }
 
+/// Returns the accounts that a trader references on every interaction with a market, in a stable
+/// order, for populating an address lookup table. Trader-specific accounts (the trader, their
+/// seat and token accounts) are not included.
+pub fn get_market_static_accounts(market: &Pubkey, header: &MarketHeader) -> Vec<Pubkey> {
+    vec![
+        crate::id(),
+        phoenix_log_authority::id(),
+        *market,
+        header.base_params.vault_key,
+        header.quote_params.vault_key,
+        header.base_params.mint_key,
+        header.quote_params.mint_key,
+        spl_token::id(),
+        system_program::id(),
+    ]
+}
+
+/// The most post only orders without expiry that a `MultipleOrderPacket` can hold while the
+/// instruction placing it (with token accounts) still fits in a transaction on its own.
+pub const MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET: usize = 42;
+
+/// The most orders that a single cancel-by-id instruction (with token accounts) can hold while
+/// still fitting in a transaction on its own.
+pub const MAX_CANCELS_PER_BATCH: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum BatchLimitError {
+    #[error("{count} orders exceeds the limit of {max} per packet")]
+    TooManyOrders { count: usize, max: usize },
+    #[error("{count} cancels exceeds the limit of {max} per instruction")]
+    TooManyCancels { count: usize, max: usize },
+    #[error("Instruction does not fit in a transaction")]
+    TransactionTooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SwapError {
+    #[error("Input amount is smaller than one lot")]
+    InputBelowOneLot,
+    #[error("Minimum output must be non-zero")]
+    MissingMinOut,
+    #[error("Market header has a zero lot size")]
+    ZeroLotSize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum UiAmountError {
+    #[error("Amount {0} is negative or not finite")]
+    InvalidAmount(f64),
+    #[error("Amount {0} does not fit in a u64 of atoms")]
+    Overflow(f64),
+    #[error("Market header has a zero lot size")]
+    ZeroLotSize,
+}
+
 #[repr(u8)]
 #[derive(TryFromPrimitive, Debug, Copy, Clone, ShankInstruction, PartialEq, Eq)]
 #[rustfmt::skip]
@@ -206,20 +266,168 @@ impl PhoenixInstruction {
     }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+/// A Phoenix instruction decoded from its instruction data, along with its parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedPhoenixInstruction {
+    Swap(OrderPacket),
+    SwapWithFreeFunds(OrderPacket),
+    PlaceLimitOrder(OrderPacket),
+    PlaceLimitOrderWithFreeFunds(OrderPacket),
+    ReduceOrder(ReduceOrderParams),
+    ReduceOrderWithFreeFunds(ReduceOrderParams),
+    CancelAllOrders,
+    CancelAllOrdersWithFreeFunds,
+    CancelUpTo(CancelUpToParams),
+    CancelUpToWithFreeFunds(CancelUpToParams),
+    CancelMultipleOrdersById(CancelMultipleOrdersByIdParams),
+    CancelMultipleOrdersByIdWithFreeFunds(CancelMultipleOrdersByIdParams),
+    WithdrawFunds(WithdrawParams),
+    DepositFunds(DepositParams),
+    RequestSeat,
+    Log,
+    PlaceMultiplePostOnlyOrders(MultipleOrderPacket),
+    PlaceMultiplePostOnlyOrdersWithFreeFunds(MultipleOrderPacket),
+}
+
+impl DecodedPhoenixInstruction {
+    /// Decodes raw instruction data (tag byte followed by the Borsh-serialized parameters).
+    /// Returns None if the tag is unknown or the parameters fail to deserialize.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (tag, params) = data.split_first()?;
+        let decoded = match PhoenixInstruction::try_from(*tag).ok()? {
+            PhoenixInstruction::Swap => Self::Swap(OrderPacket::try_from_slice(params).ok()?),
+            PhoenixInstruction::SwapWithFreeFunds => {
+                Self::SwapWithFreeFunds(OrderPacket::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::PlaceLimitOrder => {
+                Self::PlaceLimitOrder(OrderPacket::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::PlaceLimitOrderWithFreeFunds => {
+                Self::PlaceLimitOrderWithFreeFunds(OrderPacket::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::ReduceOrder => {
+                Self::ReduceOrder(ReduceOrderParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::ReduceOrderWithFreeFunds => {
+                Self::ReduceOrderWithFreeFunds(ReduceOrderParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::CancelAllOrders => Self::CancelAllOrders,
+            PhoenixInstruction::CancelAllOrdersWithFreeFunds => Self::CancelAllOrdersWithFreeFunds,
+            PhoenixInstruction::CancelUpTo => {
+                Self::CancelUpTo(CancelUpToParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::CancelUpToWithFreeFunds => {
+                Self::CancelUpToWithFreeFunds(CancelUpToParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::CancelMultipleOrdersById => Self::CancelMultipleOrdersById(
+                CancelMultipleOrdersByIdParams::try_from_slice(params).ok()?,
+            ),
+            PhoenixInstruction::CancelMultipleOrdersByIdWithFreeFunds => {
+                Self::CancelMultipleOrdersByIdWithFreeFunds(
+                    CancelMultipleOrdersByIdParams::try_from_slice(params).ok()?,
+                )
+            }
+            PhoenixInstruction::WithdrawFunds => {
+                Self::WithdrawFunds(WithdrawParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::DepositFunds => {
+                Self::DepositFunds(DepositParams::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::RequestSeat => Self::RequestSeat,
+            PhoenixInstruction::Log => Self::Log,
+            PhoenixInstruction::PlaceMultiplePostOnlyOrders => {
+                Self::PlaceMultiplePostOnlyOrders(MultipleOrderPacket::try_from_slice(params).ok()?)
+            }
+            PhoenixInstruction::PlaceMultiplePostOnlyOrdersWithFreeFunds => {
+                Self::PlaceMultiplePostOnlyOrdersWithFreeFunds(
+                    MultipleOrderPacket::try_from_slice(params).ok()?,
+                )
+            }
+        };
+        Some(decoded)
+    }
+
+    /// Returns the instruction tag of the decoded instruction.
+    pub fn instruction(&self) -> PhoenixInstruction {
+        match self {
+            Self::Swap(_) => PhoenixInstruction::Swap,
+            Self::SwapWithFreeFunds(_) => PhoenixInstruction::SwapWithFreeFunds,
+            Self::PlaceLimitOrder(_) => PhoenixInstruction::PlaceLimitOrder,
+            Self::PlaceLimitOrderWithFreeFunds(_) => {
+                PhoenixInstruction::PlaceLimitOrderWithFreeFunds
+            }
+            Self::ReduceOrder(_) => PhoenixInstruction::ReduceOrder,
+            Self::ReduceOrderWithFreeFunds(_) => PhoenixInstruction::ReduceOrderWithFreeFunds,
+            Self::CancelAllOrders => PhoenixInstruction::CancelAllOrders,
+            Self::CancelAllOrdersWithFreeFunds => PhoenixInstruction::CancelAllOrdersWithFreeFunds,
+            Self::CancelUpTo(_) => PhoenixInstruction::CancelUpTo,
+            Self::CancelUpToWithFreeFunds(_) => PhoenixInstruction::CancelUpToWithFreeFunds,
+            Self::CancelMultipleOrdersById(_) => PhoenixInstruction::CancelMultipleOrdersById,
+            Self::CancelMultipleOrdersByIdWithFreeFunds(_) => {
+                PhoenixInstruction::CancelMultipleOrdersByIdWithFreeFunds
+            }
+            Self::WithdrawFunds(_) => PhoenixInstruction::WithdrawFunds,
+            Self::DepositFunds(_) => PhoenixInstruction::DepositFunds,
+            Self::RequestSeat => PhoenixInstruction::RequestSeat,
+            Self::Log => PhoenixInstruction::Log,
+            Self::PlaceMultiplePostOnlyOrders(_) => PhoenixInstruction::PlaceMultiplePostOnlyOrders,
+            Self::PlaceMultiplePostOnlyOrdersWithFreeFunds(_) => {
+                PhoenixInstruction::PlaceMultiplePostOnlyOrdersWithFreeFunds
+            }
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CancelOrderParams {
     pub side: Side,
     pub price_in_ticks: u64,
     pub order_sequence_number: u64,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ReduceOrderParams {
     base_params: CancelOrderParams,
     size: u64,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+impl CancelOrderParams {
+    /// Creates the parameters to cancel the order with `order_id`, with the side derived from
+    /// its sequence number.
+    pub fn from_order_id(order_id: &FIFOOrderId) -> Self {
+        Self {
+            side: Side::from_order_sequence_number(order_id.order_sequence_number),
+            price_in_ticks: order_id.price_in_ticks,
+            order_sequence_number: order_id.order_sequence_number,
+        }
+    }
+
+    pub fn order_id(&self) -> FIFOOrderId {
+        FIFOOrderId::new(self.price_in_ticks, self.order_sequence_number)
+    }
+}
+
+impl ReduceOrderParams {
+    /// Creates the parameters to reduce the order with `order_id` by `size` base lots, with the
+    /// side derived from its sequence number.
+    pub fn from_order_id(order_id: &FIFOOrderId, size: u64) -> Self {
+        Self {
+            base_params: CancelOrderParams::from_order_id(order_id),
+            size,
+        }
+    }
+
+    pub fn base_params(&self) -> &CancelOrderParams {
+        &self.base_params
+    }
+
+    /// The number of base lots to remove from the order.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CancelUpToParams {
     pub side: Side,
     pub tick_limit: Option<u64>,
@@ -227,12 +435,102 @@ pub struct CancelUpToParams {
     pub num_orders_to_cancel: Option<u32>,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
 pub struct CancelMultipleOrdersByIdParams {
     pub orders: Vec<CancelOrderParams>,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+/// A borrowed view of Borsh-encoded `CancelMultipleOrdersByIdParams` that decodes orders on
+/// demand instead of allocating a `Vec<CancelOrderParams>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CancelMultipleOrdersByIdParamsRef<'a> {
+    orders: &'a [u8],
+}
+
+impl<'a> CancelMultipleOrdersByIdParamsRef<'a> {
+    /// The Borsh-encoded size of a single `CancelOrderParams`.
+    const ORDER_SIZE: usize = 1 + 8 + 8;
+
+    /// Validates `data` as exactly one Borsh-encoded `CancelMultipleOrdersByIdParams`.
+    pub fn try_from_slice(data: &'a [u8]) -> std::io::Result<Self> {
+        let invalid = |msg| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        if data.len() < 4 {
+            return Err(invalid("Missing order count"));
+        }
+        let (len, orders) = data.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if len.checked_mul(Self::ORDER_SIZE) != Some(orders.len()) {
+            return Err(invalid("Order count does not match data length"));
+        }
+        if orders
+            .chunks_exact(Self::ORDER_SIZE)
+            .any(|order| order[0] > 1)
+        {
+            return Err(invalid("Invalid side"));
+        }
+        Ok(Self { orders })
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len() / Self::ORDER_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<CancelOrderParams> {
+        self.orders
+            .chunks_exact(Self::ORDER_SIZE)
+            .nth(index)
+            .map(Self::decode_order)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = CancelOrderParams> + 'a {
+        self.orders
+            .chunks_exact(Self::ORDER_SIZE)
+            .map(Self::decode_order)
+    }
+
+    pub fn to_params(&self) -> CancelMultipleOrdersByIdParams {
+        CancelMultipleOrdersByIdParams {
+            orders: self.iter().collect(),
+        }
+    }
+
+    fn decode_order(order: &[u8]) -> CancelOrderParams {
+        CancelOrderParams {
+            side: if order[0] == 0 { Side::Bid } else { Side::Ask },
+            price_in_ticks: u64::from_le_bytes(order[1..9].try_into().unwrap()),
+            order_sequence_number: u64::from_le_bytes(order[9..17].try_into().unwrap()),
+        }
+    }
+}
+
+impl CancelMultipleOrdersByIdParams {
+    /// Creates the parameters to cancel the orders with `order_ids`, with each side derived from
+    /// the order's sequence number.
+    pub fn from_order_ids<'a>(order_ids: impl IntoIterator<Item = &'a FIFOOrderId>) -> Self {
+        Self {
+            orders: order_ids
+                .into_iter()
+                .map(CancelOrderParams::from_order_id)
+                .collect(),
+        }
+    }
+
+    pub fn check_limits(&self) -> Result<(), BatchLimitError> {
+        if self.orders.len() > MAX_CANCELS_PER_BATCH {
+            return Err(BatchLimitError::TooManyCancels {
+                count: self.orders.len(),
+                max: MAX_CANCELS_PER_BATCH,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct DepositParams {
     pub quote_lots: u64,
     pub base_lots: u64,
@@ -244,8 +542,34 @@ pub struct WithdrawParams {
     pub base_lots_to_withdraw: Option<u64>,
 }
 
+/// Parameters used to initialize a market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct InitializeParams {
+    /// The sizes of the market's order books and trader table.
+    pub market_size_params: MarketSizeParams,
+
+    /// Number of quote lots in a quote unit.
+    pub num_quote_lots_per_quote_unit: u64,
+
+    /// Tick size, in quote lots per base unit.
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+
+    /// Number of base lots in a base unit.
+    pub num_base_lots_per_base_unit: u64,
+
+    /// Taker fee, in basis points.
+    pub taker_fee_bps: u16,
+
+    /// The Pubkey of the account that collects fees.
+    pub fee_collector: Pubkey,
+
+    /// Number of raw base units (e.g. whole tokens) in a base unit. Used for tokens with very low
+    /// unit prices, where a tick of one quote lot per raw unit would be too coarse.
+    pub raw_base_units_per_base_unit: Option<u32>,
+}
+
 /// Struct to send a vector of bids and asks as PostOnly orders in a single packet.
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
 pub struct MultipleOrderPacket {
     pub bids: Vec<CondensedOrder>,
     pub asks: Vec<CondensedOrder>,
@@ -253,13 +577,96 @@ pub struct MultipleOrderPacket {
     pub reject_post_only: bool,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CondensedOrder {
     pub price_in_ticks: u64,
     pub size_in_base_lots: u64,
+
+    /// The last slot in which the order is valid. If `None`, the order does not expire by slot.
+    pub last_valid_slot: Option<u64>,
+
+    /// The last unix timestamp, in seconds, at which the order is valid. If `None`, the order
+    /// does not expire by time.
+    pub last_valid_unix_timestamp_in_seconds: Option<u64>,
 }
+
+impl CondensedOrder {
+    pub fn new_default(price_in_ticks: u64, size_in_base_lots: u64) -> Self {
+        CondensedOrder {
+            price_in_ticks,
+            size_in_base_lots,
+            last_valid_slot: None,
+            last_valid_unix_timestamp_in_seconds: None,
+        }
+    }
+
+    pub fn new_with_expiry(
+        price_in_ticks: u64,
+        size_in_base_lots: u64,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+    ) -> Self {
+        CondensedOrder {
+            price_in_ticks,
+            size_in_base_lots,
+            last_valid_slot,
+            last_valid_unix_timestamp_in_seconds,
+        }
+    }
+}
+
 /// Helpers for creating MultipleOrderPacket from vectors of u64 (price in ticks, size in base lots)
 impl MultipleOrderPacket {
+    pub fn num_orders(&self) -> usize {
+        self.bids.len() + self.asks.len()
+    }
+
+    pub fn check_limits(&self) -> Result<(), BatchLimitError> {
+        if self.num_orders() > MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET {
+            return Err(BatchLimitError::TooManyOrders {
+                count: self.num_orders(),
+                max: MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET,
+            });
+        }
+        Ok(())
+    }
+
+    /// Splits the packet into packets of at most `max_orders_per_packet` orders (clamped to
+    /// `MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET`), each holding as many bids as asks where
+    /// possible. Orders are taken from the top of the book down on both sides, so every packet
+    /// quotes both sides until one side runs out, and a packet that lands on its own never
+    /// leaves the trader one-sided. Orders with expiries take more space, so pass a lower
+    /// maximum if the packets must fit in a transaction.
+    pub fn split_by_side_affinity(&self, max_orders_per_packet: usize) -> Vec<Self> {
+        let max = max_orders_per_packet.clamp(1, MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET);
+        let (mut bids, mut asks) = (&self.bids[..], &self.asks[..]);
+        let mut packets = vec![];
+        while !bids.is_empty() || !asks.is_empty() {
+            let mut num_bids = bids.len().min(max / 2);
+            let mut num_asks = asks.len().min(max / 2);
+            // Give any spare capacity to the side with more orders left.
+            let spare = max - num_bids - num_asks;
+            if bids.len() - num_bids >= asks.len() - num_asks {
+                let extra_bids = spare.min(bids.len() - num_bids);
+                num_bids += extra_bids;
+                num_asks += (spare - extra_bids).min(asks.len() - num_asks);
+            } else {
+                let extra_asks = spare.min(asks.len() - num_asks);
+                num_asks += extra_asks;
+                num_bids += (spare - extra_asks).min(bids.len() - num_bids);
+            }
+            packets.push(Self::new_from_condensed_orders(
+                bids[..num_bids].to_vec(),
+                asks[..num_asks].to_vec(),
+                self.client_order_id,
+                self.reject_post_only,
+            ));
+            bids = &bids[num_bids..];
+            asks = &asks[num_asks..];
+        }
+        packets
+    }
+
     pub fn new(
         bids: Vec<(u64, u64)>,
         asks: Vec<(u64, u64)>,
@@ -269,17 +676,11 @@ impl MultipleOrderPacket {
         MultipleOrderPacket {
             bids: bids
                 .iter()
-                .map(|(p, s)| CondensedOrder {
-                    price_in_ticks: *p,
-                    size_in_base_lots: *s,
-                })
+                .map(|(p, s)| CondensedOrder::new_default(*p, *s))
                 .collect(),
             asks: asks
                 .iter()
-                .map(|(p, s)| CondensedOrder {
-                    price_in_ticks: *p,
-                    size_in_base_lots: *s,
-                })
+                .map(|(p, s)| CondensedOrder::new_default(*p, *s))
                 .collect(),
             client_order_id,
             reject_post_only,
@@ -287,23 +688,46 @@ impl MultipleOrderPacket {
     }
 
     pub fn new_default(bids: Vec<(u64, u64)>, asks: Vec<(u64, u64)>) -> Self {
+        Self::new(bids, asks, None, true)
+    }
+
+    /// Creates a packet where every order expires at the same slot and/or unix timestamp.
+    pub fn new_with_expiry(
+        bids: Vec<(u64, u64)>,
+        asks: Vec<(u64, u64)>,
+        client_order_id: Option<u128>,
+        reject_post_only: bool,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+    ) -> Self {
+        let to_order = |(p, s): &(u64, u64)| {
+            CondensedOrder::new_with_expiry(
+                *p,
+                *s,
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+            )
+        };
         MultipleOrderPacket {
-            bids: bids
-                .iter()
-                .map(|(p, s)| CondensedOrder {
-                    price_in_ticks: *p,
-                    size_in_base_lots: *s,
-                })
-                .collect(),
-            asks: asks
-                .iter()
-                .map(|(p, s)| CondensedOrder {
-                    price_in_ticks: *p,
-                    size_in_base_lots: *s,
-                })
-                .collect(),
-            client_order_id: None,
-            reject_post_only: true,
+            bids: bids.iter().map(to_order).collect(),
+            asks: asks.iter().map(to_order).collect(),
+            client_order_id,
+            reject_post_only,
+        }
+    }
+
+    /// Creates a packet from orders that each carry their own expiry.
+    pub fn new_from_condensed_orders(
+        bids: Vec<CondensedOrder>,
+        asks: Vec<CondensedOrder>,
+        client_order_id: Option<u128>,
+        reject_post_only: bool,
+    ) -> Self {
+        MultipleOrderPacket {
+            bids,
+            asks,
+            client_order_id,
+            reject_post_only,
         }
     }
 }
@@ -328,6 +752,112 @@ pub fn create_new_order_instruction(
     )
 }
 
+/// Builds a swap that spends `in_amount_atoms` of the input token (quote for a bid, base for an
+/// ask) and fails unless it receives at least `min_out_atoms` of the output token.
+///
+/// The input is rounded down to whole lots and the minimum output is rounded up, so the swap
+/// never spends more or accepts less than requested. Both the budget and the matching minimum
+/// fill fields of the IOC order are set, so slippage protection cannot be left out.
+pub fn create_swap_instruction_with_min_out(
+    market: &Pubkey,
+    trader: &Pubkey,
+    side: Side,
+    in_amount_atoms: u64,
+    min_out_atoms: u64,
+    header: &MarketHeader,
+) -> Result<Instruction, SwapError> {
+    create_swap_instruction_with_min_out_with_custom_token_accounts(
+        market,
+        trader,
+        &get_associated_token_address(trader, &header.base_params.mint_key),
+        &get_associated_token_address(trader, &header.quote_params.mint_key),
+        side,
+        in_amount_atoms,
+        min_out_atoms,
+        header,
+    )
+}
+
+/// Same as `create_swap_instruction_with_min_out`, settling through the given token accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn create_swap_instruction_with_min_out_with_custom_token_accounts(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base_account: &Pubkey,
+    quote_account: &Pubkey,
+    side: Side,
+    in_amount_atoms: u64,
+    min_out_atoms: u64,
+    header: &MarketHeader,
+) -> Result<Instruction, SwapError> {
+    if min_out_atoms == 0 {
+        return Err(SwapError::MissingMinOut);
+    }
+    let input_lot_size = match side {
+        Side::Bid => header.get_quote_lot_size(),
+        Side::Ask => header.get_base_lot_size(),
+    };
+    if in_amount_atoms < input_lot_size {
+        return Err(SwapError::InputBelowOneLot);
+    }
+    Ok(create_new_order_instruction_with_custom_token_accounts(
+        market,
+        trader,
+        base_account,
+        quote_account,
+        &header.base_params.mint_key,
+        &header.quote_params.mint_key,
+        &swap_order_packet(header, side, in_amount_atoms, min_out_atoms)?,
+    ))
+}
+
+/// Sets whether `trader` signs `instruction`.
+///
+/// Builders mark the trader as a signer. A program trading through a PDA keeps the flag set and
+/// signs with `invoke_signed`, but clears it when the instruction is built off chain to be
+/// forwarded to that program, since the PDA cannot sign the outer transaction.
+pub fn with_trader_signer(
+    mut instruction: Instruction,
+    trader: &Pubkey,
+    is_signer: bool,
+) -> Instruction {
+    for account in instruction
+        .accounts
+        .iter_mut()
+        .filter(|account| account.pubkey == *trader)
+    {
+        account.is_signer = is_signer;
+    }
+    instruction
+}
+
+/// Builds an IOC order that spends `in_amount_atoms` and requires at least `min_out_atoms` back.
+/// The input is rounded down to whole lots and the minimum output is rounded up.
+///
+/// Returns `SwapError::ZeroLotSize` if the header is uninitialized.
+pub(crate) fn swap_order_packet(
+    header: &MarketHeader,
+    side: Side,
+    in_amount_atoms: u64,
+    min_out_atoms: u64,
+) -> Result<OrderPacket, SwapError> {
+    let base_lot_size = header.get_base_lot_size();
+    let quote_lot_size = header.get_quote_lot_size();
+    if base_lot_size == 0 || quote_lot_size == 0 {
+        return Err(SwapError::ZeroLotSize);
+    }
+    Ok(match side {
+        Side::Bid => OrderPacket::new_ioc_buy_with_slippage(
+            QuoteLots::new(in_amount_atoms / quote_lot_size),
+            BaseLots::new(min_out_atoms.div_ceil(base_lot_size)),
+        ),
+        Side::Ask => OrderPacket::new_ioc_sell_with_slippage(
+            BaseLots::new(in_amount_atoms / base_lot_size),
+            QuoteLots::new(min_out_atoms.div_ceil(quote_lot_size)),
+        ),
+    })
+}
+
 pub fn create_new_order_instruction_with_custom_token_accounts(
     market: &Pubkey,
     trader: &Pubkey,
@@ -342,17 +872,16 @@ pub fn create_new_order_instruction_with_custom_token_accounts(
     if order_type.is_take_only() {
         Instruction {
             program_id: crate::id(),
-            accounts: vec![
-                AccountMeta::new_readonly(crate::id(), false),
-                AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-                AccountMeta::new(*market, false),
-                AccountMeta::new(*trader, true),
-                AccountMeta::new(*base_account, false),
-                AccountMeta::new(*quote_account, false),
-                AccountMeta::new(base_vault, false),
-                AccountMeta::new(quote_vault, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-            ],
+            accounts: TokenTransferAccounts {
+                market: *market,
+                trader: *trader,
+                base_account: *base_account,
+                quote_account: *quote_account,
+                base_vault,
+                quote_vault,
+                token_program: spl_token::id(),
+            }
+            .to_account_metas(),
             data: [
                 PhoenixInstruction::Swap.to_vec(),
                 order_type.try_to_vec().unwrap(),
@@ -363,18 +892,17 @@ pub fn create_new_order_instruction_with_custom_token_accounts(
         let (seat, _) = get_seat_address(market, trader);
         Instruction {
             program_id: crate::id(),
-            accounts: vec![
-                AccountMeta::new_readonly(crate::id(), false),
-                AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-                AccountMeta::new(*market, false),
-                AccountMeta::new(*trader, true),
-                AccountMeta::new_readonly(seat, false),
-                AccountMeta::new(*base_account, false),
-                AccountMeta::new(*quote_account, false),
-                AccountMeta::new(base_vault, false),
-                AccountMeta::new(quote_vault, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-            ],
+            accounts: SeatedTokenTransferAccounts {
+                market: *market,
+                trader: *trader,
+                seat,
+                base_account: *base_account,
+                quote_account: *quote_account,
+                base_vault,
+                quote_vault,
+                token_program: spl_token::id(),
+            }
+            .to_account_metas(),
             data: [
                 PhoenixInstruction::PlaceLimitOrder.to_vec(),
                 order_type.try_to_vec().unwrap(),
@@ -392,13 +920,12 @@ pub fn create_new_order_with_free_funds_instruction(
     let (seat, _) = get_seat_address(market, trader);
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new_readonly(seat, false),
-        ],
+        accounts: SeatedFreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+            seat,
+        }
+        .to_account_metas(),
         data: [
             if order_type.is_take_only() {
                 PhoenixInstruction::SwapWithFreeFunds.to_vec()
@@ -411,6 +938,77 @@ pub fn create_new_order_with_free_funds_instruction(
     }
 }
 
+/// Returns `instruction` if it fits in a transaction on its own.
+fn check_fits_in_transaction(
+    instruction: Instruction,
+    payer: &Pubkey,
+) -> Result<Instruction, BatchLimitError> {
+    if !fits_in_transaction(std::slice::from_ref(&instruction), payer) {
+        return Err(BatchLimitError::TransactionTooLarge);
+    }
+    Ok(instruction)
+}
+
+/// Like `create_new_multiple_order_instruction`, but rejects packets that exceed
+/// `MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET` or do not fit in a transaction.
+pub fn try_create_new_multiple_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+    multiple_order_packet: &MultipleOrderPacket,
+) -> Result<Instruction, BatchLimitError> {
+    multiple_order_packet.check_limits()?;
+    check_fits_in_transaction(
+        create_new_multiple_order_instruction(market, trader, base, quote, multiple_order_packet),
+        trader,
+    )
+}
+
+/// Like `create_new_multiple_order_with_free_funds_instruction`, but rejects packets that exceed
+/// `MAX_ORDERS_PER_MULTIPLE_ORDER_PACKET` or do not fit in a transaction.
+pub fn try_create_new_multiple_order_with_free_funds_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    multiple_order_packet: &MultipleOrderPacket,
+) -> Result<Instruction, BatchLimitError> {
+    multiple_order_packet.check_limits()?;
+    check_fits_in_transaction(
+        create_new_multiple_order_with_free_funds_instruction(
+            market,
+            trader,
+            multiple_order_packet,
+        ),
+        trader,
+    )
+}
+
+/// Like `create_cancel_multiple_orders_by_id_instruction`, but rejects batches that exceed
+/// `MAX_CANCELS_PER_BATCH`.
+pub fn try_create_cancel_multiple_orders_by_id_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+    params: &CancelMultipleOrdersByIdParams,
+) -> Result<Instruction, BatchLimitError> {
+    params.check_limits()?;
+    Ok(create_cancel_multiple_orders_by_id_instruction(
+        market, trader, base, quote, params,
+    ))
+}
+
+/// Like `create_cancel_multiple_orders_by_id_with_free_funds_instruction`, but rejects batches
+/// that exceed `MAX_CANCELS_PER_BATCH`.
+pub fn try_create_cancel_multiple_orders_by_id_with_free_funds_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    params: &CancelMultipleOrdersByIdParams,
+) -> Result<Instruction, BatchLimitError> {
+    params.check_limits()?;
+    Ok(create_cancel_multiple_orders_by_id_with_free_funds_instruction(market, trader, params))
+}
+
 pub fn create_new_multiple_order_instruction(
     market: &Pubkey,
     trader: &Pubkey,
@@ -445,18 +1043,17 @@ pub fn create_new_multiple_order_instruction_with_custom_token_accounts(
     let (seat, _) = get_seat_address(market, trader);
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new_readonly(seat, false),
-            AccountMeta::new(*base_account, false),
-            AccountMeta::new(*quote_account, false),
-            AccountMeta::new(base_vault, false),
-            AccountMeta::new(quote_vault, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts: SeatedTokenTransferAccounts {
+            market: *market,
+            trader: *trader,
+            seat,
+            base_account: *base_account,
+            quote_account: *quote_account,
+            base_vault,
+            quote_vault,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(),
         data: [
             PhoenixInstruction::PlaceMultiplePostOnlyOrders.to_vec(),
             multiple_order_packet.try_to_vec().unwrap(),
@@ -473,13 +1070,12 @@ pub fn create_new_multiple_order_with_free_funds_instruction(
     let (seat, _) = get_seat_address(market, trader);
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new_readonly(seat, false),
-        ],
+        accounts: SeatedFreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+            seat,
+        }
+        .to_account_metas(),
         data: [
             PhoenixInstruction::PlaceMultiplePostOnlyOrdersWithFreeFunds.to_vec(),
             multiple_order_packet.try_to_vec().unwrap(),
@@ -494,16 +1090,119 @@ pub fn create_cancel_all_order_with_free_funds_instruction(
 ) -> Instruction {
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-        ],
+        accounts: FreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+        }
+        .to_account_metas(),
         data: PhoenixInstruction::CancelAllOrdersWithFreeFunds.to_vec(),
     }
 }
 
+/// Builds the canonical quoting bundle: cancel all of the trader's orders (keeping the released
+/// funds on the market) and place a new set of post only orders with free funds.
+///
+/// The result is grouped by transaction. If the cancel and the whole packet fit in a single
+/// transaction, one group is returned. Otherwise the orders are spread across as few extra
+/// transactions as possible, alternating bids and asks from the top of the book down so that
+/// the first transaction carries the most aggressive quotes on both sides.
+pub fn create_cancel_all_and_place_instructions(
+    market: &Pubkey,
+    trader: &Pubkey,
+    multiple_order_packet: &MultipleOrderPacket,
+) -> Vec<Vec<Instruction>> {
+    let cancel_all = create_cancel_all_order_with_free_funds_instruction(market, trader);
+    let place = |bids: &[CondensedOrder], asks: &[CondensedOrder]| {
+        create_new_multiple_order_with_free_funds_instruction(
+            market,
+            trader,
+            &MultipleOrderPacket::new_from_condensed_orders(
+                bids.to_vec(),
+                asks.to_vec(),
+                multiple_order_packet.client_order_id,
+                multiple_order_packet.reject_post_only,
+            ),
+        )
+    };
+
+    let mut orders = vec![];
+    for i in 0..multiple_order_packet
+        .bids
+        .len()
+        .max(multiple_order_packet.asks.len())
+    {
+        if let Some(bid) = multiple_order_packet.bids.get(i) {
+            orders.push((Side::Bid, *bid));
+        }
+        if let Some(ask) = multiple_order_packet.asks.get(i) {
+            orders.push((Side::Ask, *ask));
+        }
+    }
+
+    let mut transactions = vec![];
+    let mut current = vec![cancel_all];
+    let (mut bids, mut asks) = (vec![], vec![]);
+    for (side, order) in orders {
+        let (mut next_bids, mut next_asks) = (bids.clone(), asks.clone());
+        match side {
+            Side::Bid => next_bids.push(order),
+            Side::Ask => next_asks.push(order),
+        }
+        let candidate = [current.clone(), vec![place(&next_bids, &next_asks)]].concat();
+        if fits_in_transaction(&candidate, trader) || (bids.is_empty() && asks.is_empty()) {
+            (bids, asks) = (next_bids, next_asks);
+            continue;
+        }
+        current.push(place(&bids, &asks));
+        transactions.push(std::mem::take(&mut current));
+        (bids, asks) = match side {
+            Side::Bid => (vec![order], vec![]),
+            Side::Ask => (vec![], vec![order]),
+        };
+    }
+    if !bids.is_empty() || !asks.is_empty() {
+        current.push(place(&bids, &asks));
+    }
+    if !current.is_empty() {
+        transactions.push(current);
+    }
+    transactions
+}
+
+/// Amends a resting order by cancelling it by id and placing a post only order at the new price
+/// and size, tagged with the same `client_order_id` so that the replacement can be tracked under
+/// the original key. The cancelled order's funds are kept on the market and reused for the
+/// replacement.
+pub fn create_replace_order_instructions(
+    market: &Pubkey,
+    trader: &Pubkey,
+    order_id: &FIFOOrderId,
+    new_price_in_ticks: u64,
+    new_num_base_lots: u64,
+    client_order_id: u128,
+) -> Vec<Instruction> {
+    let cancel = CancelOrderParams::from_order_id(order_id);
+    vec![
+        create_cancel_multiple_orders_by_id_with_free_funds_instruction(
+            market,
+            trader,
+            &CancelMultipleOrdersByIdParams {
+                orders: vec![cancel],
+            },
+        ),
+        create_new_order_with_free_funds_instruction(
+            market,
+            trader,
+            &OrderPacket::new_post_only_default_with_client_order_id(
+                cancel.side,
+                Ticks::new(new_price_in_ticks),
+                BaseLots::new(new_num_base_lots),
+                client_order_id,
+            ),
+        ),
+    ]
+}
+
 pub fn create_cancel_up_to_with_free_funds_instruction(
     market: &Pubkey,
     trader: &Pubkey,
@@ -511,12 +1210,11 @@ pub fn create_cancel_up_to_with_free_funds_instruction(
 ) -> Instruction {
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-        ],
+        accounts: FreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+        }
+        .to_account_metas(),
         data: [
             PhoenixInstruction::CancelUpToWithFreeFunds.to_vec(),
             params.try_to_vec().unwrap(),
@@ -532,12 +1230,11 @@ pub fn create_cancel_multiple_orders_by_id_with_free_funds_instruction(
 ) -> Instruction {
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-        ],
+        accounts: FreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+        }
+        .to_account_metas(),
         data: [
             PhoenixInstruction::CancelMultipleOrdersByIdWithFreeFunds.to_vec(),
             params.try_to_vec().unwrap(),
@@ -546,6 +1243,54 @@ pub fn create_cancel_multiple_orders_by_id_with_free_funds_instruction(
     }
 }
 
+/// Splits `orders` into as many `CancelMultipleOrdersByIdWithFreeFunds` instructions as needed,
+/// preserving their order. Each instruction holds at most `max_orders_per_instruction` orders
+/// (capped at `MAX_CANCELS_PER_BATCH`) and is small enough to fit in a transaction on its own.
+pub fn create_chunked_cancel_multiple_orders_by_id_with_free_funds_instructions(
+    market: &Pubkey,
+    trader: &Pubkey,
+    orders: &[CancelOrderParams],
+    max_orders_per_instruction: usize,
+) -> Vec<Instruction> {
+    chunk_cancel_multiple_orders_by_id(trader, orders, max_orders_per_instruction, |params| {
+        create_cancel_multiple_orders_by_id_with_free_funds_instruction(market, trader, params)
+    })
+}
+
+/// Splits `orders` into chunks of at most `max_orders_per_instruction` orders (capped at
+/// `MAX_CANCELS_PER_BATCH`) and builds one instruction per chunk with `build`. Cancel params have
+/// a fixed encoded size, so the cost of each order is measured once and every chunk is sized so
+/// that its instruction fits in a transaction on its own.
+fn chunk_cancel_multiple_orders_by_id(
+    trader: &Pubkey,
+    orders: &[CancelOrderParams],
+    max_orders_per_instruction: usize,
+    build: impl Fn(&CancelMultipleOrdersByIdParams) -> Instruction,
+) -> Vec<Instruction> {
+    let Some(first) = orders.first() else {
+        return vec![];
+    };
+    let size = |orders: Vec<CancelOrderParams>| {
+        estimate_transaction_size(&[build(&CancelMultipleOrdersByIdParams { orders })], trader)
+    };
+    let empty_size = size(vec![]);
+    let order_size = size(vec![*first]) - empty_size;
+    // The compact-u16 length prefix of the instruction data grows by a byte past 127 bytes.
+    let capacity = MAX_TRANSACTION_SIZE.saturating_sub(empty_size + 1) / order_size;
+    let chunk_size = max_orders_per_instruction
+        .clamp(1, MAX_CANCELS_PER_BATCH)
+        .min(capacity)
+        .max(1);
+    orders
+        .chunks(chunk_size)
+        .map(|chunk| {
+            build(&CancelMultipleOrdersByIdParams {
+                orders: chunk.to_vec(),
+            })
+        })
+        .collect()
+}
+
 pub fn create_reduce_order_with_free_funds_instruction(
     market: &Pubkey,
     trader: &Pubkey,
@@ -553,12 +1298,11 @@ pub fn create_reduce_order_with_free_funds_instruction(
 ) -> Instruction {
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-        ],
+        accounts: FreeFundsAccounts {
+            market: *market,
+            trader: *trader,
+        }
+        .to_account_metas(),
         data: [
             PhoenixInstruction::ReduceOrderWithFreeFunds.to_vec(),
             params.try_to_vec().unwrap(),
@@ -589,6 +1333,88 @@ pub fn create_deposit_funds_instruction(
     )
 }
 
+/// The exact amounts moved by a deposit built from UI amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiDeposit {
+    pub params: DepositParams,
+    /// Base atoms transferred from the trader to the base vault.
+    pub base_atoms: u64,
+    /// Quote atoms transferred from the trader to the quote vault.
+    pub quote_atoms: u64,
+    /// Base atoms of the requested amount that are less than one lot and are not deposited.
+    pub base_atoms_not_deposited: u64,
+    /// Quote atoms of the requested amount that are less than one lot and are not deposited.
+    pub quote_atoms_not_deposited: u64,
+}
+
+/// Builds a deposit of `ui_base` raw base units and `ui_quote` quote units from the trader's
+/// associated token accounts.
+///
+/// Each amount is first rounded down to a whole atom, snapping values within floating point
+/// error of an atom to it (so that 0.3 is 300000 atoms rather than 299999), and then rounded down
+/// to whole lots with integer math, so the deposit never exceeds the requested amount. The
+/// returned `UiDeposit` has the exact number of atoms that will move.
+///
+/// Returns `UiAmountError::ZeroLotSize` if the header is uninitialized.
+pub fn create_deposit_funds_instruction_ui(
+    header: &MarketHeader,
+    market: &Pubkey,
+    trader: &Pubkey,
+    ui_base: f64,
+    ui_quote: f64,
+) -> Result<(UiDeposit, Instruction), UiAmountError> {
+    let requested_base_atoms = ui_amount_to_atoms(
+        ui_base,
+        header.base_params.decimals,
+        RoundingDirection::Down,
+    )?;
+    let requested_quote_atoms = ui_amount_to_atoms(
+        ui_quote,
+        header.quote_params.decimals,
+        RoundingDirection::Down,
+    )?;
+    let base_lot_size = header.get_base_lot_size();
+    let quote_lot_size = header.get_quote_lot_size();
+    if base_lot_size == 0 || quote_lot_size == 0 {
+        return Err(UiAmountError::ZeroLotSize);
+    }
+    let params = DepositParams {
+        quote_lots: requested_quote_atoms / quote_lot_size,
+        base_lots: requested_base_atoms / base_lot_size,
+    };
+    let deposit = UiDeposit {
+        params,
+        base_atoms: params.base_lots * base_lot_size,
+        quote_atoms: params.quote_lots * quote_lot_size,
+        base_atoms_not_deposited: requested_base_atoms % base_lot_size,
+        quote_atoms_not_deposited: requested_quote_atoms % quote_lot_size,
+    };
+    let instruction = create_deposit_funds_instruction(
+        market,
+        trader,
+        &header.base_params.mint_key,
+        &header.quote_params.mint_key,
+        &params,
+    );
+    Ok((deposit, instruction))
+}
+
+/// Converts a UI amount to atoms, rounding in `rounding`.
+fn ui_amount_to_atoms(
+    amount: f64,
+    decimals: u32,
+    rounding: RoundingDirection,
+) -> Result<u64, UiAmountError> {
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(UiAmountError::InvalidAmount(amount));
+    }
+    let atoms = rounding.round(amount * 10f64.powi(decimals as i32));
+    if atoms >= u64::MAX as f64 {
+        return Err(UiAmountError::Overflow(amount));
+    }
+    Ok(atoms as u64)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn create_deposit_funds_instruction_with_custom_token_accounts(
     market: &Pubkey,
@@ -605,18 +1431,17 @@ pub fn create_deposit_funds_instruction_with_custom_token_accounts(
     let ix_data = params.try_to_vec().unwrap();
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new(*seat, false),
-            AccountMeta::new(*base_account, false),
-            AccountMeta::new(*quote_account, false),
-            AccountMeta::new(base_vault, false),
-            AccountMeta::new(quote_vault, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts: SeatedTokenTransferAccounts {
+            market: *market,
+            trader: *trader,
+            seat: *seat,
+            base_account: *base_account,
+            quote_account: *quote_account,
+            base_vault,
+            quote_vault,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(),
         data: [PhoenixInstruction::DepositFunds.to_vec(), ix_data].concat(),
     }
 }
@@ -640,17 +1465,16 @@ fn _phoenix_instruction_template<T: BorshSerialize>(
     };
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new(*base_account, false),
-            AccountMeta::new(*quote_account, false),
-            AccountMeta::new(base_vault, false),
-            AccountMeta::new(quote_vault, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts: TokenTransferAccounts {
+            market: *market,
+            trader: *trader,
+            base_account: *base_account,
+            quote_account: *quote_account,
+            base_vault,
+            quote_vault,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(),
         data: [[ix_id as u8].to_vec(), ix_data].concat(),
     }
 }
@@ -668,17 +1492,16 @@ fn _phoenix_instruction_template_no_param(
     let (quote_vault, _) = get_vault_address(market, quote);
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*trader, true),
-            AccountMeta::new(*base_account, false),
-            AccountMeta::new(*quote_account, false),
-            AccountMeta::new(base_vault, false),
-            AccountMeta::new(quote_vault, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts: TokenTransferAccounts {
+            market: *market,
+            trader: *trader,
+            base_account: *base_account,
+            quote_account: *quote_account,
+            base_vault,
+            quote_vault,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(),
         data: [ix_id as u8].to_vec(),
     }
 }
@@ -843,6 +1666,23 @@ pub fn create_cancel_multiple_orders_by_id_instruction_with_custom_token_account
     )
 }
 
+/// Splits `orders` into as many `CancelMultipleOrdersById` instructions as needed, preserving
+/// their order. Each instruction holds at most `max_orders_per_instruction` orders (capped at
+/// `MAX_CANCELS_PER_BATCH`) and is small enough to fit in a transaction on its own. The freed
+/// funds are withdrawn to the trader's associated token accounts.
+pub fn create_chunked_cancel_multiple_orders_by_id_instructions(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+    orders: &[CancelOrderParams],
+    max_orders_per_instruction: usize,
+) -> Vec<Instruction> {
+    chunk_cancel_multiple_orders_by_id(trader, orders, max_orders_per_instruction, |params| {
+        create_cancel_multiple_orders_by_id_instruction(market, trader, base, quote, params)
+    })
+}
+
 pub fn create_withdraw_funds_instruction(
     market: &Pubkey,
     trader: &Pubkey,
@@ -929,18 +1769,45 @@ pub fn create_withdraw_funds_with_custom_amounts_instruction_with_custom_token_a
     )
 }
 
+/// Builds a withdrawal of all of `trader`'s free funds on `market`, with the amounts read from
+/// `market_state` set explicitly instead of left as `None`. Returns the amounts that will be
+/// withdrawn along with the instruction, or None if the trader is not registered on the market.
+pub fn create_withdraw_all_instruction_from_state(
+    market_state: &dyn Market,
+    header: &MarketHeader,
+    market: &Pubkey,
+    trader: &Pubkey,
+) -> Option<(WithdrawParams, Instruction)> {
+    let trader_state = market_state.get_trader_state(trader)?;
+    let params = WithdrawParams {
+        quote_lots_to_withdraw: Some(trader_state.quote_lots_free.as_u64()),
+        base_lots_to_withdraw: Some(trader_state.base_lots_free.as_u64()),
+    };
+    let base = &header.base_params.mint_key;
+    let quote = &header.quote_params.mint_key;
+    let instruction =
+        create_withdraw_funds_with_custom_amounts_instruction_with_custom_token_accounts(
+            market,
+            trader,
+            &get_associated_token_address(trader, base),
+            &get_associated_token_address(trader, quote),
+            base,
+            quote,
+            &params,
+        );
+    Some((params, instruction))
+}
+
 pub fn create_request_seat_instruction(payer: &Pubkey, market: &Pubkey) -> Instruction {
     let (seat, _) = get_seat_address(market, payer);
     Instruction {
         program_id: crate::id(),
-        accounts: vec![
-            AccountMeta::new_readonly(crate::id(), false),
-            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*payer, true),
-            AccountMeta::new(seat, false),
-            AccountMeta::new_readonly(system_program::id(), false),
-        ],
+        accounts: RequestSeatAccounts {
+            market: *market,
+            payer: *payer,
+            seat,
+        }
+        .to_account_metas(),
         data: PhoenixInstruction::RequestSeat.to_vec(),
     }
 }
@@ -0,0 +1,167 @@
+//! Integrity checks on market account bytes, for refusing to mutate corrupted state.
+
+use crate::dispatch::{get_market_size, load_with_dispatch};
+use crate::enums::Side;
+use crate::market::{FIFOOrderId, MarketSizeParams};
+use crate::raw_book::RawBookTree;
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MarketIntegrityError {
+    #[error("No market layout for size params {bids_size}/{asks_size}/{num_seats}")]
+    UnsupportedSizeParams {
+        bids_size: u64,
+        asks_size: u64,
+        num_seats: u64,
+    },
+    #[error("Market data is {actual} bytes, expected {expected}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Market data could not be read as its layout")]
+    InvalidLayout,
+    #[error("{side:?} tree bump index {bump_index} is past its capacity {capacity}")]
+    BumpIndexOutOfBounds {
+        side: Side,
+        bump_index: u32,
+        capacity: u32,
+    },
+    #[error("{side:?} tree root {root} is not an allocated node")]
+    RootOutOfBounds { side: Side, root: u32 },
+    #[error("{side:?} tree records {recorded} orders but {reachable} are reachable")]
+    SizeMismatch {
+        side: Side,
+        recorded: u64,
+        reachable: u64,
+    },
+    #[error("{side:?} tree reaches order {order_id:?} more than once")]
+    DuplicateOrder { side: Side, order_id: FIFOOrderId },
+    #[error("{side:?} tree holds {order_id:?} out of priority order")]
+    OrderOutOfPriority { side: Side, order_id: FIFOOrderId },
+    #[error("{side:?} tree holds {order_id:?}, which belongs on the other side")]
+    WrongSide { side: Side, order_id: FIFOOrderId },
+    #[error("{side:?} order {order_id:?} has a sequence number at or past the market's next sequence number {next_sequence_number}")]
+    SequenceNumberFromFuture {
+        side: Side,
+        order_id: FIFOOrderId,
+        next_sequence_number: u64,
+    },
+    #[error("{side:?} order {order_id:?} is empty")]
+    EmptyOrder { side: Side, order_id: FIFOOrderId },
+    #[error(
+        "{side:?} order {order_id:?} has trader index {trader_index}, past the {num_seats} seats"
+    )]
+    TraderIndexOutOfBounds {
+        side: Side,
+        order_id: FIFOOrderId,
+        trader_index: u64,
+        num_seats: u64,
+    },
+}
+
+/// Checks that the bytes of a market that follow the header hold consistent book trees: every
+/// recorded order is reachable from the root exactly once, in priority order and on the right
+/// side, with a sequence number the market has already assigned and a valid trader index.
+///
+/// The trees are walked in place with bounded traversals, so corrupted node links cannot cause
+/// panics or infinite loops.
+pub fn check_market_integrity(
+    market_size_params: &MarketSizeParams,
+    bytes: &[u8],
+) -> Result<(), MarketIntegrityError> {
+    let expected =
+        get_market_size(market_size_params).ok_or(MarketIntegrityError::UnsupportedSizeParams {
+            bids_size: market_size_params.bids_size,
+            asks_size: market_size_params.asks_size,
+            num_seats: market_size_params.num_seats,
+        })?;
+    if bytes.len() != expected {
+        return Err(MarketIntegrityError::InvalidLength {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+    let next_sequence_number = load_with_dispatch(market_size_params, bytes)
+        .ok_or(MarketIntegrityError::InvalidLayout)?
+        .inner
+        .get_order_sequence_number();
+
+    for side in [Side::Bid, Side::Ask] {
+        let tree = RawBookTree::from_market_bytes(bytes, market_size_params, side)
+            .ok_or(MarketIntegrityError::InvalidLayout)?;
+        check_tree(
+            &tree,
+            side,
+            next_sequence_number,
+            market_size_params.num_seats,
+        )?;
+    }
+    Ok(())
+}
+
+fn check_tree(
+    tree: &RawBookTree,
+    side: Side,
+    next_sequence_number: u64,
+    num_seats: u64,
+) -> Result<(), MarketIntegrityError> {
+    if tree.bump_index() > tree.capacity().saturating_add(1) {
+        return Err(MarketIntegrityError::BumpIndexOutOfBounds {
+            side,
+            bump_index: tree.bump_index(),
+            capacity: tree.capacity(),
+        });
+    }
+    if tree.root() != 0 && tree.root() >= tree.bump_index() {
+        return Err(MarketIntegrityError::RootOutOfBounds {
+            side,
+            root: tree.root(),
+        });
+    }
+
+    let mut seen = HashSet::new();
+    let mut previous: Option<FIFOOrderId> = None;
+    for (order_id, order) in tree.orders() {
+        if !seen.insert((order_id.price_in_ticks, order_id.order_sequence_number)) {
+            return Err(MarketIntegrityError::DuplicateOrder { side, order_id });
+        }
+        if Side::from_order_sequence_number(order_id.order_sequence_number) != side {
+            return Err(MarketIntegrityError::WrongSide { side, order_id });
+        }
+        if previous.is_some_and(|previous| previous >= order_id) {
+            return Err(MarketIntegrityError::OrderOutOfPriority { side, order_id });
+        }
+        let sequence_number = match side {
+            Side::Bid => !order_id.order_sequence_number,
+            Side::Ask => order_id.order_sequence_number,
+        };
+        if sequence_number >= next_sequence_number {
+            return Err(MarketIntegrityError::SequenceNumberFromFuture {
+                side,
+                order_id,
+                next_sequence_number,
+            });
+        }
+        if order.num_base_lots == 0 {
+            return Err(MarketIntegrityError::EmptyOrder { side, order_id });
+        }
+        // Trader indices are addresses in the trader tree, which start at 1.
+        if order.trader_index == 0 || order.trader_index > num_seats {
+            return Err(MarketIntegrityError::TraderIndexOutOfBounds {
+                side,
+                order_id,
+                trader_index: order.trader_index,
+                num_seats,
+            });
+        }
+        previous = Some(order_id);
+    }
+
+    if seen.len() as u64 != tree.len() {
+        return Err(MarketIntegrityError::SizeMismatch {
+            side,
+            recorded: tree.len(),
+            reachable: seen.len() as u64,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,452 @@
+use crate::enums::Side;
+use crate::market::{FIFOOrderId, FIFORestingOrder, Ladder, LadderOrder};
+use crate::market_metadata::MarketMetadata;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+impl Ladder {
+    /// Returns the levels resting on `side` of the book.
+    pub fn levels(&self, side: Side) -> &[LadderOrder] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Returns the levels an order on `side` would match against: the asks for a bid, and the
+    /// bids for an ask.
+    pub fn opposite_levels(&self, side: Side) -> &[LadderOrder] {
+        match side {
+            Side::Bid => &self.asks,
+            Side::Ask => &self.bids,
+        }
+    }
+
+    /// Returns the levels resting on `side` priced in quote atoms, so that they can be compared
+    /// and aggregated across markets with different tick and lot sizes.
+    pub fn quote_normalized_levels(
+        &self,
+        side: Side,
+        metadata: &MarketMetadata,
+    ) -> Vec<QuoteNormalizedLevel> {
+        self.levels(side)
+            .iter()
+            .map(|level| QuoteNormalizedLevel::from_level(level, metadata))
+            .collect()
+    }
+
+    /// Returns the total notional resting on `side`, in quote atoms.
+    pub fn notional_in_quote_atoms(&self, side: Side, metadata: &MarketMetadata) -> u128 {
+        self.quote_normalized_levels(side, metadata)
+            .iter()
+            .map(|level| level.notional_in_quote_atoms as u128)
+            .sum()
+    }
+
+    /// Returns the total notional resting on `side`, converted from quote atoms by `convert`
+    /// (e.g. to USD, for markets quoted in tokens other than USDC).
+    pub fn notional_in(
+        &self,
+        side: Side,
+        metadata: &MarketMetadata,
+        convert: impl Fn(u64) -> f64,
+    ) -> f64 {
+        self.quote_normalized_levels(side, metadata)
+            .iter()
+            .map(|level| convert(level.notional_in_quote_atoms))
+            .sum()
+    }
+
+    /// Returns the volume weighted average price, in ticks, that an order on `side` would pay
+    /// to fill `num_base_lots` against the ladder. Returns None if the ladder does not have
+    /// enough depth or `num_base_lots` is zero.
+    pub fn average_fill_price_in_ticks(&self, side: Side, num_base_lots: u64) -> Option<f64> {
+        if num_base_lots == 0 {
+            return None;
+        }
+        let mut remaining = num_base_lots;
+        let mut adjusted_quote = 0u128;
+        for level in self.opposite_levels(side) {
+            let filled = remaining.min(level.size_in_base_lots);
+            adjusted_quote += filled as u128 * level.price_in_ticks as u128;
+            remaining -= filled;
+            if remaining == 0 {
+                return Some(adjusted_quote as f64 / num_base_lots as f64);
+            }
+        }
+        None
+    }
+
+    /// Returns the size, in base lots, that an order on `side` could fill at `price_in_ticks` or
+    /// better: the asks at or below the price for a bid, and the bids at or above it for an ask.
+    pub fn size_available_at_or_better(&self, side: Side, price_in_ticks: u64) -> u64 {
+        let levels = self.opposite_levels(side);
+        // Levels are sorted best price first, so the levels within the price form a prefix.
+        let within_price = levels.partition_point(|level| match side {
+            Side::Bid => level.price_in_ticks <= price_in_ticks,
+            Side::Ask => level.price_in_ticks >= price_in_ticks,
+        });
+        levels[..within_price]
+            .iter()
+            .map(|level| level.size_in_base_lots)
+            .sum()
+    }
+
+    /// Returns the price of the last level, in ticks, that an order on `side` would reach to
+    /// fill `num_base_lots`: the limit price needed to fill the size in full. Returns None if
+    /// the ladder does not have enough depth or `num_base_lots` is zero.
+    pub fn worst_price_to_fill(&self, side: Side, num_base_lots: u64) -> Option<u64> {
+        if num_base_lots == 0 {
+            return None;
+        }
+        let mut remaining = num_base_lots;
+        for level in self.opposite_levels(side) {
+            remaining = remaining.saturating_sub(level.size_in_base_lots);
+            if remaining == 0 {
+                return Some(level.price_in_ticks);
+            }
+        }
+        None
+    }
+
+    /// Returns the fair price for a size, in ticks: the midpoint of the average prices needed
+    /// to buy and to sell `num_base_lots`. Returns None if either side lacks the depth.
+    pub fn fair_price_for_size(&self, num_base_lots: u64) -> Option<f64> {
+        let buy_price = self.average_fill_price_in_ticks(Side::Bid, num_base_lots)?;
+        let sell_price = self.average_fill_price_in_ticks(Side::Ask, num_base_lots)?;
+        Some((buy_price + sell_price) / 2.0)
+    }
+
+    /// Renders the top `depth` levels of each side as an aligned text table, with asks above
+    /// bids and each level's size drawn as a bar. Prices and sizes are shown in decimal units.
+    ///
+    /// `width` is the total width of each line. The bars use whatever space is left after the
+    /// price and size columns.
+    pub fn render_ascii(&self, width: usize, depth: usize, metadata: &MarketMetadata) -> String {
+        let price_decimals = decimals_for_increment(metadata.ticks_to_float_price(1));
+        let size_decimals = decimals_for_increment(metadata.raw_to_ui_size(1));
+        let asks = self.asks.iter().take(depth).collect::<Vec<_>>();
+        let bids = self.bids.iter().take(depth).collect::<Vec<_>>();
+        let max_size = asks
+            .iter()
+            .chain(bids.iter())
+            .map(|level| level.size_in_base_lots)
+            .max()
+            .unwrap_or(0);
+
+        let column_width = 14;
+        let bar_width = width.saturating_sub(2 * column_width + 4);
+        let render_level = |label: &str, level: &LadderOrder| {
+            let bar_length = if max_size == 0 {
+                0
+            } else {
+                (level.size_in_base_lots as u128 * bar_width as u128 / max_size as u128) as usize
+            };
+            format!(
+                "{} {:>column_width$.price_decimals$} {:>column_width$.size_decimals$} {}\n",
+                label,
+                metadata.ticks_to_float_price(level.price_in_ticks),
+                metadata.raw_to_ui_size(level.size_in_base_lots),
+                "#".repeat(bar_length.max(1)),
+            )
+        };
+
+        let mut output = String::new();
+        output.push_str(&format!(
+            "  {:>column_width$} {:>column_width$}\n",
+            "price", "size"
+        ));
+        for level in asks.iter().rev() {
+            output.push_str(&render_level("A", level));
+        }
+        output.push_str(&format!("{}\n", "-".repeat(width.max(1))));
+        for level in bids.iter() {
+            output.push_str(&render_level("B", level));
+        }
+        output
+    }
+}
+
+/// A level of the book with details about the orders that compose it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetailedLadderOrder {
+    /// The limit price of the level, in ticks.
+    pub price_in_ticks: u64,
+
+    /// The total quantity at the level, in base lots.
+    pub size_in_base_lots: u64,
+
+    /// The number of resting orders at the level.
+    pub num_orders: u64,
+
+    /// The number of distinct makers with orders at the level.
+    pub num_makers: u64,
+}
+
+impl From<DetailedLadderOrder> for LadderOrder {
+    fn from(level: DetailedLadderOrder) -> Self {
+        LadderOrder {
+            price_in_ticks: level.price_in_ticks,
+            size_in_base_lots: level.size_in_base_lots,
+        }
+    }
+}
+
+/// Representation of an order book that includes order and maker counts for each level.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetailedLadder {
+    pub bids: Vec<DetailedLadderOrder>,
+    pub asks: Vec<DetailedLadderOrder>,
+}
+
+impl From<DetailedLadder> for Ladder {
+    fn from(ladder: DetailedLadder) -> Self {
+        Ladder {
+            bids: ladder.bids.into_iter().map(LadderOrder::from).collect(),
+            asks: ladder.asks.into_iter().map(LadderOrder::from).collect(),
+        }
+    }
+}
+
+/// The size a single maker has resting at a level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakerLevelSize {
+    #[serde(with = "crate::pubkey_serde")]
+    pub maker: Pubkey,
+
+    /// The maker's total quantity at the level, in base lots.
+    pub size_in_base_lots: u64,
+
+    /// The number of the maker's orders at the level.
+    pub num_orders: u64,
+}
+
+/// A level of the book broken down by the makers with orders at it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakerLadderOrder {
+    /// The limit price of the level, in ticks.
+    pub price_in_ticks: u64,
+
+    /// The total quantity at the level, in base lots.
+    pub size_in_base_lots: u64,
+
+    /// The number of resting orders at the level.
+    pub num_orders: u64,
+
+    /// The makers at the level, in the time priority of their first order.
+    pub makers: Vec<MakerLevelSize>,
+}
+
+impl MakerLadderOrder {
+    /// Returns the size `maker` has resting at the level, in base lots.
+    pub fn size_for_maker(&self, maker: &Pubkey) -> u64 {
+        self.makers
+            .iter()
+            .find(|level| level.maker == *maker)
+            .map(|level| level.size_in_base_lots)
+            .unwrap_or(0)
+    }
+}
+
+impl From<&MakerLadderOrder> for DetailedLadderOrder {
+    fn from(level: &MakerLadderOrder) -> Self {
+        DetailedLadderOrder {
+            price_in_ticks: level.price_in_ticks,
+            size_in_base_lots: level.size_in_base_lots,
+            num_orders: level.num_orders,
+            num_makers: level.makers.len() as u64,
+        }
+    }
+}
+
+/// Representation of an order book that breaks each level down by maker.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakerLadder {
+    pub bids: Vec<MakerLadderOrder>,
+    pub asks: Vec<MakerLadderOrder>,
+}
+
+impl MakerLadder {
+    pub fn levels(&self, side: Side) -> &[MakerLadderOrder] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+}
+
+impl From<&MakerLadder> for DetailedLadder {
+    fn from(ladder: &MakerLadder) -> Self {
+        DetailedLadder {
+            bids: ladder.bids.iter().map(DetailedLadderOrder::from).collect(),
+            asks: ladder.asks.iter().map(DetailedLadderOrder::from).collect(),
+        }
+    }
+}
+
+impl From<&MakerLadder> for Ladder {
+    fn from(ladder: &MakerLadder) -> Self {
+        DetailedLadder::from(ladder).into()
+    }
+}
+
+/// Groups orders, given in book order, into at most `levels` price levels. Each level is started
+/// with `new_level` and every order at its price is folded in with `add`.
+pub(crate) fn aggregate_by_price<T, L>(
+    orders: impl IntoIterator<Item = T>,
+    levels: u64,
+    price_in_ticks: impl Fn(&T) -> u64,
+    new_level: impl Fn(u64) -> L,
+    mut add: impl FnMut(&mut L, T),
+) -> Vec<L> {
+    let mut book: Vec<(u64, L)> = vec![];
+    if levels == 0 {
+        return vec![];
+    }
+    for order in orders {
+        let price = price_in_ticks(&order);
+        if book.last().map(|(last, _)| *last) != Some(price) {
+            if book.len() as u64 == levels {
+                break;
+            }
+            book.push((price, new_level(price)));
+        }
+        let (_, level) = book.last_mut().unwrap();
+        add(level, order);
+    }
+    book.into_iter().map(|(_, level)| level).collect()
+}
+
+/// Aggregates orders, in book order and tagged with their maker, into at most `levels` levels.
+pub(crate) fn aggregate_maker_levels(
+    orders: impl IntoIterator<Item = (FIFOOrderId, FIFORestingOrder, Pubkey)>,
+    levels: u64,
+) -> Vec<MakerLadderOrder> {
+    aggregate_by_price(
+        orders,
+        levels,
+        |(order_id, _, _)| order_id.price_in_ticks,
+        |price_in_ticks| MakerLadderOrder {
+            price_in_ticks,
+            size_in_base_lots: 0,
+            num_orders: 0,
+            makers: vec![],
+        },
+        |level, (_, order, maker)| {
+            level.size_in_base_lots += order.num_base_lots;
+            level.num_orders += 1;
+            match level.makers.iter_mut().find(|size| size.maker == maker) {
+                Some(size) => {
+                    size.size_in_base_lots += order.num_base_lots;
+                    size.num_orders += 1;
+                }
+                None => level.makers.push(MakerLevelSize {
+                    maker,
+                    size_in_base_lots: order.num_base_lots,
+                    num_orders: 1,
+                }),
+            }
+        },
+    )
+}
+
+/// A ladder level priced in quote atoms. The values are computed in 128 bits and saturate at
+/// `u64::MAX` if they do not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteNormalizedLevel {
+    /// The price of the level, in quote atoms per base unit.
+    pub price_in_quote_atoms_per_base_unit: u64,
+
+    /// The size resting at the level, in base atoms.
+    pub size_in_base_atoms: u64,
+
+    /// The notional resting at the level, in quote atoms (rounded down).
+    pub notional_in_quote_atoms: u64,
+}
+
+impl QuoteNormalizedLevel {
+    pub fn from_level(level: &LadderOrder, metadata: &MarketMetadata) -> Self {
+        let price_in_quote_atoms_per_base_unit =
+            level.price_in_ticks as u128 * metadata.tick_size_in_quote_atoms_per_base_unit as u128;
+        let notional_in_quote_atoms = price_in_quote_atoms_per_base_unit
+            .checked_mul(level.size_in_base_lots as u128)
+            .map(|adjusted| adjusted / metadata.base_lots_per_base_unit.max(1) as u128)
+            .unwrap_or(u128::MAX);
+        Self {
+            price_in_quote_atoms_per_base_unit: saturate(price_in_quote_atoms_per_base_unit),
+            size_in_base_atoms: saturate(
+                level.size_in_base_lots as u128 * metadata.base_atoms_per_base_lot as u128,
+            ),
+            notional_in_quote_atoms: saturate(notional_in_quote_atoms),
+        }
+    }
+}
+
+fn saturate(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// A single point on a depth chart curve, in UI units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DepthChartPoint {
+    /// The price of the level, in quote units per base unit.
+    pub price: f64,
+
+    /// The size resting at this level, in base units.
+    pub size: f64,
+
+    /// The total size resting at this level and every more aggressive level, in base units.
+    pub cumulative_size: f64,
+
+    /// The total notional resting at this level and every more aggressive level, in quote units.
+    pub cumulative_notional: f64,
+}
+
+/// Cumulative bid and ask curves for rendering a depth chart.
+///
+/// Both curves start at the top of the book and move away from the spread, so bids are sorted
+/// by descending price and asks by ascending price.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DepthChartData {
+    pub bids: Vec<DepthChartPoint>,
+    pub asks: Vec<DepthChartPoint>,
+}
+
+impl DepthChartData {
+    pub fn from_ladder(ladder: &Ladder, metadata: &MarketMetadata) -> Self {
+        let to_curve = |levels: &[LadderOrder]| {
+            let mut cumulative_size = 0.0;
+            let mut cumulative_notional = 0.0;
+            levels
+                .iter()
+                .map(|level| {
+                    let price = metadata.ticks_to_float_price(level.price_in_ticks);
+                    let size = metadata.raw_to_ui_size(level.size_in_base_lots);
+                    cumulative_size += size;
+                    cumulative_notional += price * size;
+                    DepthChartPoint {
+                        price,
+                        size,
+                        cumulative_size,
+                        cumulative_notional,
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        Self {
+            bids: to_curve(&ladder.bids),
+            asks: to_curve(&ladder.asks),
+        }
+    }
+}
+
+/// Returns the number of decimal places needed to display multiples of `increment` exactly.
+fn decimals_for_increment(increment: f64) -> usize {
+    let mut decimals = 0;
+    let mut scaled = increment;
+    while decimals < 12 && (scaled - scaled.round()).abs() > 1e-9 * scaled.abs().max(1.0) {
+        scaled *= 10.0;
+        decimals += 1;
+    }
+    decimals
+}
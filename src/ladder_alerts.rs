@@ -0,0 +1,175 @@
+use crate::book_health::spread_bps;
+use crate::enums::Side;
+use crate::market::Ladder;
+use serde::{Deserialize, Serialize};
+
+/// A minimum amount of liquidity near the mid price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthFloor {
+    /// How far from the mid price liquidity counts, in basis points.
+    pub within_bps: u64,
+    pub min_base_lots: u64,
+}
+
+/// The conditions a `LadderAlerts` watches for. Conditions left empty are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LadderAlertConfig {
+    /// Prices, in ticks, that fire an alert when the best bid or best ask crosses them.
+    pub price_thresholds_in_ticks: Vec<u64>,
+    pub max_spread_bps: Option<u64>,
+    /// Applied to each side of the book separately.
+    pub depth_floor: Option<DepthFloor>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossDirection {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LadderAlert {
+    /// The best price of `side` moved across a threshold between two ladders.
+    PriceCrossed {
+        side: Side,
+        threshold_in_ticks: u64,
+        direction: CrossDirection,
+        previous_in_ticks: u64,
+        current_in_ticks: u64,
+    },
+    /// The spread became wider than `max_spread_bps`, or the book became crossed or one sided.
+    SpreadAboveLimit {
+        /// None if the book is crossed or a side is empty.
+        spread_bps: Option<u64>,
+    },
+    /// The liquidity of `side` near the mid price fell below the floor.
+    DepthBelowFloor { side: Side, base_lots: u64 },
+}
+
+type AlertCallback = Box<dyn FnMut(&LadderAlert)>;
+
+/// Watches successive ladders of a market and fires alerts when the conditions of a
+/// `LadderAlertConfig` are met.
+///
+/// Spread and depth alerts are edge triggered: they fire when a condition starts to hold and
+/// again only after it has cleared.
+pub struct LadderAlerts {
+    config: LadderAlertConfig,
+    previous_best: Option<(Option<u64>, Option<u64>)>,
+    spread_breached: bool,
+    depth_breached: [bool; 2],
+    callbacks: Vec<AlertCallback>,
+}
+
+impl LadderAlerts {
+    pub fn new(config: LadderAlertConfig) -> Self {
+        Self {
+            config,
+            previous_best: None,
+            spread_breached: false,
+            depth_breached: [false; 2],
+            callbacks: vec![],
+        }
+    }
+
+    pub fn config(&self) -> &LadderAlertConfig {
+        &self.config
+    }
+
+    /// Registers a callback invoked with every alert fired by `update`.
+    pub fn on_alert(&mut self, callback: impl FnMut(&LadderAlert) + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Checks the next ladder of the market, invokes the callbacks and returns the alerts fired.
+    pub fn update(&mut self, ladder: &Ladder) -> Vec<LadderAlert> {
+        let mut alerts = vec![];
+        let best_bid = ladder.bids.first().map(|level| level.price_in_ticks);
+        let best_ask = ladder.asks.first().map(|level| level.price_in_ticks);
+
+        if let Some((previous_bid, previous_ask)) = self.previous_best {
+            for (side, previous, current) in [
+                (Side::Bid, previous_bid, best_bid),
+                (Side::Ask, previous_ask, best_ask),
+            ] {
+                let (previous, current) = match (previous, current) {
+                    (Some(previous), Some(current)) => (previous, current),
+                    _ => continue,
+                };
+                for &threshold in self.config.price_thresholds_in_ticks.iter() {
+                    let direction = if previous < threshold && current >= threshold {
+                        CrossDirection::Up
+                    } else if previous >= threshold && current < threshold {
+                        CrossDirection::Down
+                    } else {
+                        continue;
+                    };
+                    alerts.push(LadderAlert::PriceCrossed {
+                        side,
+                        threshold_in_ticks: threshold,
+                        direction,
+                        previous_in_ticks: previous,
+                        current_in_ticks: current,
+                    });
+                }
+            }
+        }
+        self.previous_best = Some((best_bid, best_ask));
+
+        if let Some(max_spread_bps) = self.config.max_spread_bps {
+            let spread = match (best_bid, best_ask) {
+                (Some(bid), Some(ask)) if bid < ask => Some(spread_bps(bid, ask)),
+                _ => None,
+            };
+            let breached = spread.is_none_or(|spread| spread > max_spread_bps);
+            if breached && !self.spread_breached {
+                alerts.push(LadderAlert::SpreadAboveLimit { spread_bps: spread });
+            }
+            self.spread_breached = breached;
+        }
+
+        if let Some(floor) = self.config.depth_floor {
+            for (i, side) in [Side::Bid, Side::Ask].into_iter().enumerate() {
+                let base_lots = depth_near_mid(ladder, side, floor.within_bps);
+                let breached = base_lots < floor.min_base_lots;
+                if breached && !self.depth_breached[i] {
+                    alerts.push(LadderAlert::DepthBelowFloor { side, base_lots });
+                }
+                self.depth_breached[i] = breached;
+            }
+        }
+
+        for alert in alerts.iter() {
+            for callback in self.callbacks.iter_mut() {
+                callback(alert);
+            }
+        }
+        alerts
+    }
+}
+
+/// Returns the base lots on `side` priced within `within_bps` of the mid price. Without a mid
+/// price, the best price of the side is used instead.
+fn depth_near_mid(ladder: &Ladder, side: Side, within_bps: u64) -> u64 {
+    let levels = match side {
+        Side::Bid => &ladder.bids,
+        Side::Ask => &ladder.asks,
+    };
+    // Twice the reference price, to keep the mid price whole.
+    let reference = match (ladder.bids.first(), ladder.asks.first(), levels.first()) {
+        (Some(bid), Some(ask), _) => bid.price_in_ticks as u128 + ask.price_in_ticks as u128,
+        (_, _, Some(best)) => 2 * best.price_in_ticks as u128,
+        (_, _, None) => return 0,
+    };
+    levels
+        .iter()
+        .take_while(|level| {
+            let price = 20_000 * level.price_in_ticks as u128;
+            match side {
+                Side::Bid => price >= reference * 10_000u128.saturating_sub(within_bps as u128),
+                Side::Ask => price <= reference * (10_000 + within_bps as u128),
+            }
+        })
+        .map(|level| level.size_in_base_lots)
+        .sum()
+}
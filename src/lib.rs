@@ -1,9 +1,69 @@
+#[cfg(feature = "on-chain")]
+pub mod account_info;
+pub mod accounts;
+pub mod book_health;
+pub mod book_recorder;
+pub mod cached_market;
+pub mod canonical_json;
+#[cfg(feature = "compression")]
+pub mod compressed_account;
+pub mod conformance;
+pub mod cpi;
+pub mod delegated_trader;
 pub mod dispatch;
 pub mod enums;
+pub mod event_key;
+pub mod event_stream;
+pub mod event_views;
 pub mod events;
+pub mod expiry;
+pub mod fees;
+pub mod fill_math;
 pub mod instructions;
+pub mod integrity;
+pub mod ladder;
+pub mod ladder_alerts;
+pub mod liquidity_metrics;
+pub mod log_verification;
 pub mod market;
+pub mod market_comparison;
+pub mod market_metadata;
+pub mod market_params;
+pub mod market_rules;
+pub mod onboarding;
+pub mod order_flow;
+pub mod order_ids;
 pub mod order_packet;
+pub mod phoenix_order;
+pub mod prelude;
+pub mod price_source;
+#[cfg(feature = "program-test")]
+pub mod program_test;
+pub mod pubkey_serde;
+pub mod raw_book;
+pub mod reconcile;
+#[cfg(feature = "rpc")]
+pub mod remote_market;
+pub mod rounding;
+pub mod routing;
+#[cfg(feature = "rows")]
+pub mod rows;
+#[cfg(feature = "rpc")]
+pub mod rpc_filters;
+pub mod sanitize;
+pub mod sdk_types;
+pub mod simulation;
+pub mod snapshot;
+pub mod synthetic;
+pub mod trader_index;
+pub mod trader_journal;
+pub mod trader_risk;
+pub mod transaction;
+pub mod typed_instructions;
+pub mod u64_string;
+pub mod ui_format;
+pub mod units;
+pub mod wire_compat;
 
 // You need to import Pubkey prior to using the declare_id macro
 use ellipsis_macros::declare_id;
@@ -0,0 +1,203 @@
+use crate::enums::Side;
+use crate::snapshot::MarketSnapshot;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Time-weighted liquidity provided by a single maker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MakerLiquidityMetrics {
+    /// Average size of the maker's bids within the depth band, in base lots.
+    pub average_bid_depth_in_base_lots: f64,
+
+    /// Average size of the maker's asks within the depth band, in base lots.
+    pub average_ask_depth_in_base_lots: f64,
+
+    /// Fraction of the time the maker quoted both sides within the depth band.
+    pub two_sided_uptime: f64,
+}
+
+/// Time-weighted liquidity of a market over a series of snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityMetrics {
+    /// The total time covered by the metrics, in seconds.
+    pub elapsed_seconds: u64,
+
+    /// Average spread in basis points of the mid price, over the time the book was two-sided.
+    pub average_spread_bps: Option<f64>,
+
+    /// Fraction of the time the book had both bids and asks.
+    pub two_sided_uptime: f64,
+
+    /// Average size of all bids within the depth band, in base lots.
+    pub average_bid_depth_in_base_lots: f64,
+
+    /// Average size of all asks within the depth band, in base lots.
+    pub average_ask_depth_in_base_lots: f64,
+
+    /// Metrics for each maker. Orders whose maker is not a registered trader count towards the
+    /// market's depth but are not attributed to any maker.
+    #[serde(with = "crate::pubkey_serde::map")]
+    pub makers: HashMap<Pubkey, MakerLiquidityMetrics>,
+}
+
+/// The liquidity of a single snapshot, held until the next snapshot arrives.
+#[derive(Debug, Clone, Default)]
+struct Observation {
+    spread_bps: Option<f64>,
+    bid_depth: u64,
+    ask_depth: u64,
+    makers: HashMap<Pubkey, (u64, u64)>,
+}
+
+/// Accumulates time-weighted liquidity metrics from timestamped snapshots of one market.
+///
+/// Each snapshot is assumed to describe the book until the next one is observed, and depth is
+/// measured within `depth_bps` basis points of the mid price, the way liquidity programs
+/// typically score market makers.
+#[derive(Debug, Clone)]
+pub struct LiquidityMetricsAccumulator {
+    depth_bps: u64,
+    last: Option<(i64, Observation)>,
+    elapsed: u64,
+    two_sided_time: u64,
+    spread_bps_time: f64,
+    bid_depth_time: f64,
+    ask_depth_time: f64,
+    makers: HashMap<Pubkey, MakerTotals>,
+}
+
+/// Time-weighted sums for a single maker.
+#[derive(Debug, Clone, Copy, Default)]
+struct MakerTotals {
+    bid_depth_time: f64,
+    ask_depth_time: f64,
+    two_sided_time: u64,
+}
+
+impl LiquidityMetricsAccumulator {
+    pub fn new(depth_bps: u64) -> Self {
+        Self {
+            depth_bps,
+            last: None,
+            elapsed: 0,
+            two_sided_time: 0,
+            spread_bps_time: 0.0,
+            bid_depth_time: 0.0,
+            ask_depth_time: 0.0,
+            makers: HashMap::new(),
+        }
+    }
+
+    /// Records the state of the book at `unix_timestamp`. Snapshots older than the last one
+    /// observed are ignored.
+    pub fn observe(&mut self, unix_timestamp: i64, snapshot: &MarketSnapshot) {
+        if matches!(self.last, Some((last_timestamp, _)) if unix_timestamp < last_timestamp) {
+            return;
+        }
+        self.close_interval(unix_timestamp);
+        self.last = Some((unix_timestamp, self.measure(snapshot)));
+    }
+
+    /// Returns the metrics up to `unix_timestamp`, treating the last snapshot as still current.
+    pub fn metrics_at(&self, unix_timestamp: i64) -> LiquidityMetrics {
+        let mut accumulator = self.clone();
+        accumulator.close_interval(unix_timestamp);
+        accumulator.metrics()
+    }
+
+    /// Returns the metrics up to the last observed snapshot.
+    pub fn metrics(&self) -> LiquidityMetrics {
+        let elapsed = self.elapsed.max(1) as f64;
+        LiquidityMetrics {
+            elapsed_seconds: self.elapsed,
+            average_spread_bps: (self.two_sided_time > 0)
+                .then(|| self.spread_bps_time / self.two_sided_time as f64),
+            two_sided_uptime: self.two_sided_time as f64 / elapsed,
+            average_bid_depth_in_base_lots: self.bid_depth_time / elapsed,
+            average_ask_depth_in_base_lots: self.ask_depth_time / elapsed,
+            makers: self
+                .makers
+                .iter()
+                .map(|(maker, totals)| {
+                    (
+                        *maker,
+                        MakerLiquidityMetrics {
+                            average_bid_depth_in_base_lots: totals.bid_depth_time / elapsed,
+                            average_ask_depth_in_base_lots: totals.ask_depth_time / elapsed,
+                            two_sided_uptime: totals.two_sided_time as f64 / elapsed,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Credits the last observation with the time until `unix_timestamp`.
+    fn close_interval(&mut self, unix_timestamp: i64) {
+        let Some((last_timestamp, observation)) = self.last.as_mut() else {
+            return;
+        };
+        let dt = unix_timestamp.saturating_sub(*last_timestamp).max(0) as u64;
+        *last_timestamp = unix_timestamp;
+        if dt == 0 {
+            return;
+        }
+        self.elapsed += dt;
+        if let Some(spread_bps) = observation.spread_bps {
+            self.two_sided_time += dt;
+            self.spread_bps_time += spread_bps * dt as f64;
+        }
+        self.bid_depth_time += observation.bid_depth as f64 * dt as f64;
+        self.ask_depth_time += observation.ask_depth as f64 * dt as f64;
+        for (maker, (bid_depth, ask_depth)) in observation.makers.iter() {
+            let totals = self.makers.entry(*maker).or_default();
+            totals.bid_depth_time += *bid_depth as f64 * dt as f64;
+            totals.ask_depth_time += *ask_depth as f64 * dt as f64;
+            if *bid_depth > 0 && *ask_depth > 0 {
+                totals.two_sided_time += dt;
+            }
+        }
+    }
+
+    fn measure(&self, snapshot: &MarketSnapshot) -> Observation {
+        let (Some((best_bid, _)), Some((best_ask, _))) =
+            (snapshot.bids.first(), snapshot.asks.first())
+        else {
+            return Observation::default();
+        };
+        let mid = (best_bid.price_in_ticks as f64 + best_ask.price_in_ticks as f64) / 2.0;
+        let band = mid * self.depth_bps as f64 / 10_000.0;
+        let mut observation = Observation {
+            spread_bps: Some(
+                (best_ask
+                    .price_in_ticks
+                    .saturating_sub(best_bid.price_in_ticks)) as f64
+                    / mid
+                    * 10_000.0,
+            ),
+            ..Default::default()
+        };
+        for side in [Side::Bid, Side::Ask] {
+            for (order_id, order) in snapshot.get_book(side) {
+                if (order_id.price_in_ticks as f64 - mid).abs() > band {
+                    break;
+                }
+                match side {
+                    Side::Bid => observation.bid_depth += order.num_base_lots,
+                    Side::Ask => observation.ask_depth += order.num_base_lots,
+                }
+                let Some(maker) = snapshot.get_trader_id_from_index(order.trader_index as u32)
+                else {
+                    continue;
+                };
+                let maker_depth = observation.makers.entry(maker).or_default();
+                match side {
+                    Side::Bid => maker_depth.0 += order.num_base_lots,
+                    Side::Ask => maker_depth.1 += order.num_base_lots,
+                }
+            }
+        }
+        observation
+    }
+}
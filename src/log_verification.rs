@@ -0,0 +1,135 @@
+//! Verification that `Log` instructions were emitted by Phoenix, so that consumers can reject
+//! events forged by other programs in the same transaction.
+//!
+//! Any program can invoke an instruction whose data looks like a Phoenix log, but only Phoenix
+//! can sign for its log authority PDA. A genuine log is a Phoenix instruction, invoked by
+//! Phoenix itself, whose first account is the log authority.
+//!
+//! The log authority is also a signer of genuine logs, but that flag is only meaningful
+//! on-chain: inner instructions rebuilt from RPC transaction data do not carry signer
+//! privileges, so the signer check is off unless enabled with `require_signer`.
+
+use crate::events::{parse_log_instruction_data, AuditLogHeader, MarketEvent};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum LogVerificationError {
+    #[error("Log was sent to program {0}, not the expected program")]
+    WrongProgram(Pubkey),
+    #[error("Log was invoked by program {0}, not the expected program")]
+    WrongInvoker(Pubkey),
+    #[error("Log has no log authority account")]
+    MissingLogAuthority,
+    #[error("Log authority {0} is not the expected log authority")]
+    WrongLogAuthority(Pubkey),
+    #[error("Log authority did not sign the log")]
+    LogAuthorityNotSigner,
+    #[error("Instruction data is not a log")]
+    InvalidLogData,
+}
+
+/// Checks `Log` instructions against a program id and the log authority derived from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogVerifier {
+    program_id: Pubkey,
+    log_authority: Pubkey,
+    require_signer: bool,
+}
+
+impl Default for LogVerifier {
+    fn default() -> Self {
+        Self {
+            program_id: crate::id(),
+            log_authority: crate::phoenix_log_authority::id(),
+            require_signer: false,
+        }
+    }
+}
+
+impl LogVerifier {
+    /// Returns a verifier for the Phoenix program and its log authority.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a verifier for a deployment of Phoenix at `program_id`, deriving its log
+    /// authority from the `log` seed.
+    pub fn with_program_id(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            log_authority: Pubkey::find_program_address(&[b"log"], &program_id).0,
+            require_signer: false,
+        }
+    }
+
+    /// Sets whether the log authority must be marked as a signer. Off by default; enable it only
+    /// for instructions seen on-chain, such as in a CPI, where signer privileges are reported.
+    pub fn require_signer(self, require_signer: bool) -> Self {
+        Self {
+            require_signer,
+            ..self
+        }
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    pub fn log_authority(&self) -> Pubkey {
+        self.log_authority
+    }
+
+    /// Checks that `instruction` was sent to the program with its log authority as the first
+    /// account, and that the authority signed if `require_signer` is set.
+    pub fn verify(&self, instruction: &Instruction) -> Result<(), LogVerificationError> {
+        if instruction.program_id != self.program_id {
+            return Err(LogVerificationError::WrongProgram(instruction.program_id));
+        }
+        let authority = instruction
+            .accounts
+            .first()
+            .ok_or(LogVerificationError::MissingLogAuthority)?;
+        if authority.pubkey != self.log_authority {
+            return Err(LogVerificationError::WrongLogAuthority(authority.pubkey));
+        }
+        if self.require_signer && !authority.is_signer {
+            return Err(LogVerificationError::LogAuthorityNotSigner);
+        }
+        Ok(())
+    }
+
+    /// Checks `instruction` like `verify`, and that it was invoked by the program itself, as
+    /// every genuine log is.
+    pub fn verify_invoked_by(
+        &self,
+        instruction: &Instruction,
+        invoking_program: &Pubkey,
+    ) -> Result<(), LogVerificationError> {
+        if *invoking_program != self.program_id {
+            return Err(LogVerificationError::WrongInvoker(*invoking_program));
+        }
+        self.verify(instruction)
+    }
+
+    /// Verifies `instruction` and parses its header and events.
+    pub fn parse(
+        &self,
+        instruction: &Instruction,
+    ) -> Result<(AuditLogHeader, Vec<MarketEvent>), LogVerificationError> {
+        self.verify(instruction)?;
+        parse_log_instruction_data(&instruction.data).ok_or(LogVerificationError::InvalidLogData)
+    }
+
+    /// Returns the header and events of every verified log in `instructions`, skipping
+    /// instructions that fail verification or are not logs.
+    pub fn verified_logs<'a>(
+        &'a self,
+        instructions: impl IntoIterator<Item = &'a Instruction> + 'a,
+    ) -> impl Iterator<Item = (AuditLogHeader, Vec<MarketEvent>)> + 'a {
+        instructions
+            .into_iter()
+            .filter_map(move |instruction| self.parse(instruction).ok())
+    }
+}
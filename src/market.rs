@@ -1,10 +1,20 @@
 use crate::enums::Side;
+use crate::ladder::{aggregate_by_price, aggregate_maker_levels, DetailedLadder, MakerLadder};
+use crate::market_metadata::MarketMetadata;
+use crate::rounding::RoundingDirection;
+use crate::ui_format::{MarketContext, UiFormat};
+use crate::units::{
+    BaseAtoms, BaseLots, BaseLotsPerBaseUnit, QuoteAtoms, QuoteLots, QuoteLotsPerBaseUnitPerTick,
+    Ticks,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 use serde::{Deserialize, Serialize};
 use sokoban::node_allocator::{NodeAllocatorMap, OrderedNodeAllocatorMap, ZeroCopy, SENTINEL};
 use sokoban::RedBlackTree;
 use solana_sdk::pubkey::Pubkey;
+use std::any::Any;
+use std::borrow::Borrow;
 use std::fmt::Display;
 
 #[cfg(feature = "pyo3")]
@@ -58,6 +68,16 @@ impl LadderOrder {
     }
 }
 
+impl LadderOrder {
+    pub fn price(&self) -> Ticks {
+        Ticks(self.price_in_ticks)
+    }
+
+    pub fn size(&self) -> BaseLots {
+        BaseLots(self.size_in_base_lots)
+    }
+}
+
 /// Representation of an order book.
 #[cfg_attr(feature = "pyo3", pyclass(get_all, set_all))]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -82,63 +102,275 @@ impl Ladder {
     }
 }
 
+/// Aggregate liquidity resting on one side of the book.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SideTotals {
+    /// The number of resting orders.
+    pub num_orders: u64,
+
+    /// The total size of the resting orders, in base lots.
+    pub total_base_lots: u64,
+
+    /// The total notional of the resting orders, in quote lots (rounded down).
+    /// Use `MarketMetadata::quote_lots_to_ui_amount` to convert to quote units.
+    pub total_quote_lots: u64,
+}
+
+/// The liquidity resting ahead of an order in the book's price-time priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuePosition {
+    /// The number of orders at the same price placed before the order.
+    pub orders_ahead_at_level: u64,
+
+    /// The size of the orders at the same price placed before the order, in base lots.
+    pub base_lots_ahead_at_level: u64,
+
+    /// The size resting at more aggressive prices, in base lots.
+    pub base_lots_at_better_prices: u64,
+}
+
+impl QueuePosition {
+    /// Returns the total size that must trade before the order is filled, in base lots.
+    pub fn total_base_lots_ahead(&self) -> u64 {
+        self.base_lots_ahead_at_level + self.base_lots_at_better_prices
+    }
+}
+
+/// Returns the liquidity resting ahead of `order_id`, or None if the order is not on the book.
+pub fn queue_position(market: &dyn Market, order_id: &FIFOOrderId) -> Option<QueuePosition> {
+    let side = Side::from_order_sequence_number(order_id.order_sequence_number);
+    let mut position = QueuePosition::default();
+    for (id, order) in market.get_book(side).iter() {
+        if id == order_id {
+            return Some(position);
+        }
+        if id.price_in_ticks == order_id.price_in_ticks {
+            position.orders_ahead_at_level += 1;
+            position.base_lots_ahead_at_level += order.num_base_lots;
+        } else if side.is_price_more_aggressive(id.price_in_ticks, order_id.price_in_ticks) {
+            position.base_lots_at_better_prices += order.num_base_lots;
+        } else {
+            return None;
+        }
+    }
+    None
+}
+
 pub trait Market {
     fn get_ladder(&self, levels: u64) -> Ladder {
-        let mut bids = vec![];
-        let mut asks = vec![];
+        build_ladder(self, levels, |_| true)
+    }
 
-        if levels == 0 {
-            return Ladder { bids, asks };
+    /// Returns the top `levels` levels of each side, leaving out the orders placed by `trader`.
+    /// If the trader is not registered on the market, this is the same as `get_ladder`.
+    fn get_ladder_excluding_trader(&self, levels: u64, trader: &Pubkey) -> Ladder {
+        match self.get_trader_index(trader) {
+            Some(trader_index) => build_ladder(self, levels, |order| {
+                order.trader_index != trader_index as u64
+            }),
+            None => self.get_ladder(levels),
         }
+    }
 
-        for (side, book) in [(Side::Bid, &mut bids), (Side::Ask, &mut asks)].iter_mut() {
-            for (key, order) in self.get_book(*side).iter() {
-                let price = key.price_in_ticks;
-                let size = order.num_base_lots;
-                if book.is_empty() {
-                    book.push(LadderOrder {
-                        price_in_ticks: price,
-                        size_in_base_lots: size,
-                    });
-                } else {
-                    let last = book.last().unwrap();
-                    if last.price_in_ticks == price {
-                        book.last_mut().unwrap().size_in_base_lots += size;
-                    } else {
-                        if book.len() as u64 == levels {
-                            break;
-                        }
-                        book.push(LadderOrder {
-                            price_in_ticks: price,
-                            size_in_base_lots: size,
-                        });
-                    }
-                }
-            }
+    /// Looks up a resting order by its id. The side of the book is derived from the order's
+    /// sequence number.
+    fn get_order(&self, order_id: &FIFOOrderId) -> Option<&FIFORestingOrder> {
+        self.get_book(Side::from_order_sequence_number(
+            order_id.order_sequence_number,
+        ))
+        .get(order_id)
+    }
+
+    /// Returns true if the order is still resting on the book.
+    fn order_exists(&self, order_id: &FIFOOrderId) -> bool {
+        self.get_order(order_id).is_some()
+    }
+
+    /// Returns the id the program will assign to the next order placed on `side` at
+    /// `price_in_ticks`, if it rests on the book. Bid sequence numbers have their bits inverted.
+    ///
+    /// Every order placed on the market takes a sequence number, so the prediction only holds
+    /// if no other order is placed first.
+    fn next_order_id(&self, side: Side, price_in_ticks: Ticks) -> FIFOOrderId {
+        let order_sequence_number = match side {
+            Side::Bid => !self.get_order_sequence_number(),
+            Side::Ask => self.get_order_sequence_number(),
+        };
+        FIFOOrderId::new(price_in_ticks.as_u64(), order_sequence_number)
+    }
+
+    /// Returns the price of the best order on `side`, or None if that side is empty.
+    fn best_price(&self, side: Side) -> Option<Ticks> {
+        self.get_book(side)
+            .iter()
+            .next()
+            .map(|(order_id, _)| order_id.price())
+    }
+
+    /// Returns the orders resting at a single price level, in time priority.
+    fn get_orders_at_price(
+        &self,
+        side: Side,
+        price_in_ticks: Ticks,
+    ) -> Vec<(FIFOOrderId, FIFORestingOrder)> {
+        let price_in_ticks = price_in_ticks.as_u64();
+        self.get_book(side)
+            .iter()
+            .skip_while(|(order_id, _)| {
+                side.is_price_more_aggressive(order_id.price_in_ticks, price_in_ticks)
+            })
+            .take_while(|(order_id, _)| order_id.price_in_ticks == price_in_ticks)
+            .map(|(order_id, order)| (*order_id, *order))
+            .collect()
+    }
+
+    /// Returns the orders `trader` has resting on the book, bids first, each side in priority
+    /// order. Returns an empty list if the trader is not registered on the market.
+    fn get_orders_for_trader(&self, trader: &Pubkey) -> Vec<(FIFOOrderId, FIFORestingOrder)> {
+        let trader_index = match self.get_trader_index(trader) {
+            Some(trader_index) => trader_index as u64,
+            None => return vec![],
+        };
+        [Side::Bid, Side::Ask]
+            .into_iter()
+            .flat_map(|side| {
+                self.get_book(side)
+                    .iter()
+                    .filter(|(_, order)| order.trader_index == trader_index)
+                    .map(|(order_id, order)| (*order_id, *order))
+            })
+            .collect()
+    }
+
+    /// Returns every order resting on one side of the book in priority order, with the key of
+    /// the trader that placed it.
+    fn get_full_book(&self, side: Side) -> Vec<(FIFOOrderId, FIFORestingOrder, Pubkey)> {
+        self.get_book(side)
+            .iter()
+            .map(|(order_id, order)| {
+                let maker = self.get_trader_id_from_index(order.trader_index as u32);
+                (*order_id, *order, maker)
+            })
+            .collect()
+    }
+
+    /// Returns the total size and notional resting on one side of the book.
+    fn get_side_totals(&self, side: Side) -> SideTotals {
+        let mut num_orders = 0;
+        let mut total_base_lots = 0u64;
+        let mut adjusted_quote_lots = 0u128;
+        for (order_id, order) in self.get_book(side).iter() {
+            num_orders += 1;
+            total_base_lots += order.num_base_lots;
+            adjusted_quote_lots += order_id.price_in_ticks as u128
+                * self.get_tick_size_in_quote_lots_per_base_unit().as_u64() as u128
+                * order.num_base_lots as u128;
+        }
+        SideTotals {
+            num_orders,
+            total_base_lots,
+            total_quote_lots: (adjusted_quote_lots
+                / self.get_base_lots_per_base_unit().as_u64() as u128)
+                as u64,
         }
+    }
 
-        Ladder { bids, asks }
+    /// Returns the top `levels` levels of each side, including the number of orders and
+    /// distinct makers at each level.
+    fn get_detailed_ladder(&self, levels: u64) -> DetailedLadder {
+        DetailedLadder::from(&self.get_maker_ladder(levels))
+    }
+
+    /// Returns the top `levels` levels of each side, with the size each maker has at each level.
+    fn get_maker_ladder(&self, levels: u64) -> MakerLadder {
+        MakerLadder {
+            bids: aggregate_maker_levels(self.get_full_book(Side::Bid), levels),
+            asks: aggregate_maker_levels(self.get_full_book(Side::Ask), levels),
+        }
     }
 
     fn get_registered_traders(&self) -> &dyn OrderedNodeAllocatorMap<Pubkey, TraderState>;
 
     fn get_taker_bps(&self) -> u16;
 
-    fn get_base_lots_per_base_unit(&self) -> u64;
+    fn get_base_lots_per_base_unit(&self) -> BaseLotsPerBaseUnit;
+
+    fn get_tick_size_in_quote_lots_per_base_unit(&self) -> QuoteLotsPerBaseUnitPerTick;
+
+    /// Returns the sequence number that will be assigned to the next order placed on the market.
+    fn get_order_sequence_number(&self) -> u64;
 
     fn get_trader_address(&self, trader: &Pubkey) -> Option<u32>;
 
     fn get_trader_state(&self, trader: &Pubkey) -> Option<&TraderState>;
 
-    fn get_trader_index(&self, trader_id: &Pubkey) -> Option<u32>; 
+    fn get_trader_index(&self, trader_id: &Pubkey) -> Option<u32>;
 
     fn get_trader_id_from_index(&self, trader_index: u32) -> Pubkey;
 
     fn get_book(&self, side: Side) -> &dyn OrderedNodeAllocatorMap<FIFOOrderId, FIFORestingOrder>;
+
+    /// Returns the market as `Any`, so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<'a> dyn Market + 'a {
+    /// Returns the concrete market if it is a `T`, e.g. `FIFOMarket<4096, 4096, 8192>`.
+    pub fn downcast_ref<T: Market + 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+
+    pub fn downcast_mut<T: Market + 'static>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut::<T>()
+    }
+}
+
+/// Aggregates the orders on each side of a market's book into at most `levels` price levels,
+/// skipping orders for which `include` returns false.
+fn build_ladder<M: Market + ?Sized>(
+    market: &M,
+    levels: u64,
+    include: impl Fn(&FIFORestingOrder) -> bool,
+) -> Ladder {
+    Ladder {
+        bids: aggregate_levels(
+            market
+                .get_book(Side::Bid)
+                .iter()
+                .filter(|(_, o)| include(o)),
+            levels,
+        ),
+        asks: aggregate_levels(
+            market
+                .get_book(Side::Ask)
+                .iter()
+                .filter(|(_, o)| include(o)),
+            levels,
+        ),
+    }
+}
+
+/// Aggregates orders, given in book order, into at most `levels` price levels.
+pub(crate) fn aggregate_levels<K: Borrow<FIFOOrderId>, O: Borrow<FIFORestingOrder>>(
+    orders: impl Iterator<Item = (K, O)>,
+    levels: u64,
+) -> Vec<LadderOrder> {
+    aggregate_by_price(
+        orders,
+        levels,
+        |(key, _)| key.borrow().price_in_ticks,
+        |price_in_ticks| LadderOrder {
+            price_in_ticks,
+            size_in_base_lots: 0,
+        },
+        |level, (_, order)| level.size_in_base_lots += order.borrow().num_base_lots,
+    )
 }
 
 /// Struct representing a market's header.
-#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct MarketHeader {
     pub discriminant: u64,
@@ -196,8 +428,105 @@ impl MarketHeader {
     pub fn get_tick_size_in_quote_atoms_per_base_unit(&self) -> u64 {
         self.tick_size_in_quote_atoms_per_base_unit
     }
-}
 
+    /// Returns the number of base lots in a base unit of `raw_base_units_per_base_unit` raw base
+    /// units, which the header does not record. Returns None if the header has no base lot
+    /// size, e.g. because it is uninitialized, or if the count is zero or overflows.
+    ///
+    /// For a loaded market, prefer `Market::get_base_lots_per_base_unit`.
+    pub fn get_base_lots_per_base_unit(&self, raw_base_units_per_base_unit: u32) -> Option<u64> {
+        10u64
+            .checked_pow(self.base_params.decimals)?
+            .checked_mul(raw_base_units_per_base_unit as u64)?
+            .checked_div(self.base_lot_size)
+            .filter(|lots| *lots > 0)
+    }
+
+    /// Returns the tick size in quote lots per base unit, or None if the header has no quote lot
+    /// size.
+    pub fn get_tick_size_in_quote_lots_per_base_unit(&self) -> Option<u64> {
+        self.tick_size_in_quote_atoms_per_base_unit
+            .checked_div(self.quote_lot_size)
+    }
+
+    /// Converts base lots to base atoms, or None if the result overflows.
+    pub fn base_lots_to_base_atoms(&self, base_lots: BaseLots) -> Option<BaseAtoms> {
+        base_lots
+            .as_u64()
+            .checked_mul(self.base_lot_size)
+            .map(BaseAtoms::new)
+    }
+
+    /// Converts quote lots to quote atoms, or None if the result overflows.
+    pub fn quote_lots_to_quote_atoms(&self, quote_lots: QuoteLots) -> Option<QuoteAtoms> {
+        quote_lots
+            .as_u64()
+            .checked_mul(self.quote_lot_size)
+            .map(QuoteAtoms::new)
+    }
+
+    /// Converts base atoms to base units (e.g. lamports to SOL).
+    pub fn base_atoms_to_base_units_as_float(&self, base_atoms: u64) -> f64 {
+        base_atoms as f64 / 10f64.powi(self.base_params.decimals as i32)
+    }
+
+    /// Converts quote atoms to quote units (e.g. 1_000_000 to 1 USDC).
+    pub fn quote_atoms_to_quote_units_as_float(&self, quote_atoms: u64) -> f64 {
+        quote_atoms as f64 / 10f64.powi(self.quote_params.decimals as i32)
+    }
+
+    /// Converts a price in ticks to quote units per raw base unit (e.g. USDC per SOL), for a
+    /// market whose base unit is `raw_base_units_per_base_unit` raw base units. Returns None if
+    /// the header is invalid (see `MarketMetadata::new`).
+    pub fn ticks_to_price(
+        &self,
+        price_in_ticks: u64,
+        raw_base_units_per_base_unit: u32,
+    ) -> Option<f64> {
+        Some(
+            MarketMetadata::new(self, raw_base_units_per_base_unit)?
+                .ticks_to_float_price(price_in_ticks),
+        )
+    }
+
+    /// Converts a price in quote units per raw base unit to ticks, rounding in `rounding` when
+    /// the price falls between ticks.
+    pub fn price_to_ticks(
+        &self,
+        price: f64,
+        raw_base_units_per_base_unit: u32,
+        rounding: RoundingDirection,
+    ) -> Option<u64> {
+        Some(
+            MarketMetadata::new(self, raw_base_units_per_base_unit)?
+                .float_price_to_ticks_rounded(price, rounding),
+        )
+    }
+
+    /// Converts the price of an order on `side` to ticks, rounding to the less aggressive tick.
+    pub fn price_to_ticks_for_side(
+        &self,
+        side: Side,
+        price: f64,
+        raw_base_units_per_base_unit: u32,
+    ) -> Option<u64> {
+        self.price_to_ticks(
+            price,
+            raw_base_units_per_base_unit,
+            RoundingDirection::passive(side),
+        )
+    }
+
+    /// Formats a price in ticks as an exact decimal number of quote units per raw base unit.
+    pub fn format_price(
+        &self,
+        price_in_ticks: u64,
+        raw_base_units_per_base_unit: u32,
+    ) -> Option<String> {
+        let context = MarketContext::from_header(self, raw_base_units_per_base_unit)?;
+        Some(Ticks(price_in_ticks).to_ui_string(&context))
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 #[repr(u64)]
@@ -336,8 +665,24 @@ impl<const BIDS_SIZE: usize, const ASKS_SIZE: usize, const NUM_SEATS: usize> Mar
         }
     }
 
-    fn get_base_lots_per_base_unit(&self) -> u64 {
-        self.base_lots_per_base_unit
+    fn get_base_lots_per_base_unit(&self) -> BaseLotsPerBaseUnit {
+        BaseLotsPerBaseUnit::new(self.base_lots_per_base_unit)
+    }
+
+    fn get_order_sequence_number(&self) -> u64 {
+        self.order_sequence_number
+    }
+
+    fn get_tick_size_in_quote_lots_per_base_unit(&self) -> QuoteLotsPerBaseUnitPerTick {
+        QuoteLotsPerBaseUnitPerTick::new(self.tick_size_in_quote_lots_per_base_unit)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
 
     fn get_registered_traders(&self) -> &dyn OrderedNodeAllocatorMap<Pubkey, TraderState> {
@@ -346,7 +691,7 @@ impl<const BIDS_SIZE: usize, const ASKS_SIZE: usize, const NUM_SEATS: usize> Mar
 }
 
 /// Struct representing the size parameters of a market.
-#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct MarketSizeParams {
     pub bids_size: u64,
@@ -356,7 +701,7 @@ pub struct MarketSizeParams {
 impl ZeroCopy for MarketSizeParams {}
 
 /// Struct representing the parameters for a token.
-#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct TokenParams {
     /// Number of decimals for the token (e.g. 9 for SOL, 6 for USDC).
@@ -374,7 +719,7 @@ pub struct TokenParams {
 impl ZeroCopy for TokenParams {}
 
 /// Struct representing the state of a trader's seat in a market.
-#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct Seat {
     pub discriminant: u64,
@@ -385,6 +730,14 @@ pub struct Seat {
 
 impl ZeroCopy for Seat {}
 
+impl Seat {
+    /// Byte offset of `market` in a seat account.
+    pub const MARKET_OFFSET: usize = 8;
+
+    /// Byte offset of `trader` in a seat account.
+    pub const TRADER_OFFSET: usize = 40;
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 #[repr(u64)]
 pub enum SeatApprovalStatus {
@@ -423,7 +776,20 @@ impl Default for SeatApprovalStatus {
 /// Struct representing an order's key in the order book. It is a combination of the order's price and the order's sequence number.
 #[cfg_attr(feature = "pyo3", pyclass(get_all, set_all))]
 #[repr(C)]
-#[derive(Eq, PartialEq, Debug, Default, Copy, Clone, Zeroable, Pod, Serialize, Deserialize)]
+#[derive(
+    Eq,
+    PartialEq,
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Zeroable,
+    Pod,
+    Serialize,
+    Deserialize,
+    BorshDeserialize,
+    BorshSerialize,
+)]
 pub struct FIFOOrderId {
     /// The price of the order, in ticks. Each market has a designated
     /// tick size (some number of quote lots) that is used to convert the price to quote ticks per base unit.
@@ -482,6 +848,10 @@ impl FIFOOrderId {
             order_sequence_number,
         }
     }
+
+    pub fn price(&self) -> Ticks {
+        Ticks(self.price_in_ticks)
+    }
 }
 
 impl PartialOrd for FIFOOrderId {
@@ -519,7 +889,9 @@ impl Ord for FIFOOrderId {
 }
 
 #[repr(C)]
-#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+#[derive(
+    Default, Debug, Copy, Clone, PartialEq, Eq, Zeroable, Pod, BorshDeserialize, BorshSerialize,
+)]
 pub struct FIFORestingOrder {
     pub trader_index: u64,
     pub num_base_lots: u64,
@@ -532,12 +904,28 @@ impl FIFORestingOrder {
             num_base_lots,
         }
     }
+
+    pub fn base_lots(&self) -> BaseLots {
+        BaseLots(self.num_base_lots)
+    }
 }
 #[repr(C)]
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Zeroable, Pod)]
+#[derive(
+    Default, Debug, Copy, Clone, PartialEq, Eq, Zeroable, Pod, BorshDeserialize, BorshSerialize,
+)]
 pub struct TraderState {
-    pub quote_lots_locked: u64,
-    pub quote_lots_free: u64,
-    pub base_lots_locked: u64,
-    pub base_lots_free: u64,
+    pub quote_lots_locked: QuoteLots,
+    pub quote_lots_free: QuoteLots,
+    pub base_lots_locked: BaseLots,
+    pub base_lots_free: BaseLots,
+}
+
+impl TraderState {
+    pub fn total_quote_lots(&self) -> QuoteLots {
+        self.quote_lots_locked + self.quote_lots_free
+    }
+
+    pub fn total_base_lots(&self) -> BaseLots {
+        self.base_lots_locked + self.base_lots_free
+    }
 }
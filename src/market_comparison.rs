@@ -0,0 +1,125 @@
+use crate::market_metadata::MarketMetadata;
+use serde::{Deserialize, Serialize};
+
+/// How the increments of two markets relate to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridRelation {
+    /// Both markets use the same increment.
+    Identical,
+    /// Every increment of the first market is a whole number of increments of the second, so
+    /// values can be translated from the first market to the second without rounding.
+    FirstCoarser,
+    /// Every increment of the second market is a whole number of increments of the first.
+    SecondCoarser,
+    /// Neither increment is a multiple of the other, so translating in either direction may
+    /// round.
+    Incompatible,
+}
+
+impl GridRelation {
+    /// Compares the increments `first_numerator / first_denominator` and
+    /// `second_numerator / second_denominator`. Increments that cannot be compared exactly are
+    /// incompatible.
+    fn from_ratios(
+        first_numerator: u128,
+        first_denominator: u128,
+        second_numerator: u128,
+        second_denominator: u128,
+    ) -> Self {
+        let (first, second) = match (
+            first_numerator.checked_mul(second_denominator),
+            second_numerator.checked_mul(first_denominator),
+        ) {
+            (Some(first), Some(second)) => (first, second),
+            _ => return Self::Incompatible,
+        };
+        if first == 0 || second == 0 {
+            Self::Incompatible
+        } else if first == second {
+            Self::Identical
+        } else if first.is_multiple_of(second) {
+            Self::FirstCoarser
+        } else if second.is_multiple_of(first) {
+            Self::SecondCoarser
+        } else {
+            Self::Incompatible
+        }
+    }
+
+    /// Returns true if values can be translated in at least one direction without rounding.
+    pub fn is_compatible(&self) -> bool {
+        *self != Self::Incompatible
+    }
+}
+
+/// A side by side comparison of the parameters of two markets, for deciding whether sizes and
+/// prices can be translated between them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketComparison {
+    pub first: MarketMetadata,
+    pub second: MarketMetadata,
+    pub first_taker_fee_bps: u16,
+    pub second_taker_fee_bps: u16,
+
+    /// Whether both markets trade the same base and quote mints.
+    pub same_pair: bool,
+
+    /// Whether both markets use the same base and quote decimals.
+    pub same_decimals: bool,
+
+    /// How the base lot sizes, in base atoms, relate.
+    pub size_relation: GridRelation,
+
+    /// How the tick sizes, in quote atoms per base atom, relate.
+    pub price_relation: GridRelation,
+}
+
+impl MarketComparison {
+    pub fn new(
+        first: &MarketMetadata,
+        first_taker_fee_bps: u16,
+        second: &MarketMetadata,
+        second_taker_fee_bps: u16,
+    ) -> Self {
+        let (first, second) = (*first, *second);
+        // Zero when the count overflows, which makes the prices incompatible.
+        let base_atoms_per_base_unit = |metadata: &MarketMetadata| {
+            10u128
+                .checked_pow(metadata.base_decimals)
+                .and_then(|atoms| atoms.checked_mul(metadata.raw_base_units_per_base_unit as u128))
+                .unwrap_or(0)
+        };
+        Self {
+            same_pair: first.base_mint == second.base_mint && first.quote_mint == second.quote_mint,
+            same_decimals: first.base_decimals == second.base_decimals
+                && first.quote_decimals == second.quote_decimals,
+            size_relation: GridRelation::from_ratios(
+                first.base_atoms_per_base_lot as u128,
+                1,
+                second.base_atoms_per_base_lot as u128,
+                1,
+            ),
+            price_relation: GridRelation::from_ratios(
+                first.tick_size_in_quote_atoms_per_base_unit as u128,
+                base_atoms_per_base_unit(&first),
+                second.tick_size_in_quote_atoms_per_base_unit as u128,
+                base_atoms_per_base_unit(&second),
+            ),
+            first,
+            second,
+            first_taker_fee_bps,
+            second_taker_fee_bps,
+        }
+    }
+
+    /// Returns the second market's taker fee minus the first's, in basis points.
+    pub fn taker_fee_bps_difference(&self) -> i32 {
+        self.second_taker_fee_bps as i32 - self.first_taker_fee_bps as i32
+    }
+
+    /// Returns true if the markets trade the same pair and both sizes and prices can be
+    /// translated between them in at least one direction without rounding.
+    pub fn is_compatible(&self) -> bool {
+        self.same_pair && self.size_relation.is_compatible() && self.price_relation.is_compatible()
+    }
+}
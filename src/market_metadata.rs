@@ -0,0 +1,198 @@
+use crate::enums::Side;
+use crate::market::{Market, MarketHeader};
+use crate::rounding::RoundingDirection;
+use crate::snapshot::MarketSnapshot;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+#[cfg(feature = "pyo3")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// The static parameters of a market needed to convert between on-chain units (ticks, lots)
+/// and human readable units.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketMetadata {
+    /// Pubkey of the base token mint.
+    #[serde(with = "crate::pubkey_serde")]
+    pub base_mint: Pubkey,
+
+    /// Pubkey of the quote token mint.
+    #[serde(with = "crate::pubkey_serde")]
+    pub quote_mint: Pubkey,
+
+    /// Number of decimals for the base token.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub base_decimals: u32,
+
+    /// Number of decimals for the quote token.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub quote_decimals: u32,
+
+    /// Size of a base lot, in base atoms.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub base_atoms_per_base_lot: u64,
+
+    /// Size of a quote lot, in quote atoms.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub quote_atoms_per_quote_lot: u64,
+
+    /// Tick size, in quote atoms per base unit.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+
+    /// Tick size, in quote lots per base unit.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+
+    /// Number of base lots in a base unit.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub base_lots_per_base_unit: u64,
+
+    /// Number of raw base units (whole tokens) in a base unit.
+    #[cfg_attr(feature = "pyo3", pyo3(get))]
+    pub raw_base_units_per_base_unit: u32,
+}
+
+impl MarketMetadata {
+    /// Creates the metadata of a market whose base unit is `raw_base_units_per_base_unit` raw
+    /// base units (whole tokens). The header does not record this, so it must come from the
+    /// market's configuration or from the loaded market (see `from_market`).
+    ///
+    /// Returns None if the header has no lot sizes, e.g. because it is uninitialized, or if the
+    /// number of base lots in a base unit is zero or overflows.
+    pub fn new(header: &MarketHeader, raw_base_units_per_base_unit: u32) -> Option<Self> {
+        let base_lots_per_base_unit =
+            header.get_base_lots_per_base_unit(raw_base_units_per_base_unit)?;
+        Some(Self {
+            base_mint: header.base_params.mint_key,
+            quote_mint: header.quote_params.mint_key,
+            base_decimals: header.base_params.decimals,
+            quote_decimals: header.quote_params.decimals,
+            base_atoms_per_base_lot: header.get_base_lot_size(),
+            quote_atoms_per_quote_lot: header.get_quote_lot_size(),
+            tick_size_in_quote_atoms_per_base_unit: header
+                .get_tick_size_in_quote_atoms_per_base_unit(),
+            tick_size_in_quote_lots_per_base_unit: header
+                .get_tick_size_in_quote_lots_per_base_unit()?,
+            base_lots_per_base_unit,
+            raw_base_units_per_base_unit,
+        })
+    }
+
+    /// Creates the metadata of a loaded market, deriving the raw base units in a base unit from
+    /// the market's base lots per base unit. Returns None if the header and market disagree.
+    pub fn from_market(header: &MarketHeader, market: &dyn Market) -> Option<Self> {
+        Self::from_base_lots_per_base_unit(header, market.get_base_lots_per_base_unit().as_u64())
+    }
+
+    /// Creates the metadata of the market a snapshot was taken of, like `from_market`.
+    pub fn from_snapshot(snapshot: &MarketSnapshot) -> Option<Self> {
+        Self::from_base_lots_per_base_unit(&snapshot.header, snapshot.base_lots_per_base_unit)
+    }
+
+    fn from_base_lots_per_base_unit(
+        header: &MarketHeader,
+        base_lots_per_base_unit: u64,
+    ) -> Option<Self> {
+        let base_atoms_per_base_unit =
+            base_lots_per_base_unit.checked_mul(header.get_base_lot_size())?;
+        let base_atoms_per_raw_base_unit = 10u64.checked_pow(header.base_params.decimals)?;
+        if base_atoms_per_base_unit % base_atoms_per_raw_base_unit != 0 {
+            return None;
+        }
+        let raw_base_units_per_base_unit =
+            u32::try_from(base_atoms_per_base_unit / base_atoms_per_raw_base_unit).ok()?;
+        Self::new(header, raw_base_units_per_base_unit)
+            .filter(|metadata| metadata.base_lots_per_base_unit == base_lots_per_base_unit)
+    }
+
+    /// Converts a price in quote units per raw base unit to ticks, rounding in `rounding`.
+    pub fn float_price_to_ticks_rounded(&self, price: f64, rounding: RoundingDirection) -> u64 {
+        rounding.round(
+            price * self.quote_atoms_per_quote_unit() * self.raw_base_units_per_base_unit as f64
+                / self.tick_size_in_quote_atoms_per_base_unit as f64,
+        ) as u64
+    }
+
+    /// Converts the price of an order on `side` to ticks, rounding to the less aggressive tick.
+    pub fn float_price_to_ticks_for_side(&self, side: Side, price: f64) -> u64 {
+        self.float_price_to_ticks_rounded(price, RoundingDirection::passive(side))
+    }
+
+    /// Converts a size in raw base units to base lots, rounding in `rounding`.
+    pub fn ui_size_to_raw_rounded(&self, size: f64, rounding: RoundingDirection) -> u64 {
+        rounding
+            .round(size * self.base_atoms_per_raw_base_unit() / self.base_atoms_per_base_lot as f64)
+            as u64
+    }
+
+    fn quote_atoms_per_quote_unit(&self) -> f64 {
+        10f64.powi(self.quote_decimals as i32)
+    }
+
+    fn base_atoms_per_raw_base_unit(&self) -> f64 {
+        10f64.powi(self.base_decimals as i32)
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pymethods)]
+impl MarketMetadata {
+    /// Converts a price in quote units per raw base unit to ticks, rounding down.
+    pub fn float_price_to_ticks(&self, price: f64) -> u64 {
+        self.float_price_to_ticks_rounded(price, RoundingDirection::Down)
+    }
+
+    /// Converts a price in ticks to a price in quote units per raw base unit.
+    pub fn ticks_to_float_price(&self, price_in_ticks: u64) -> f64 {
+        price_in_ticks as f64 * self.tick_size_in_quote_atoms_per_base_unit as f64
+            / self.quote_atoms_per_quote_unit()
+            / self.raw_base_units_per_base_unit as f64
+    }
+
+    /// Converts a size in base lots, as stored on chain, to a size in raw base units.
+    pub fn raw_to_ui_size(&self, size_in_base_lots: u64) -> f64 {
+        size_in_base_lots as f64 * self.base_atoms_per_base_lot as f64
+            / self.base_atoms_per_raw_base_unit()
+    }
+
+    /// Converts a size in raw base units to base lots, rounding down.
+    pub fn ui_size_to_raw(&self, size: f64) -> u64 {
+        self.ui_size_to_raw_rounded(size, RoundingDirection::Down)
+    }
+
+    /// Converts an amount in quote lots to quote units.
+    pub fn quote_lots_to_ui_amount(&self, quote_lots: u64) -> f64 {
+        quote_lots as f64 * self.quote_atoms_per_quote_lot as f64
+            / self.quote_atoms_per_quote_unit()
+    }
+
+    /// Creates the metadata from the bytes of a market header (the first bytes of a market
+    /// account) and the number of raw base units in a base unit.
+    #[cfg(feature = "pyo3")]
+    #[staticmethod]
+    pub fn from_header_bytes(bytes: &[u8], raw_base_units_per_base_unit: u32) -> PyResult<Self> {
+        let header_size = std::mem::size_of::<MarketHeader>();
+        if bytes.len() < header_size {
+            return Err(PyValueError::new_err(
+                "Not enough bytes for a market header",
+            ));
+        }
+        let header = bytemuck::try_pod_read_unaligned::<MarketHeader>(&bytes[..header_size])
+            .map_err(|_| PyValueError::new_err("Invalid market header"))?;
+        Self::new(&header, raw_base_units_per_base_unit)
+            .ok_or_else(|| PyValueError::new_err("Invalid market lot sizes"))
+    }
+
+    #[cfg(feature = "pyo3")]
+    #[getter(base_mint)]
+    pub fn py_base_mint(&self) -> String {
+        self.base_mint.to_string()
+    }
+
+    #[cfg(feature = "pyo3")]
+    #[getter(quote_mint)]
+    pub fn py_quote_mint(&self) -> String {
+        self.quote_mint.to_string()
+    }
+}
@@ -0,0 +1,269 @@
+use crate::dispatch::is_supported;
+use crate::instructions::InitializeParams;
+use crate::market::MarketSizeParams;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+/// The maximum taker fee a market can charge, in basis points.
+pub const MAX_TAKER_FEE_BPS: u16 = 10_000;
+
+/// Errors returned when deriving or validating market parameters.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MarketParamsError {
+    #[error("Tick size of {0} is not a whole number of quote atoms per base unit")]
+    FractionalTickSize(f64),
+    #[error("Minimum order size of {0} is not a whole number of base atoms")]
+    FractionalBaseLotSize(f64),
+    #[error("Base lot size of {base_lot_size} atoms does not divide a base unit of {base_atoms_per_base_unit} atoms")]
+    BaseLotSizeDoesNotDivideBaseUnit {
+        base_lot_size: u64,
+        base_atoms_per_base_unit: u64,
+    },
+    #[error("Tick size of {tick_size_in_quote_atoms_per_base_unit} quote atoms per base unit is not divisible by {num_base_lots_per_base_unit} base lots per base unit")]
+    TickSizeNotDivisibleByBaseLots {
+        tick_size_in_quote_atoms_per_base_unit: u64,
+        num_base_lots_per_base_unit: u64,
+    },
+    #[error("Tick size of {tick_size_in_quote_lots_per_base_unit} quote lots per base unit is not divisible by {num_base_lots_per_base_unit} base lots per base unit")]
+    TickSizeInQuoteLotsNotDivisibleByBaseLots {
+        tick_size_in_quote_lots_per_base_unit: u64,
+        num_base_lots_per_base_unit: u64,
+    },
+    #[error("{0} must be greater than zero")]
+    ZeroValue(&'static str),
+    #[error("{num_quote_lots_per_quote_unit} quote lots per quote unit does not divide a quote unit of {quote_atoms_per_quote_unit} atoms")]
+    QuoteLotsDoNotDivideQuoteUnit {
+        num_quote_lots_per_quote_unit: u64,
+        quote_atoms_per_quote_unit: u64,
+    },
+    #[error("{num_base_lots_per_base_unit} base lots per base unit does not divide a base unit of {base_atoms_per_base_unit} atoms")]
+    BaseLotsDoNotDivideBaseUnit {
+        num_base_lots_per_base_unit: u64,
+        base_atoms_per_base_unit: u64,
+    },
+    #[error("Market size params {0:?} are not supported")]
+    UnsupportedMarketSize(MarketSizeParams),
+    #[error("Taker fee of {0} bps exceeds the maximum of {MAX_TAKER_FEE_BPS} bps")]
+    TakerFeeTooHigh(u16),
+    #[error("Value overflowed while computing market parameters")]
+    Overflow,
+}
+
+/// A consistent set of lot and tick sizes for a new market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketParamsDesign {
+    /// Size of a base lot, in base atoms.
+    pub base_lot_size_in_atoms: u64,
+
+    /// Size of a quote lot, in quote atoms.
+    pub quote_lot_size_in_atoms: u64,
+
+    /// Tick size, in quote atoms per base unit.
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+
+    /// Tick size, in quote lots per base unit.
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+
+    /// Number of base lots in a base unit.
+    pub num_base_lots_per_base_unit: u64,
+
+    /// Number of quote lots in a quote unit.
+    pub num_quote_lots_per_quote_unit: u64,
+
+    /// Number of raw base units in a base unit.
+    pub raw_base_units_per_base_unit: u32,
+}
+
+impl MarketParamsDesign {
+    /// Derives lot and tick sizes from human readable market specifications.
+    ///
+    /// * `tick_size` - The desired price increment, in quote units per raw base unit.
+    /// * `min_order_size` - The desired minimum order size (one base lot), in raw base units.
+    /// * `raw_base_units_per_base_unit` - Number of raw base units in a base unit, usually 1.
+    ///
+    /// The quote lot size is chosen to be as large as possible while keeping every tick worth a
+    /// whole number of quote lots for every base lot.
+    pub fn new(
+        tick_size: f64,
+        min_order_size: f64,
+        base_decimals: u32,
+        quote_decimals: u32,
+        raw_base_units_per_base_unit: u32,
+    ) -> Result<Self, MarketParamsError> {
+        let base_atoms_per_raw_base_unit = pow10(base_decimals)?;
+        let quote_atoms_per_quote_unit = pow10(quote_decimals)?;
+        let base_atoms_per_base_unit = base_atoms_per_raw_base_unit
+            .checked_mul(raw_base_units_per_base_unit as u64)
+            .ok_or(MarketParamsError::Overflow)?;
+
+        let tick_size_in_quote_atoms_per_base_unit = to_whole_number(
+            tick_size * quote_atoms_per_quote_unit as f64 * raw_base_units_per_base_unit as f64,
+        )
+        .ok_or(MarketParamsError::FractionalTickSize(tick_size))?;
+        let base_lot_size_in_atoms =
+            to_whole_number(min_order_size * base_atoms_per_raw_base_unit as f64)
+                .ok_or(MarketParamsError::FractionalBaseLotSize(min_order_size))?;
+
+        if !base_atoms_per_base_unit.is_multiple_of(base_lot_size_in_atoms) {
+            return Err(MarketParamsError::BaseLotSizeDoesNotDivideBaseUnit {
+                base_lot_size: base_lot_size_in_atoms,
+                base_atoms_per_base_unit,
+            });
+        }
+        let num_base_lots_per_base_unit = base_atoms_per_base_unit / base_lot_size_in_atoms;
+
+        if !tick_size_in_quote_atoms_per_base_unit.is_multiple_of(num_base_lots_per_base_unit) {
+            return Err(MarketParamsError::TickSizeNotDivisibleByBaseLots {
+                tick_size_in_quote_atoms_per_base_unit,
+                num_base_lots_per_base_unit,
+            });
+        }
+        let quote_lot_size_in_atoms = gcd(
+            tick_size_in_quote_atoms_per_base_unit / num_base_lots_per_base_unit,
+            quote_atoms_per_quote_unit,
+        );
+
+        Ok(Self {
+            base_lot_size_in_atoms,
+            quote_lot_size_in_atoms,
+            tick_size_in_quote_atoms_per_base_unit,
+            tick_size_in_quote_lots_per_base_unit: tick_size_in_quote_atoms_per_base_unit
+                / quote_lot_size_in_atoms,
+            num_base_lots_per_base_unit,
+            num_quote_lots_per_quote_unit: quote_atoms_per_quote_unit / quote_lot_size_in_atoms,
+            raw_base_units_per_base_unit,
+        })
+    }
+
+    /// Builds the `InitializeParams` for a market using these lot and tick sizes.
+    pub fn to_initialize_params(
+        &self,
+        market_size_params: MarketSizeParams,
+        taker_fee_bps: u16,
+        fee_collector: Pubkey,
+    ) -> InitializeParams {
+        InitializeParams {
+            market_size_params,
+            num_quote_lots_per_quote_unit: self.num_quote_lots_per_quote_unit,
+            tick_size_in_quote_lots_per_base_unit: self.tick_size_in_quote_lots_per_base_unit,
+            num_base_lots_per_base_unit: self.num_base_lots_per_base_unit,
+            taker_fee_bps,
+            fee_collector,
+            raw_base_units_per_base_unit: if self.raw_base_units_per_base_unit == 1 {
+                None
+            } else {
+                Some(self.raw_base_units_per_base_unit)
+            },
+        }
+    }
+}
+
+/// Checks that `InitializeParams` describe a market that can be created and loaded, given the
+/// decimals of the base and quote mints. Returns every problem found.
+pub fn validate_market_params(
+    params: &InitializeParams,
+    base_decimals: u32,
+    quote_decimals: u32,
+) -> Result<(), Vec<MarketParamsError>> {
+    let mut errors = vec![];
+    if !is_supported(&params.market_size_params) {
+        errors.push(MarketParamsError::UnsupportedMarketSize(
+            params.market_size_params,
+        ));
+    }
+    if params.taker_fee_bps > MAX_TAKER_FEE_BPS {
+        errors.push(MarketParamsError::TakerFeeTooHigh(params.taker_fee_bps));
+    }
+    if params.raw_base_units_per_base_unit == Some(0) {
+        errors.push(MarketParamsError::ZeroValue("raw_base_units_per_base_unit"));
+    }
+    for (name, value) in [
+        (
+            "num_quote_lots_per_quote_unit",
+            params.num_quote_lots_per_quote_unit,
+        ),
+        (
+            "tick_size_in_quote_lots_per_base_unit",
+            params.tick_size_in_quote_lots_per_base_unit,
+        ),
+        (
+            "num_base_lots_per_base_unit",
+            params.num_base_lots_per_base_unit,
+        ),
+    ] {
+        if value == 0 {
+            errors.push(MarketParamsError::ZeroValue(name));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    if !params
+        .tick_size_in_quote_lots_per_base_unit
+        .is_multiple_of(params.num_base_lots_per_base_unit)
+    {
+        errors.push(
+            MarketParamsError::TickSizeInQuoteLotsNotDivisibleByBaseLots {
+                tick_size_in_quote_lots_per_base_unit: params.tick_size_in_quote_lots_per_base_unit,
+                num_base_lots_per_base_unit: params.num_base_lots_per_base_unit,
+            },
+        );
+    }
+    match pow10(quote_decimals) {
+        Ok(quote_atoms_per_quote_unit) => {
+            if !quote_atoms_per_quote_unit.is_multiple_of(params.num_quote_lots_per_quote_unit) {
+                errors.push(MarketParamsError::QuoteLotsDoNotDivideQuoteUnit {
+                    num_quote_lots_per_quote_unit: params.num_quote_lots_per_quote_unit,
+                    quote_atoms_per_quote_unit,
+                });
+            }
+        }
+        Err(e) => errors.push(e),
+    }
+    match pow10(base_decimals).and_then(|atoms| {
+        atoms
+            .checked_mul(params.raw_base_units_per_base_unit.unwrap_or(1) as u64)
+            .ok_or(MarketParamsError::Overflow)
+    }) {
+        Ok(base_atoms_per_base_unit) => {
+            if !base_atoms_per_base_unit.is_multiple_of(params.num_base_lots_per_base_unit) {
+                errors.push(MarketParamsError::BaseLotsDoNotDivideBaseUnit {
+                    num_base_lots_per_base_unit: params.num_base_lots_per_base_unit,
+                    base_atoms_per_base_unit,
+                });
+            }
+        }
+        Err(e) => errors.push(e),
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn pow10(exponent: u32) -> Result<u64, MarketParamsError> {
+    10u64
+        .checked_pow(exponent)
+        .ok_or(MarketParamsError::Overflow)
+}
+
+/// Rounds a float to the nearest integer, returning None if it is not (close to) a positive
+/// whole number.
+fn to_whole_number(value: f64) -> Option<u64> {
+    let rounded = value.round();
+    if rounded < 1.0 || rounded > u64::MAX as f64 || (value - rounded).abs() > 1e-6 * rounded {
+        return None;
+    }
+    Some(rounded as u64)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::enums::Side;
+use crate::market::MarketHeader;
+use crate::market_metadata::MarketMetadata;
+use crate::rounding::RoundingDirection;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Relative tolerance used when checking that a UI value is a whole number of increments.
+const INCREMENT_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum MarketRuleViolation {
+    #[error("Price {price} is not a multiple of the price increment {increment}")]
+    PriceNotOnTick { price: f64, increment: f64 },
+    #[error("Size {size} is not a multiple of the size increment {increment}")]
+    SizeNotOnLot { size: f64, increment: f64 },
+    #[error("Price {price} is outside of the range (0, {max}]")]
+    PriceOutOfRange { price: f64, max: f64 },
+    #[error("Size {size} is outside of the range (0, {max}]")]
+    SizeOutOfRange { size: f64, max: f64 },
+}
+
+/// The increments and limits of a market in UI units (quote units per raw base unit for prices,
+/// raw base units for sizes), for validating order forms before building orders.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MarketRules {
+    /// The smallest price change, one tick, in quote units per raw base unit.
+    pub min_price_increment: f64,
+
+    /// The smallest size change, one base lot, in raw base units.
+    pub min_size_increment: f64,
+
+    /// The smallest change in a quote amount, one quote lot, in quote units.
+    pub min_quote_increment: f64,
+
+    /// The notional of the smallest possible order (one base lot at one tick), in quote units.
+    pub min_notional: f64,
+
+    /// The largest price representable in ticks, in quote units per raw base unit.
+    pub max_price: f64,
+
+    /// The largest size representable in base lots, in raw base units.
+    pub max_size: f64,
+}
+
+impl MarketRules {
+    /// Returns the rules of a market whose base unit is `raw_base_units_per_base_unit` raw base
+    /// units, or None if the header is invalid (see `MarketMetadata::new`).
+    pub fn from_header(header: &MarketHeader, raw_base_units_per_base_unit: u32) -> Option<Self> {
+        MarketMetadata::new(header, raw_base_units_per_base_unit)
+            .map(|metadata| Self::from_metadata(&metadata))
+    }
+
+    pub fn from_metadata(metadata: &MarketMetadata) -> Self {
+        let min_price_increment = metadata.ticks_to_float_price(1);
+        let min_size_increment = metadata.raw_to_ui_size(1);
+        Self {
+            min_price_increment,
+            min_size_increment,
+            min_quote_increment: metadata.quote_lots_to_ui_amount(1),
+            min_notional: min_price_increment * min_size_increment,
+            max_price: metadata.ticks_to_float_price(u64::MAX),
+            max_size: metadata.raw_to_ui_size(u64::MAX),
+        }
+    }
+
+    /// Checks that `price` is positive, representable, and a whole number of ticks.
+    pub fn check_price(&self, price: f64) -> Result<(), MarketRuleViolation> {
+        if !(price > 0.0 && price <= self.max_price) {
+            return Err(MarketRuleViolation::PriceOutOfRange {
+                price,
+                max: self.max_price,
+            });
+        }
+        if !is_multiple_of(price, self.min_price_increment) {
+            return Err(MarketRuleViolation::PriceNotOnTick {
+                price,
+                increment: self.min_price_increment,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that `size` is positive, representable, and a whole number of base lots.
+    pub fn check_size(&self, size: f64) -> Result<(), MarketRuleViolation> {
+        if !(size > 0.0 && size <= self.max_size) {
+            return Err(MarketRuleViolation::SizeOutOfRange {
+                size,
+                max: self.max_size,
+            });
+        }
+        if !is_multiple_of(size, self.min_size_increment) {
+            return Err(MarketRuleViolation::SizeNotOnLot {
+                size,
+                increment: self.min_size_increment,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks both the price and size of an order.
+    pub fn check_order(&self, price: f64, size: f64) -> Result<(), MarketRuleViolation> {
+        self.check_price(price)?;
+        self.check_size(size)
+    }
+
+    /// Rounds `price` to a tick in `rounding`.
+    pub fn round_price(&self, price: f64, rounding: RoundingDirection) -> f64 {
+        rounding.round_to_increment(price, self.min_price_increment)
+    }
+
+    /// Rounds the price of an order on `side` to the less aggressive tick, returning the price.
+    pub fn round_float_price_for_side(&self, side: Side, price: f64) -> f64 {
+        self.round_price(price, RoundingDirection::passive(side))
+    }
+
+    /// Rounds `size` to a base lot in `rounding`.
+    pub fn round_size(&self, size: f64, rounding: RoundingDirection) -> f64 {
+        rounding.round_to_increment(size, self.min_size_increment)
+    }
+
+    /// Rounds `price` down to the nearest tick.
+    pub fn round_price_down(&self, price: f64) -> f64 {
+        self.round_price(price, RoundingDirection::Down)
+    }
+
+    /// Rounds `size` down to the nearest base lot.
+    pub fn round_size_down(&self, size: f64) -> f64 {
+        self.round_size(size, RoundingDirection::Down)
+    }
+}
+
+fn is_multiple_of(value: f64, increment: f64) -> bool {
+    let increments = value / increment;
+    (increments - increments.round()).abs() <= INCREMENT_TOLERANCE * increments.abs().max(1.0)
+}
@@ -0,0 +1,125 @@
+use crate::instructions::{
+    create_deposit_funds_instruction, create_request_seat_instruction, DepositParams,
+};
+use crate::market::{MarketHeader, Seat, SeatApprovalStatus, TraderState};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum OnboardingError {
+    #[error("Seat account does not belong to this market and trader")]
+    SeatMismatch,
+    #[error("Invalid seat approval status {0}")]
+    InvalidSeatStatus(u64),
+    #[error("The trader's seat is retired")]
+    SeatRetired,
+}
+
+/// The state of a trader on a market, decoded from fetched accounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OnboardingAccounts {
+    /// The trader's seat account, or None if it does not exist.
+    pub seat: Option<Seat>,
+
+    /// Whether the trader's base token associated token account exists.
+    pub base_token_account_exists: bool,
+
+    /// Whether the trader's quote token associated token account exists.
+    pub quote_token_account_exists: bool,
+
+    /// The trader's state in the market, or None if they are not registered.
+    pub trader_state: Option<TraderState>,
+}
+
+/// The steps needed to make a trader ready to quote on a market.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnboardingPlan {
+    /// The instructions to execute, in order, signed by the trader.
+    pub instructions: Vec<Instruction>,
+
+    /// True if the trader's seat must be approved by the market authority before they can
+    /// deposit. When set, `instructions` does not include the deposit, and the plan should be
+    /// recomputed once the seat is approved.
+    pub awaiting_seat_approval: bool,
+}
+
+impl OnboardingPlan {
+    /// Returns true if the trader is already ready to quote.
+    pub fn is_complete(&self) -> bool {
+        self.instructions.is_empty() && !self.awaiting_seat_approval
+    }
+}
+
+/// Returns the minimal ordered list of instructions that makes `trader` ready to quote on
+/// `market` with at least `target` lots free to trade: requesting a seat, creating the trader's
+/// associated token accounts, and depositing whatever `target` exceeds the free lots already
+/// deposited.
+pub fn plan_onboarding(
+    market: &Pubkey,
+    trader: &Pubkey,
+    header: &MarketHeader,
+    accounts: &OnboardingAccounts,
+    target: &DepositParams,
+) -> Result<OnboardingPlan, OnboardingError> {
+    let base_mint = &header.base_params.mint_key;
+    let quote_mint = &header.quote_params.mint_key;
+    let mut instructions = vec![];
+
+    let seat_status = match accounts.seat {
+        Some(seat) => {
+            if seat.market != *market || seat.trader != *trader {
+                return Err(OnboardingError::SeatMismatch);
+            }
+            if seat.approval_status > SeatApprovalStatus::Retired as u64 {
+                return Err(OnboardingError::InvalidSeatStatus(seat.approval_status));
+            }
+            SeatApprovalStatus::from(seat.approval_status)
+        }
+        None => {
+            instructions.push(create_request_seat_instruction(trader, market));
+            SeatApprovalStatus::NotApproved
+        }
+    };
+    if seat_status == SeatApprovalStatus::Retired {
+        return Err(OnboardingError::SeatRetired);
+    }
+
+    if !accounts.base_token_account_exists {
+        instructions.push(create_associated_token_account_idempotent(
+            trader,
+            trader,
+            base_mint,
+            &spl_token::id(),
+        ));
+    }
+    if !accounts.quote_token_account_exists {
+        instructions.push(create_associated_token_account_idempotent(
+            trader,
+            trader,
+            quote_mint,
+            &spl_token::id(),
+        ));
+    }
+
+    let awaiting_seat_approval = seat_status != SeatApprovalStatus::Approved;
+    let free = accounts.trader_state.unwrap_or_default();
+    let deposit = DepositParams {
+        quote_lots: target
+            .quote_lots
+            .saturating_sub(free.quote_lots_free.as_u64()),
+        base_lots: target
+            .base_lots
+            .saturating_sub(free.base_lots_free.as_u64()),
+    };
+    if !awaiting_seat_approval && (deposit.quote_lots > 0 || deposit.base_lots > 0) {
+        instructions.push(create_deposit_funds_instruction(
+            market, trader, base_mint, quote_mint, &deposit,
+        ));
+    }
+
+    Ok(OnboardingPlan {
+        instructions,
+        awaiting_seat_approval,
+    })
+}
@@ -0,0 +1,140 @@
+use crate::enums::Side;
+use crate::events::{taker_fills, AuditLogHeader, MarketEvent, TakerFill};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Taker flow over a window of the trade tape, where every fill counts as a trade.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderFlowMetrics {
+    /// Base lots bought by takers.
+    pub buy_base_lots: u64,
+
+    /// Base lots sold by takers.
+    pub sell_base_lots: u64,
+
+    /// Taker buy volume minus taker sell volume, in base lots.
+    pub signed_base_lots: i128,
+
+    pub buy_count: u64,
+
+    pub sell_count: u64,
+
+    /// Average size of a trade in base lots, or None if there were no trades.
+    pub average_trade_size_in_base_lots: Option<f64>,
+}
+
+impl OrderFlowMetrics {
+    pub fn trade_count(&self) -> u64 {
+        self.buy_count + self.sell_count
+    }
+
+    /// Signed flow as a fraction of total volume, from -1 (all sells) to 1 (all buys), or None
+    /// if there were no trades.
+    pub fn imbalance(&self) -> Option<f64> {
+        let volume = self.buy_base_lots as f64 + self.sell_base_lots as f64;
+        (volume > 0.0).then(|| self.signed_base_lots as f64 / volume)
+    }
+}
+
+/// A single fill on the tape.
+#[derive(Debug, Clone, Copy)]
+struct Trade {
+    timestamp: i64,
+    taker_side: Side,
+    base_lots: u64,
+}
+
+/// Accumulates taker order flow over a sliding window of `window_seconds` from the fills of a
+/// market's logs, which must be added in timestamp order.
+#[derive(Debug, Clone)]
+pub struct OrderFlowAccumulator {
+    window_seconds: u64,
+    trades: VecDeque<Trade>,
+    buy_base_lots: u64,
+    sell_base_lots: u64,
+    buy_count: u64,
+    sell_count: u64,
+}
+
+impl OrderFlowAccumulator {
+    pub fn new(window_seconds: u64) -> Self {
+        Self {
+            window_seconds,
+            trades: VecDeque::new(),
+            buy_base_lots: 0,
+            sell_base_lots: 0,
+            buy_count: 0,
+            sell_count: 0,
+        }
+    }
+
+    pub fn window_seconds(&self) -> u64 {
+        self.window_seconds
+    }
+
+    /// Adds the fills of a single log and drops the trades that fell out of the window as of the
+    /// log's timestamp.
+    pub fn apply_log(&mut self, header: &AuditLogHeader, events: &[MarketEvent]) {
+        for fill in taker_fills(header, events) {
+            self.add_fill(header.timestamp, &fill);
+        }
+        self.advance_to(header.timestamp);
+    }
+
+    /// Adds a fill that happened at `timestamp`, in unix seconds.
+    pub fn add_fill(&mut self, timestamp: i64, fill: &TakerFill) {
+        match fill.taker_side {
+            Side::Bid => {
+                self.buy_base_lots += fill.base_lots_filled;
+                self.buy_count += 1;
+            }
+            Side::Ask => {
+                self.sell_base_lots += fill.base_lots_filled;
+                self.sell_count += 1;
+            }
+        }
+        self.trades.push_back(Trade {
+            timestamp,
+            taker_side: fill.taker_side,
+            base_lots: fill.base_lots_filled,
+        });
+    }
+
+    /// Drops the trades older than the window ending at `now`, in unix seconds. Trades exactly
+    /// `window_seconds` old are dropped.
+    pub fn advance_to(&mut self, now: i64) {
+        let cutoff = now.saturating_sub_unsigned(self.window_seconds);
+        while let Some(trade) = self.trades.front().copied() {
+            if trade.timestamp > cutoff {
+                break;
+            }
+            self.trades.pop_front();
+            match trade.taker_side {
+                Side::Bid => {
+                    self.buy_base_lots -= trade.base_lots;
+                    self.buy_count -= 1;
+                }
+                Side::Ask => {
+                    self.sell_base_lots -= trade.base_lots;
+                    self.sell_count -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the metrics of the trades in the window, as of the last call to `advance_to` or
+    /// `apply_log`.
+    pub fn metrics(&self) -> OrderFlowMetrics {
+        let trade_count = self.buy_count + self.sell_count;
+        OrderFlowMetrics {
+            buy_base_lots: self.buy_base_lots,
+            sell_base_lots: self.sell_base_lots,
+            signed_base_lots: self.buy_base_lots as i128 - self.sell_base_lots as i128,
+            buy_count: self.buy_count,
+            sell_count: self.sell_count,
+            average_trade_size_in_base_lots: (trade_count > 0).then(|| {
+                (self.buy_base_lots as f64 + self.sell_base_lots as f64) / trade_count as f64
+            }),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::enums::Side;
+use crate::instructions::CancelMultipleOrdersByIdParams;
+use crate::market::FIFOOrderId;
+use std::collections::HashSet;
+
+/// A set of order ids split by side, each side in book order (best price first, then time
+/// priority).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderIdsBySide {
+    pub bids: Vec<FIFOOrderId>,
+    pub asks: Vec<FIFOOrderId>,
+}
+
+impl OrderIdsBySide {
+    pub fn from_order_ids(order_ids: impl IntoIterator<Item = FIFOOrderId>) -> Self {
+        let mut order_ids_by_side = Self::default();
+        for order_id in order_ids {
+            match Side::from_order_sequence_number(order_id.order_sequence_number) {
+                Side::Bid => order_ids_by_side.bids.push(order_id),
+                Side::Ask => order_ids_by_side.asks.push(order_id),
+            }
+        }
+        order_ids_by_side.bids.sort();
+        order_ids_by_side.asks.sort();
+        order_ids_by_side
+    }
+
+    pub fn get(&self, side: Side) -> &[FIFOOrderId] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bids.len() + self.asks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Returns the order ids, bids first.
+    pub fn iter(&self) -> impl Iterator<Item = &FIFOOrderId> {
+        self.bids.iter().chain(self.asks.iter())
+    }
+
+    /// Returns the parameters to cancel every order in the set, bids first.
+    pub fn to_cancel_params(&self) -> CancelMultipleOrdersByIdParams {
+        CancelMultipleOrdersByIdParams::from_order_ids(self.iter())
+    }
+}
+
+/// Returns the orders in `current` that are not in `desired`: the orders to cancel to bring the
+/// resting orders in line with the desired quotes. Orders are matched by sequence number, so
+/// duplicates in `current` are cancelled once.
+pub fn orders_to_cancel(current: &[FIFOOrderId], desired: &[FIFOOrderId]) -> OrderIdsBySide {
+    difference(current, desired)
+}
+
+/// Returns the orders in `expected` that are no longer in `current`, e.g. the orders of a
+/// quoting loop that were filled or cancelled since the book was last read.
+pub fn orders_no_longer_resting(
+    expected: &[FIFOOrderId],
+    current: &[FIFOOrderId],
+) -> OrderIdsBySide {
+    difference(expected, current)
+}
+
+fn difference(left: &[FIFOOrderId], right: &[FIFOOrderId]) -> OrderIdsBySide {
+    let right = right
+        .iter()
+        .map(|order_id| order_id.order_sequence_number)
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    OrderIdsBySide::from_order_ids(left.iter().copied().filter(|order_id| {
+        !right.contains(&order_id.order_sequence_number)
+            && seen.insert(order_id.order_sequence_number)
+    }))
+}
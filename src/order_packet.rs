@@ -1,5 +1,7 @@
 use crate::enums::{SelfTradeBehavior, Side};
+use crate::units::{BaseLots, QuoteLots, Ticks};
 use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
 
 /// An enum representing a new order.
 #[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug)]
@@ -10,10 +12,10 @@ pub enum OrderPacket {
         side: Side,
 
         /// The price of the order, in ticks
-        price_in_ticks: u64,
+        price_in_ticks: Ticks,
 
         /// Number of base lots to place on the book
-        num_base_lots: u64,
+        num_base_lots: BaseLots,
 
         /// Client order id used to identify the order in the response to the client
         client_order_id: u128,
@@ -33,10 +35,10 @@ pub enum OrderPacket {
         side: Side,
 
         /// The price of the order, in ticks
-        price_in_ticks: u64,
+        price_in_ticks: Ticks,
 
         /// Total number of base lots to place on the book or fill at a better price
-        num_base_lots: u64,
+        num_base_lots: BaseLots,
 
         /// How the matching engine should handle a self trade
         self_trade_behavior: SelfTradeBehavior,
@@ -64,23 +66,23 @@ pub enum OrderPacket {
         /// to purchase 1 lot with the price_in_ticks parameter set to 10, then the order will never
         /// be matched at a price higher than 10 ticks. If this value is None, then the order
         /// is treated as a market order.
-        price_in_ticks: Option<u64>,
+        price_in_ticks: Option<Ticks>,
 
         /// The number of base lots to fill against the order book. Either this parameter or the `num_quote_lots`
         /// parameter must be set to a nonzero value.
-        num_base_lots: u64,
+        num_base_lots: BaseLots,
 
         /// The number of quote lots to fill against the order book. Either this parameter or the `num_base_lots`
         /// parameter must be set to a nonzero value.
-        num_quote_lots: u64,
+        num_quote_lots: QuoteLots,
 
         /// The minimum number of base lots to fill against the order book. If the order does not fill
         /// this many base lots, it will be voided.
-        min_base_lots_to_fill: u64,
+        min_base_lots_to_fill: BaseLots,
 
         /// The minimum number of quote lots to fill against the order book. If the order does not fill
         /// this many quote lots, it will be voided.
-        min_quote_lots_to_fill: u64,
+        min_quote_lots_to_fill: QuoteLots,
 
         /// How the matching engine should handle a self trade.
         self_trade_behavior: SelfTradeBehavior,
@@ -97,6 +99,53 @@ pub enum OrderPacket {
 }
 
 impl OrderPacket {
+    pub fn side(&self) -> Side {
+        match *self {
+            OrderPacket::PostOnly { side, .. }
+            | OrderPacket::Limit { side, .. }
+            | OrderPacket::ImmediateOrCancel { side, .. } => side,
+        }
+    }
+
+    /// Returns the limit price, or None for a market order.
+    pub fn price(&self) -> Option<Ticks> {
+        match *self {
+            OrderPacket::PostOnly { price_in_ticks, .. }
+            | OrderPacket::Limit { price_in_ticks, .. } => Some(price_in_ticks),
+            OrderPacket::ImmediateOrCancel { price_in_ticks, .. } => price_in_ticks,
+        }
+    }
+
+    pub fn base_lots(&self) -> BaseLots {
+        match *self {
+            OrderPacket::PostOnly { num_base_lots, .. }
+            | OrderPacket::Limit { num_base_lots, .. }
+            | OrderPacket::ImmediateOrCancel { num_base_lots, .. } => num_base_lots,
+        }
+    }
+
+    /// Returns the quote lot budget of an IOC order sized in quote lots, and zero otherwise.
+    pub fn quote_lots(&self) -> QuoteLots {
+        match *self {
+            OrderPacket::ImmediateOrCancel { num_quote_lots, .. } => num_quote_lots,
+            _ => QuoteLots::ZERO,
+        }
+    }
+
+    pub fn client_order_id(&self) -> u128 {
+        match *self {
+            OrderPacket::PostOnly {
+                client_order_id, ..
+            }
+            | OrderPacket::Limit {
+                client_order_id, ..
+            }
+            | OrderPacket::ImmediateOrCancel {
+                client_order_id, ..
+            } => client_order_id,
+        }
+    }
+
     pub fn is_take_only(&self) -> bool {
         match self {
             OrderPacket::PostOnly { .. } => false,
@@ -105,7 +154,11 @@ impl OrderPacket {
         }
     }
 
-    pub fn new_post_only_default(side: Side, price_in_ticks: u64, num_base_lots: u64) -> Self {
+    pub fn new_post_only_default(
+        side: Side,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
+    ) -> Self {
         Self::PostOnly {
             side,
             price_in_ticks,
@@ -118,8 +171,8 @@ impl OrderPacket {
 
     pub fn new_post_only_default_with_client_order_id(
         side: Side,
-        price_in_ticks: u64,
-        num_base_lots: u64,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
         client_order_id: u128,
     ) -> Self {
         Self::PostOnly {
@@ -134,8 +187,8 @@ impl OrderPacket {
 
     pub fn new_adjustable_post_only_default_with_client_order_id(
         side: Side,
-        price_in_ticks: u64,
-        num_base_lots: u64,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
         client_order_id: u128,
     ) -> Self {
         Self::PostOnly {
@@ -150,8 +203,8 @@ impl OrderPacket {
 
     pub fn new_post_only(
         side: Side,
-        price_in_ticks: u64,
-        num_base_lots: u64,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
         client_order_id: u128,
         reject_post_only: bool,
         use_only_deposited_funds: bool,
@@ -166,7 +219,11 @@ impl OrderPacket {
         }
     }
 
-    pub fn new_limit_order_default(side: Side, price_in_ticks: u64, num_base_lots: u64) -> Self {
+    pub fn new_limit_order_default(
+        side: Side,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
+    ) -> Self {
         Self::new_limit_order(
             side,
             price_in_ticks,
@@ -180,8 +237,8 @@ impl OrderPacket {
 
     pub fn new_limit_order_default_with_client_order_id(
         side: Side,
-        price_in_ticks: u64,
-        num_lots: u64,
+        price_in_ticks: Ticks,
+        num_lots: BaseLots,
         client_order_id: u128,
     ) -> Self {
         Self::new_limit_order(
@@ -197,8 +254,8 @@ impl OrderPacket {
 
     pub fn new_limit_order(
         side: Side,
-        price_in_ticks: u64,
-        num_base_lots: u64,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -216,8 +273,8 @@ impl OrderPacket {
     }
 
     pub fn new_fok_sell_with_limit_price(
-        target_price_in_ticks: u64,
-        base_lot_budget: u64,
+        target_price_in_ticks: Ticks,
+        base_lot_budget: BaseLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -227,9 +284,9 @@ impl OrderPacket {
             Side::Ask,
             Some(target_price_in_ticks),
             base_lot_budget,
-            0,
+            QuoteLots::ZERO,
             base_lot_budget,
-            0,
+            QuoteLots::ZERO,
             self_trade_behavior,
             match_limit,
             client_order_id,
@@ -238,8 +295,8 @@ impl OrderPacket {
     }
 
     pub fn new_fok_buy_with_limit_price(
-        target_price_in_ticks: u64,
-        base_lot_budget: u64,
+        target_price_in_ticks: Ticks,
+        base_lot_budget: BaseLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -249,9 +306,9 @@ impl OrderPacket {
             Side::Bid,
             Some(target_price_in_ticks),
             base_lot_budget,
-            0,
+            QuoteLots::ZERO,
             base_lot_budget,
-            0,
+            QuoteLots::ZERO,
             self_trade_behavior,
             match_limit,
             client_order_id,
@@ -260,8 +317,8 @@ impl OrderPacket {
     }
 
     pub fn new_ioc_sell_with_limit_price(
-        price_in_ticks: u64,
-        num_base_lots: u64,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -271,9 +328,9 @@ impl OrderPacket {
             Side::Ask,
             Some(price_in_ticks),
             num_base_lots,
-            0,
-            0,
-            0,
+            QuoteLots::ZERO,
+            BaseLots::ZERO,
+            QuoteLots::ZERO,
             self_trade_behavior,
             match_limit,
             client_order_id,
@@ -282,8 +339,8 @@ impl OrderPacket {
     }
 
     pub fn new_ioc_buy_with_limit_price(
-        price_in_ticks: u64,
-        num_quote_lots: u64,
+        price_in_ticks: Ticks,
+        num_quote_lots: QuoteLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -292,10 +349,10 @@ impl OrderPacket {
         Self::new_ioc(
             Side::Bid,
             Some(price_in_ticks),
-            0,
+            BaseLots::ZERO,
             num_quote_lots,
-            0,
-            0,
+            BaseLots::ZERO,
+            QuoteLots::ZERO,
             self_trade_behavior,
             match_limit,
             client_order_id,
@@ -305,8 +362,8 @@ impl OrderPacket {
 
     pub fn new_ioc_by_lots(
         side: Side,
-        price_in_ticks: u64,
-        base_lot_budget: u64,
+        price_in_ticks: Ticks,
+        base_lot_budget: BaseLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -316,9 +373,9 @@ impl OrderPacket {
             side,
             Some(price_in_ticks),
             base_lot_budget,
-            0,
-            0,
-            0,
+            QuoteLots::ZERO,
+            BaseLots::ZERO,
+            QuoteLots::ZERO,
             self_trade_behavior,
             match_limit,
             client_order_id,
@@ -326,14 +383,17 @@ impl OrderPacket {
         )
     }
 
-    pub fn new_ioc_buy_with_slippage(quote_lots_in: u64, min_base_lots_out: u64) -> Self {
+    pub fn new_ioc_buy_with_slippage(
+        quote_lots_in: QuoteLots,
+        min_base_lots_out: BaseLots,
+    ) -> Self {
         Self::new_ioc(
             Side::Bid,
             None,
-            0,
+            BaseLots::ZERO,
             quote_lots_in,
             min_base_lots_out,
-            0,
+            QuoteLots::ZERO,
             SelfTradeBehavior::CancelProvide,
             None,
             0,
@@ -341,13 +401,16 @@ impl OrderPacket {
         )
     }
 
-    pub fn new_ioc_sell_with_slippage(base_lots_in: u64, min_quote_lots_out: u64) -> Self {
+    pub fn new_ioc_sell_with_slippage(
+        base_lots_in: BaseLots,
+        min_quote_lots_out: QuoteLots,
+    ) -> Self {
         Self::new_ioc(
             Side::Ask,
             None,
             base_lots_in,
-            0,
-            0,
+            QuoteLots::ZERO,
+            BaseLots::ZERO,
             min_quote_lots_out,
             SelfTradeBehavior::CancelProvide,
             None,
@@ -359,11 +422,11 @@ impl OrderPacket {
     #[allow(clippy::too_many_arguments)]
     pub fn new_ioc(
         side: Side,
-        price_in_ticks: Option<u64>,
-        num_base_lots: u64,
-        num_quote_lots: u64,
-        min_base_lots_to_fill: u64,
-        min_quote_lots_to_fill: u64,
+        price_in_ticks: Option<Ticks>,
+        num_base_lots: BaseLots,
+        num_quote_lots: QuoteLots,
+        min_base_lots_to_fill: BaseLots,
+        min_quote_lots_to_fill: QuoteLots,
         self_trade_behavior: SelfTradeBehavior,
         match_limit: Option<u64>,
         client_order_id: u128,
@@ -383,3 +446,288 @@ impl OrderPacket {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum OrderPacketBuilderError {
+    #[error("Post only and limit orders require a limit price")]
+    MissingPrice,
+    #[error("The order has no size")]
+    MissingSize,
+    #[error("Only immediate or cancel orders can be sized in quote lots or have minimum fills")]
+    IocOnlyField,
+    #[error(
+        "Post only orders never match, so they cannot have a match limit or self trade behavior"
+    )]
+    PostOnlyMatchingField,
+    #[error("reject_post_only only applies to post only orders")]
+    RejectPostOnlyWithoutPostOnly,
+}
+
+/// Settings applied to orders that do not set them explicitly, so that they can be configured
+/// once for all call sites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderDefaults {
+    /// Self trade behavior of limit and immediate or cancel orders.
+    pub self_trade_behavior: SelfTradeBehavior,
+    /// Match limit of limit and immediate or cancel orders.
+    pub match_limit: Option<u64>,
+    /// Whether post only orders that would cross are rejected rather than amended.
+    pub reject_post_only: bool,
+}
+
+impl Default for OrderDefaults {
+    fn default() -> Self {
+        Self {
+            self_trade_behavior: SelfTradeBehavior::CancelProvide,
+            match_limit: None,
+            reject_post_only: true,
+        }
+    }
+}
+
+impl OrderDefaults {
+    /// Returns a builder for an order on `side` that uses these defaults.
+    pub fn builder(&self, side: Side) -> OrderPacketBuilder {
+        OrderPacket::builder(side).defaults(*self)
+    }
+
+    pub fn post_only(
+        &self,
+        side: Side,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
+        client_order_id: u128,
+    ) -> OrderPacket {
+        OrderPacket::new_post_only(
+            side,
+            price_in_ticks,
+            num_base_lots,
+            client_order_id,
+            self.reject_post_only,
+            false,
+        )
+    }
+
+    pub fn limit_order(
+        &self,
+        side: Side,
+        price_in_ticks: Ticks,
+        num_base_lots: BaseLots,
+        client_order_id: u128,
+    ) -> OrderPacket {
+        OrderPacket::new_limit_order(
+            side,
+            price_in_ticks,
+            num_base_lots,
+            self.self_trade_behavior,
+            self.match_limit,
+            client_order_id,
+            false,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OrderKind {
+    PostOnly,
+    Limit,
+    ImmediateOrCancel,
+}
+
+/// Builds an `OrderPacket` from named settings, checking that they are consistent.
+///
+/// Orders default to limit orders with a client order id of 0. Settings that are not set
+/// explicitly are taken from `OrderDefaults`, which defaults to `SelfTradeBehavior::CancelProvide`
+/// and no match limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderPacketBuilder {
+    side: Side,
+    kind: OrderKind,
+    price_in_ticks: Option<Ticks>,
+    num_base_lots: BaseLots,
+    num_quote_lots: QuoteLots,
+    min_base_lots_to_fill: BaseLots,
+    min_quote_lots_to_fill: QuoteLots,
+    self_trade_behavior: Option<SelfTradeBehavior>,
+    match_limit: Option<u64>,
+    client_order_id: u128,
+    reject_post_only: Option<bool>,
+    use_only_deposited_funds: bool,
+    fill_or_kill: bool,
+    defaults: OrderDefaults,
+}
+
+impl OrderPacket {
+    pub fn builder(side: Side) -> OrderPacketBuilder {
+        OrderPacketBuilder {
+            side,
+            kind: OrderKind::Limit,
+            price_in_ticks: None,
+            num_base_lots: BaseLots::ZERO,
+            num_quote_lots: QuoteLots::ZERO,
+            min_base_lots_to_fill: BaseLots::ZERO,
+            min_quote_lots_to_fill: QuoteLots::ZERO,
+            self_trade_behavior: None,
+            match_limit: None,
+            client_order_id: 0,
+            reject_post_only: None,
+            use_only_deposited_funds: false,
+            fill_or_kill: false,
+            defaults: OrderDefaults::default(),
+        }
+    }
+}
+
+impl OrderPacketBuilder {
+    pub fn limit_price(mut self, price_in_ticks: Ticks) -> Self {
+        self.price_in_ticks = Some(price_in_ticks);
+        self
+    }
+
+    pub fn base_lots(mut self, num_base_lots: BaseLots) -> Self {
+        self.num_base_lots = num_base_lots;
+        self
+    }
+
+    pub fn quote_lots(mut self, num_quote_lots: QuoteLots) -> Self {
+        self.num_quote_lots = num_quote_lots;
+        self
+    }
+
+    pub fn post_only(mut self) -> Self {
+        self.kind = OrderKind::PostOnly;
+        self.fill_or_kill = false;
+        self
+    }
+
+    pub fn limit(mut self) -> Self {
+        self.kind = OrderKind::Limit;
+        self.fill_or_kill = false;
+        self
+    }
+
+    pub fn ioc(mut self) -> Self {
+        self.kind = OrderKind::ImmediateOrCancel;
+        self.fill_or_kill = false;
+        self
+    }
+
+    /// Makes the order an immediate or cancel order that must fill its whole size.
+    pub fn fok(mut self) -> Self {
+        self.kind = OrderKind::ImmediateOrCancel;
+        self.fill_or_kill = true;
+        self
+    }
+
+    pub fn min_base_lots_to_fill(mut self, min_base_lots_to_fill: BaseLots) -> Self {
+        self.min_base_lots_to_fill = min_base_lots_to_fill;
+        self
+    }
+
+    pub fn min_quote_lots_to_fill(mut self, min_quote_lots_to_fill: QuoteLots) -> Self {
+        self.min_quote_lots_to_fill = min_quote_lots_to_fill;
+        self
+    }
+
+    pub fn self_trade(mut self, self_trade_behavior: SelfTradeBehavior) -> Self {
+        self.self_trade_behavior = Some(self_trade_behavior);
+        self
+    }
+
+    pub fn match_limit(mut self, match_limit: u64) -> Self {
+        self.match_limit = Some(match_limit);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: u128) -> Self {
+        self.client_order_id = client_order_id;
+        self
+    }
+
+    /// For post only orders, whether to reject the order if it would cross (the default) or
+    /// amend it to the best non-crossing price.
+    pub fn reject_post_only(mut self, reject_post_only: bool) -> Self {
+        self.reject_post_only = Some(reject_post_only);
+        self
+    }
+
+    pub fn use_only_deposited_funds(mut self, use_only_deposited_funds: bool) -> Self {
+        self.use_only_deposited_funds = use_only_deposited_funds;
+        self
+    }
+
+    /// Replaces the defaults used for settings that are not set explicitly.
+    pub fn defaults(mut self, defaults: OrderDefaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    pub fn build(self) -> Result<OrderPacket, OrderPacketBuilderError> {
+        if self.num_base_lots == BaseLots::ZERO && self.num_quote_lots == QuoteLots::ZERO {
+            return Err(OrderPacketBuilderError::MissingSize);
+        }
+        if self.kind != OrderKind::ImmediateOrCancel {
+            if self.num_quote_lots != QuoteLots::ZERO
+                || self.min_base_lots_to_fill != BaseLots::ZERO
+                || self.min_quote_lots_to_fill != QuoteLots::ZERO
+            {
+                return Err(OrderPacketBuilderError::IocOnlyField);
+            }
+            if self.price_in_ticks.is_none() {
+                return Err(OrderPacketBuilderError::MissingPrice);
+            }
+        }
+        if self.kind != OrderKind::PostOnly && self.reject_post_only.is_some() {
+            return Err(OrderPacketBuilderError::RejectPostOnlyWithoutPostOnly);
+        }
+        let self_trade_behavior = self
+            .self_trade_behavior
+            .unwrap_or(self.defaults.self_trade_behavior);
+        let match_limit = self.match_limit.or(self.defaults.match_limit);
+        Ok(match self.kind {
+            OrderKind::PostOnly => {
+                if self.self_trade_behavior.is_some() || self.match_limit.is_some() {
+                    return Err(OrderPacketBuilderError::PostOnlyMatchingField);
+                }
+                OrderPacket::new_post_only(
+                    self.side,
+                    self.price_in_ticks.unwrap_or_default(),
+                    self.num_base_lots,
+                    self.client_order_id,
+                    self.reject_post_only
+                        .unwrap_or(self.defaults.reject_post_only),
+                    self.use_only_deposited_funds,
+                )
+            }
+            OrderKind::Limit => OrderPacket::new_limit_order(
+                self.side,
+                self.price_in_ticks.unwrap_or_default(),
+                self.num_base_lots,
+                self_trade_behavior,
+                match_limit,
+                self.client_order_id,
+                self.use_only_deposited_funds,
+            ),
+            OrderKind::ImmediateOrCancel => OrderPacket::new_ioc(
+                self.side,
+                self.price_in_ticks,
+                self.num_base_lots,
+                self.num_quote_lots,
+                if self.fill_or_kill {
+                    self.num_base_lots
+                } else {
+                    self.min_base_lots_to_fill
+                },
+                if self.fill_or_kill {
+                    self.num_quote_lots
+                } else {
+                    self.min_quote_lots_to_fill
+                },
+                self_trade_behavior,
+                match_limit,
+                self.client_order_id,
+                self.use_only_deposited_funds,
+            ),
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use crate::enums::{SelfTradeBehavior, Side};
+use crate::instructions::create_new_order_instruction;
+use crate::market_metadata::MarketMetadata;
+use crate::order_packet::OrderPacket;
+use crate::rounding::{round_ticks_for_side, RoundingDirection};
+use crate::units::{BaseLots, QuoteLots, Ticks};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use thiserror::Error;
+
+/// How long an order stays active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// Rest on the book until cancelled or filled.
+    GoodTillCancelled,
+    /// Fill as much as possible immediately and cancel the rest.
+    ImmediateOrCancel,
+    /// Fill the entire size immediately or not at all.
+    FillOrKill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PhoenixOrderError {
+    #[error("Price must be a positive finite number")]
+    InvalidPrice,
+    #[error("Size must be a positive finite number")]
+    InvalidSize,
+    #[error("Price is below the market's tick size")]
+    PriceBelowTickSize,
+    #[error("Size is below the market's base lot size")]
+    SizeBelowLotSize,
+    #[error("Post only orders must be good till cancelled")]
+    PostOnlyRequiresGoodTillCancelled,
+}
+
+/// An order expressed in human readable units: a limit price in quote units per base unit and
+/// a size in base units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhoenixOrder {
+    pub side: Side,
+    pub price: f64,
+    pub size: f64,
+    pub time_in_force: TimeInForce,
+    pub post_only: bool,
+    pub client_order_id: u128,
+    pub self_trade_behavior: SelfTradeBehavior,
+    pub use_only_deposited_funds: bool,
+}
+
+impl PhoenixOrder {
+    pub fn limit(side: Side, price: f64, size: f64) -> Self {
+        Self {
+            side,
+            price,
+            size,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            post_only: false,
+            client_order_id: 0,
+            self_trade_behavior: SelfTradeBehavior::CancelProvide,
+            use_only_deposited_funds: false,
+        }
+    }
+
+    pub fn post_only(side: Side, price: f64, size: f64) -> Self {
+        Self {
+            post_only: true,
+            ..Self::limit(side, price, size)
+        }
+    }
+
+    pub fn immediate_or_cancel(side: Side, price: f64, size: f64) -> Self {
+        Self {
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            ..Self::limit(side, price, size)
+        }
+    }
+
+    pub fn fill_or_kill(side: Side, price: f64, size: f64) -> Self {
+        Self {
+            time_in_force: TimeInForce::FillOrKill,
+            ..Self::limit(side, price, size)
+        }
+    }
+
+    pub fn with_client_order_id(self, client_order_id: u128) -> Self {
+        Self {
+            client_order_id,
+            ..self
+        }
+    }
+
+    pub fn with_self_trade_behavior(self, self_trade_behavior: SelfTradeBehavior) -> Self {
+        Self {
+            self_trade_behavior,
+            ..self
+        }
+    }
+
+    pub fn with_only_deposited_funds(self, use_only_deposited_funds: bool) -> Self {
+        Self {
+            use_only_deposited_funds,
+            ..self
+        }
+    }
+
+    /// Converts the order to ticks and lots. Prices that fall between ticks are rounded to the
+    /// less aggressive tick (down for bids, up for asks) and sizes are rounded down to whole lots.
+    pub fn to_order_packet(
+        &self,
+        metadata: &MarketMetadata,
+    ) -> Result<OrderPacket, PhoenixOrderError> {
+        if !self.price.is_finite() || self.price <= 0.0 {
+            return Err(PhoenixOrderError::InvalidPrice);
+        }
+        if !self.size.is_finite() || self.size <= 0.0 {
+            return Err(PhoenixOrderError::InvalidSize);
+        }
+        if self.post_only && self.time_in_force != TimeInForce::GoodTillCancelled {
+            return Err(PhoenixOrderError::PostOnlyRequiresGoodTillCancelled);
+        }
+
+        let price_in_ticks = Ticks::new(round_ticks_for_side(
+            self.side,
+            self.price / metadata.ticks_to_float_price(1),
+        ));
+        let num_base_lots = BaseLots::new(
+            RoundingDirection::Down.round(self.size / metadata.raw_to_ui_size(1)) as u64,
+        );
+        if price_in_ticks == Ticks::ZERO {
+            return Err(PhoenixOrderError::PriceBelowTickSize);
+        }
+        if num_base_lots == BaseLots::ZERO {
+            return Err(PhoenixOrderError::SizeBelowLotSize);
+        }
+
+        Ok(match (self.time_in_force, self.post_only) {
+            (_, true) => OrderPacket::new_post_only(
+                self.side,
+                price_in_ticks,
+                num_base_lots,
+                self.client_order_id,
+                true,
+                self.use_only_deposited_funds,
+            ),
+            (TimeInForce::GoodTillCancelled, false) => OrderPacket::new_limit_order(
+                self.side,
+                price_in_ticks,
+                num_base_lots,
+                self.self_trade_behavior,
+                None,
+                self.client_order_id,
+                self.use_only_deposited_funds,
+            ),
+            (TimeInForce::ImmediateOrCancel, false) => OrderPacket::new_ioc_by_lots(
+                self.side,
+                price_in_ticks,
+                num_base_lots,
+                self.self_trade_behavior,
+                None,
+                self.client_order_id,
+                self.use_only_deposited_funds,
+            ),
+            (TimeInForce::FillOrKill, false) => OrderPacket::new_ioc(
+                self.side,
+                Some(price_in_ticks),
+                num_base_lots,
+                QuoteLots::ZERO,
+                num_base_lots,
+                QuoteLots::ZERO,
+                self.self_trade_behavior,
+                None,
+                self.client_order_id,
+                self.use_only_deposited_funds,
+            ),
+        })
+    }
+
+    /// Builds the instruction to place the order, settling through the trader's associated
+    /// token accounts.
+    pub fn to_instruction(
+        &self,
+        market: &Pubkey,
+        trader: &Pubkey,
+        metadata: &MarketMetadata,
+    ) -> Result<Instruction, PhoenixOrderError> {
+        Ok(create_new_order_instruction(
+            market,
+            trader,
+            &metadata.base_mint,
+            &metadata.quote_mint,
+            &self.to_order_packet(metadata)?,
+        ))
+    }
+}
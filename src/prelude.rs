@@ -0,0 +1,39 @@
+//! Commonly used types and functions, for glob importing with `use phoenix_types::prelude::*;`.
+
+pub use crate::dispatch::{
+    load_with_dispatch, load_with_dispatch_mut, load_with_dispatch_mut_checked, MarketWrapper,
+    MarketWrapperMut,
+};
+pub use crate::enums::{SelfTradeBehavior, Side};
+pub use crate::events::{
+    parse_log_instruction_data, AuditLogHeader, MarketEvent, PhoenixTransactionEvents,
+};
+pub use crate::fees::FeeSchedule;
+pub use crate::instructions::{
+    create_cancel_all_and_place_instructions, create_cancel_all_order_with_free_funds_instruction,
+    create_cancel_all_orders_instruction, create_cancel_multiple_orders_by_id_instruction,
+    create_cancel_multiple_orders_by_id_with_free_funds_instruction,
+    create_cancel_up_to_instruction, create_cancel_up_to_with_free_funds_instruction,
+    create_deposit_funds_instruction, create_deposit_funds_instruction_ui,
+    create_new_multiple_order_instruction, create_new_multiple_order_with_free_funds_instruction,
+    create_new_order_instruction, create_new_order_with_free_funds_instruction,
+    create_replace_order_instructions, create_request_seat_instruction,
+    create_swap_instruction_with_min_out, create_withdraw_funds_instruction, get_seat_address,
+    get_vault_address, CancelMultipleOrdersByIdParams, CancelOrderParams, CancelUpToParams,
+    CondensedOrder, DecodedPhoenixInstruction, DepositParams, MultipleOrderPacket,
+    PhoenixInstruction, UiDeposit, WithdrawParams,
+};
+pub use crate::ladder::DetailedLadder;
+pub use crate::ladder::MakerLadder;
+pub use crate::market::{
+    FIFOOrderId, FIFORestingOrder, Ladder, LadderOrder, Market, MarketHeader, MarketSizeParams,
+    TraderState,
+};
+pub use crate::market_metadata::MarketMetadata;
+pub use crate::order_packet::{OrderDefaults, OrderPacket};
+pub use crate::rounding::{round_ticks_for_side, RoundingDirection};
+pub use crate::snapshot::MarketSnapshot;
+pub use crate::units::{
+    BaseAtoms, BaseLots, BaseLotsPerBaseUnit, QuoteAtoms, QuoteAtomsPerBaseUnit, QuoteLots,
+    QuoteLotsPerBaseUnit, QuoteLotsPerBaseUnitPerTick, Ticks,
+};
@@ -0,0 +1,57 @@
+use crate::market_metadata::MarketMetadata;
+use crate::snapshot::MarketSnapshot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Provides the price analytics mark markets at, in quote units per raw base unit.
+pub trait PriceSource {
+    /// Returns the mark price of `market`, or None if it has no price.
+    fn mark_price(&self, market: &Pubkey) -> Option<f64>;
+}
+
+/// Closures, e.g. over an external oracle.
+impl<F: Fn(&Pubkey) -> Option<f64>> PriceSource for F {
+    fn mark_price(&self, market: &Pubkey) -> Option<f64> {
+        self(market)
+    }
+}
+
+/// Fixed prices per market.
+impl PriceSource for HashMap<Pubkey, f64> {
+    fn mark_price(&self, market: &Pubkey) -> Option<f64> {
+        self.get(market).copied()
+    }
+}
+
+/// Marks markets at the mid price of their books. Markets with an empty side have no price.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MidPriceSource {
+    prices: HashMap<Pubkey, f64>,
+}
+
+impl MidPriceSource {
+    /// Computes the mid prices of snapshots keyed by market address.
+    pub fn from_snapshots<'a>(
+        snapshots: impl IntoIterator<Item = (&'a Pubkey, &'a MarketSnapshot)>,
+    ) -> Self {
+        let prices = snapshots
+            .into_iter()
+            .filter_map(|(market, snapshot)| {
+                let (best_bid, _) = snapshot.bids.first()?;
+                let (best_ask, _) = snapshot.asks.first()?;
+                let metadata = MarketMetadata::from_snapshot(snapshot)?;
+                let mid = (metadata.ticks_to_float_price(best_bid.price_in_ticks)
+                    + metadata.ticks_to_float_price(best_ask.price_in_ticks))
+                    / 2.0;
+                Some((*market, mid))
+            })
+            .collect();
+        Self { prices }
+    }
+}
+
+impl PriceSource for MidPriceSource {
+    fn mark_price(&self, market: &Pubkey) -> Option<f64> {
+        self.prices.mark_price(market)
+    }
+}
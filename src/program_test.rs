@@ -0,0 +1,291 @@
+//! Helpers for end-to-end tests against a real Phoenix program with `solana-program-test`.
+//!
+//! The Phoenix program binary must be available to `ProgramTest` as `phoenix_v1.so`, e.g. in
+//! `tests/fixtures` or the directory named by `SBF_OUT_DIR`.
+
+use crate::dispatch::get_market_size;
+use crate::instructions::{
+    create_deposit_funds_instruction, create_request_seat_instruction, get_seat_address,
+    get_vault_address, DepositParams, InitializeParams,
+};
+use crate::market::{MarketHeader, MarketStatus, SeatApprovalStatus};
+use crate::phoenix_log_authority;
+use crate::snapshot::MarketSnapshot;
+use borsh::BorshSerialize;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+/// The name under which `ProgramTest` looks for the Phoenix program binary.
+pub const PHOENIX_PROGRAM_NAME: &str = "phoenix_v1";
+
+// Tags of the admin instructions used to set up markets. These are not part of
+// `PhoenixInstruction`, which only covers the instructions available to traders.
+const INITIALIZE_MARKET_TAG: u8 = 100;
+const CHANGE_MARKET_STATUS_TAG: u8 = 103;
+const CHANGE_SEAT_STATUS_TAG: u8 = 104;
+
+/// Lamports sent to each seeded maker to pay for their seat and token accounts.
+const MAKER_LAMPORTS: u64 = 10_000_000_000;
+
+/// Returns a `ProgramTest` with the Phoenix program loaded from its compiled binary.
+pub fn phoenix_program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(PHOENIX_PROGRAM_NAME, crate::id(), None);
+    program_test.prefer_bpf(true);
+    program_test
+}
+
+/// The addresses of a market created by `create_market`. The context's payer is the market
+/// authority and the mint authority of both mints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketFixture {
+    pub market: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+}
+
+/// Signs `instructions` with the context's payer and `signers` and processes them.
+pub async fn process_instructions(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Creates two mints and an active market trading them.
+///
+/// # Panics
+///
+/// Panics if `params.market_size_params` is not a supported market size.
+pub async fn create_market(
+    context: &mut ProgramTestContext,
+    base_decimals: u8,
+    quote_decimals: u8,
+    params: &InitializeParams,
+) -> Result<MarketFixture, BanksClientError> {
+    let payer = context.payer.pubkey();
+    let rent = context.banks_client.get_rent().await?;
+    let base_mint = Keypair::new();
+    let quote_mint = Keypair::new();
+    let market = Keypair::new();
+
+    let mut instructions = vec![];
+    for (mint, decimals) in [(&base_mint, base_decimals), (&quote_mint, quote_decimals)] {
+        instructions.push(system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ));
+        instructions.push(
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        );
+    }
+    process_instructions(context, &instructions, &[&base_mint, &quote_mint]).await?;
+
+    let market_size = std::mem::size_of::<MarketHeader>()
+        + get_market_size(&params.market_size_params).expect("Unsupported market size");
+    let (base_vault, _) = get_vault_address(&market.pubkey(), &base_mint.pubkey());
+    let (quote_vault, _) = get_vault_address(&market.pubkey(), &quote_mint.pubkey());
+    let initialize_market = Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(market.pubkey(), false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(base_mint.pubkey(), false),
+            AccountMeta::new_readonly(quote_mint.pubkey(), false),
+            AccountMeta::new(base_vault, false),
+            AccountMeta::new(quote_vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: [vec![INITIALIZE_MARKET_TAG], params.try_to_vec().unwrap()].concat(),
+    };
+    let activate_market = Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(market.pubkey(), false),
+            AccountMeta::new_readonly(payer, true),
+        ],
+        data: [
+            vec![CHANGE_MARKET_STATUS_TAG],
+            MarketStatus::Active.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    };
+    process_instructions(
+        context,
+        &[
+            system_instruction::create_account(
+                &payer,
+                &market.pubkey(),
+                rent.minimum_balance(market_size),
+                market_size as u64,
+                &crate::id(),
+            ),
+            initialize_market,
+            activate_market,
+        ],
+        &[&market],
+    )
+    .await?;
+
+    Ok(MarketFixture {
+        market: market.pubkey(),
+        base_mint: base_mint.pubkey(),
+        quote_mint: quote_mint.pubkey(),
+        base_vault,
+        quote_vault,
+    })
+}
+
+/// Funds `maker`, mints `base_atoms` and `quote_atoms` to their associated token accounts, gives
+/// them an approved seat, and deposits as many whole lots of both tokens as possible.
+pub async fn seed_maker(
+    context: &mut ProgramTestContext,
+    fixture: &MarketFixture,
+    maker: &Keypair,
+    base_atoms: u64,
+    quote_atoms: u64,
+) -> Result<(), BanksClientError> {
+    let payer = context.payer.pubkey();
+    let trader = maker.pubkey();
+    let mut instructions = vec![system_instruction::transfer(
+        &payer,
+        &trader,
+        MAKER_LAMPORTS,
+    )];
+    for (mint, amount) in [
+        (fixture.base_mint, base_atoms),
+        (fixture.quote_mint, quote_atoms),
+    ] {
+        instructions.push(create_associated_token_account_idempotent(
+            &payer,
+            &trader,
+            &mint,
+            &spl_token::id(),
+        ));
+        instructions.push(
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint,
+                &get_associated_token_address(&trader, &mint),
+                &payer,
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+    }
+    process_instructions(context, &instructions, &[]).await?;
+
+    let (seat, _) = get_seat_address(&fixture.market, &trader);
+    let approve_seat = Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(fixture.market, false),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new(seat, false),
+        ],
+        data: [
+            vec![CHANGE_SEAT_STATUS_TAG],
+            SeatApprovalStatus::Approved.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    };
+    process_instructions(
+        context,
+        &[
+            create_request_seat_instruction(&trader, &fixture.market),
+            approve_seat,
+        ],
+        &[maker],
+    )
+    .await?;
+
+    let header = get_market_header(context, &fixture.market).await?;
+    let deposit = create_deposit_funds_instruction(
+        &fixture.market,
+        &trader,
+        &fixture.base_mint,
+        &fixture.quote_mint,
+        &DepositParams {
+            quote_lots: quote_atoms / header.get_quote_lot_size(),
+            base_lots: base_atoms / header.get_base_lot_size(),
+        },
+    );
+    process_instructions(context, &[deposit], &[maker]).await
+}
+
+/// Fetches the data of a market account, or returns a `ClientError` if the account does not
+/// exist.
+async fn get_market_account_data(
+    context: &mut ProgramTestContext,
+    market: &Pubkey,
+) -> Result<Vec<u8>, BanksClientError> {
+    context
+        .banks_client
+        .get_account(*market)
+        .await?
+        .map(|account| account.data)
+        .ok_or(BanksClientError::ClientError("Market account not found"))
+}
+
+/// Fetches and decodes the header of a market account. Returns a `ClientError` if the account
+/// does not exist or is too short to hold a header.
+pub async fn get_market_header(
+    context: &mut ProgramTestContext,
+    market: &Pubkey,
+) -> Result<MarketHeader, BanksClientError> {
+    let data = get_market_account_data(context, market).await?;
+    data.get(..std::mem::size_of::<MarketHeader>())
+        .and_then(|bytes| bytemuck::try_pod_read_unaligned::<MarketHeader>(bytes).ok())
+        .ok_or(BanksClientError::ClientError("Invalid market header"))
+}
+
+/// Fetches a market account and decodes it into a snapshot at the current slot. Returns a
+/// `ClientError` if the account does not exist or is not a market.
+pub async fn get_market_snapshot(
+    context: &mut ProgramTestContext,
+    market: &Pubkey,
+) -> Result<MarketSnapshot, BanksClientError> {
+    let slot = context.banks_client.get_root_slot().await?;
+    let data = get_market_account_data(context, market).await?;
+    MarketSnapshot::from_account_data(&data, slot)
+        .ok_or(BanksClientError::ClientError("Invalid market account"))
+}
@@ -0,0 +1,38 @@
+//! Serde helpers that encode `Pubkey`s as base58 strings, for use with `#[serde(with = "...")]`.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(pubkey)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Pubkey::from_str(&s).map_err(D::Error::custom)
+}
+
+/// Encodes the keys of a `HashMap<Pubkey, V>` as base58 strings.
+pub mod map {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    pub fn serialize<V: Serialize, S: Serializer>(
+        map: &HashMap<Pubkey, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(k, v)| (k.to_string(), v)))
+    }
+
+    pub fn deserialize<'de, V: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Pubkey, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(k, v)| Ok((Pubkey::from_str(&k).map_err(D::Error::custom)?, v)))
+            .collect()
+    }
+}
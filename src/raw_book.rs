@@ -0,0 +1,294 @@
+//! Reading of the book trees directly from market account bytes.
+//!
+//! The book trees are walked in place, reading only the nodes on the path to the best orders, so
+//! decoding the touch does not depend on the size of the market. The data only has to extend
+//! past the book trees (see `book_data_len`), so the trader tree at the end of the account can be
+//! left out of the fetched data, e.g. with an RPC data slice.
+//!
+//! This relies on the sokoban layout of `FIFOMarket`: each red-black tree is a root address and
+//! padding, followed by a node allocator holding its size, bump index and free list head, and
+//! then an array of nodes. Each node is 4 registers (left, right, parent, color) followed by its
+//! key and value. Node addresses start at 1, with 0 as the sentinel. Nodes are allocated in
+//! address order up to the bump index, and freed nodes are linked into a free list for reuse.
+
+use crate::enums::Side;
+use crate::market::{
+    aggregate_levels, FIFOOrderId, FIFORestingOrder, Ladder, MarketHeader, MarketSizeParams,
+};
+use std::collections::HashSet;
+
+/// Size of the fixed fields of `FIFOMarket` that precede the bid tree.
+const MARKET_FIELDS_SIZE: usize = 6 * 8;
+
+/// Size of the root address and padding at the start of a red-black tree.
+const TREE_HEADER_SIZE: usize = 16;
+
+/// Size of the size, bump index and free list head of a node allocator.
+const ALLOCATOR_HEADER_SIZE: usize = 16;
+
+const NUM_REGISTERS: usize = 4;
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+/// Freed nodes link to the next free node through their first register.
+const FREE_LIST_NEXT: usize = 0;
+
+const SENTINEL: u32 = 0;
+
+const ORDER_NODE_SIZE: usize = NUM_REGISTERS * 4
+    + std::mem::size_of::<FIFOOrderId>()
+    + std::mem::size_of::<FIFORestingOrder>();
+
+fn tree_size(capacity: usize) -> usize {
+    TREE_HEADER_SIZE + ALLOCATOR_HEADER_SIZE + capacity * ORDER_NODE_SIZE
+}
+
+/// Returns the number of bytes at the start of a market account, including the header, that
+/// hold both book trees.
+pub fn book_data_len(market_size_params: &MarketSizeParams) -> usize {
+    std::mem::size_of::<MarketHeader>()
+        + MARKET_FIELDS_SIZE
+        + tree_size(market_size_params.bids_size as usize)
+        + tree_size(market_size_params.asks_size as usize)
+}
+
+/// One side's tree of orders, read in place from market account bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct RawBookTree<'a> {
+    nodes: &'a [u8],
+    capacity: u32,
+    root: u32,
+    size: u64,
+    bump_index: u32,
+    free_list_head: u32,
+}
+
+impl<'a> RawBookTree<'a> {
+    /// Reads the tree of `side` of the market account `data`, which starts with the market
+    /// header. Returns None if the data does not hold the header and the tree's fields.
+    pub fn new(data: &'a [u8], side: Side) -> Option<Self> {
+        let header_size = std::mem::size_of::<MarketHeader>();
+        let header: MarketHeader =
+            bytemuck::try_pod_read_unaligned(data.get(..header_size)?).ok()?;
+        Self::from_market_bytes(&data[header_size..], &header.market_size_params, side)
+    }
+
+    /// Reads the tree of `side` from the bytes of a market that follow the header, as passed to
+    /// `load_with_dispatch`. Returns None if the data does not hold the tree's fields.
+    pub fn from_market_bytes(
+        market_bytes: &'a [u8],
+        market_size_params: &MarketSizeParams,
+        side: Side,
+    ) -> Option<Self> {
+        let (tree_offset, capacity) = match side {
+            Side::Bid => (MARKET_FIELDS_SIZE, market_size_params.bids_size),
+            Side::Ask => (
+                MARKET_FIELDS_SIZE + tree_size(market_size_params.bids_size as usize),
+                market_size_params.asks_size,
+            ),
+        };
+        let allocator_offset = tree_offset + TREE_HEADER_SIZE;
+        let nodes_offset = allocator_offset + ALLOCATOR_HEADER_SIZE;
+        Some(Self {
+            nodes: market_bytes.get(nodes_offset..).unwrap_or_default(),
+            capacity: u32::try_from(capacity).ok()?,
+            root: read_u32(market_bytes, tree_offset)?,
+            size: u64::from_le_bytes(
+                market_bytes
+                    .get(allocator_offset..allocator_offset + 8)?
+                    .try_into()
+                    .ok()?,
+            ),
+            bump_index: read_u32(market_bytes, allocator_offset + 8)?,
+            free_list_head: read_u32(market_bytes, allocator_offset + 12)?,
+        })
+    }
+
+    /// The number of nodes the tree can hold.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// The number of orders in the tree, as recorded by its allocator.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn root(&self) -> u32 {
+        self.root
+    }
+
+    /// The address of the first node that has never been allocated. Nodes from this address up
+    /// to the capacity are unused.
+    pub fn bump_index(&self) -> u32 {
+        self.bump_index
+    }
+
+    /// The address of the most recently freed node, or `bump_index` if no node is free.
+    pub fn free_list_head(&self) -> u32 {
+        self.free_list_head
+    }
+
+    /// Returns the orders of the tree in price-time priority.
+    pub fn orders(&self) -> RawBookIter<'a> {
+        let mut iter = RawBookIter {
+            tree: *self,
+            stack: vec![],
+            remaining: self.capacity as usize,
+        };
+        iter.push_left_path(self.root);
+        iter
+    }
+
+    /// Returns the addresses on the free list, in the order they would be reused. Stops early
+    /// if the list leaves the allocated nodes or is longer than the capacity.
+    pub fn free_list(&self) -> Vec<u32> {
+        let mut free_list = vec![];
+        let mut address = self.free_list_head;
+        while address != SENTINEL
+            && address < self.bump_index
+            && free_list.len() < self.capacity as usize
+        {
+            free_list.push(address);
+            address = match self.register(address, FREE_LIST_NEXT) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        free_list
+    }
+
+    /// Returns every node that has been allocated, in address order, whether it holds an order
+    /// or is on the free list. Free nodes hold whatever order was last stored in them.
+    pub fn nodes(&self) -> impl Iterator<Item = RawBookNode> + 'a {
+        let tree = *self;
+        let free = self.free_list().into_iter().collect::<HashSet<_>>();
+        let end = self.bump_index.min(self.capacity.saturating_add(1));
+        (1..end).map_while(move |address| {
+            let (order_id, order) = tree.read_order(address)?;
+            Some(RawBookNode {
+                address,
+                order_id,
+                order,
+                is_free: free.contains(&address),
+            })
+        })
+    }
+
+    /// Summarizes how the capacity of the tree is used.
+    pub fn stats(&self) -> RawBookTreeStats {
+        let allocated = self.bump_index.saturating_sub(1).min(self.capacity);
+        let free_nodes = self.free_list().len() as u32;
+        RawBookTreeStats {
+            capacity: self.capacity,
+            used_nodes: allocated.saturating_sub(free_nodes),
+            free_nodes,
+            unallocated_nodes: self.capacity - allocated,
+        }
+    }
+
+    fn node_offset(&self, address: u32) -> Option<usize> {
+        if address == SENTINEL || address > self.capacity {
+            return None;
+        }
+        let offset = (address as usize - 1) * ORDER_NODE_SIZE;
+        (offset + ORDER_NODE_SIZE <= self.nodes.len()).then_some(offset)
+    }
+
+    fn register(&self, address: u32, register: usize) -> Option<u32> {
+        read_u32(self.nodes, self.node_offset(address)? + register * 4)
+    }
+
+    fn read_order(&self, address: u32) -> Option<(FIFOOrderId, FIFORestingOrder)> {
+        let node_offset = self.node_offset(address)?;
+        let key_offset = node_offset + NUM_REGISTERS * 4;
+        let value_offset = key_offset + std::mem::size_of::<FIFOOrderId>();
+        let order_id: FIFOOrderId =
+            bytemuck::try_pod_read_unaligned(&self.nodes[key_offset..value_offset]).ok()?;
+        let order: FIFORestingOrder = bytemuck::try_pod_read_unaligned(
+            &self.nodes[value_offset..node_offset + ORDER_NODE_SIZE],
+        )
+        .ok()?;
+        Some((order_id, order))
+    }
+}
+
+/// An allocated node of a book tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBookNode {
+    /// The node's address in the allocator, starting at 1.
+    pub address: u32,
+    pub order_id: FIFOOrderId,
+    pub order: FIFORestingOrder,
+    /// Whether the node is on the free list rather than holding a resting order.
+    pub is_free: bool,
+}
+
+/// How the nodes of a book tree are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBookTreeStats {
+    pub capacity: u32,
+    /// Nodes holding resting orders.
+    pub used_nodes: u32,
+    /// Nodes that were freed and are waiting on the free list to be reused.
+    pub free_nodes: u32,
+    /// Nodes that have never been allocated.
+    pub unallocated_nodes: u32,
+}
+
+/// Iterates over the orders of one side of the book in price-time priority, reading nodes
+/// directly from account bytes. Iteration stops early if a node address is out of bounds.
+pub struct RawBookIter<'a> {
+    tree: RawBookTree<'a>,
+    stack: Vec<u32>,
+    remaining: usize,
+}
+
+impl<'a> RawBookIter<'a> {
+    /// Creates an iterator over the side of the book of the market account `data`, which starts
+    /// with the market header. Returns None if the data does not hold the header and the tree's
+    /// fields.
+    pub fn new(data: &'a [u8], side: Side) -> Option<Self> {
+        Some(RawBookTree::new(data, side)?.orders())
+    }
+
+    /// Pushes `address` and its chain of left children.
+    fn push_left_path(&mut self, mut address: u32) {
+        while address != SENTINEL && self.stack.len() < self.tree.capacity as usize {
+            self.stack.push(address);
+            address = self.tree.register(address, LEFT).unwrap_or(SENTINEL);
+        }
+    }
+}
+
+impl Iterator for RawBookIter<'_> {
+    type Item = (FIFOOrderId, FIFORestingOrder);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Bounds the traversal of corrupted trees that contain cycles.
+        self.remaining = self.remaining.checked_sub(1)?;
+        let address = self.stack.pop()?;
+        let order = self.tree.read_order(address)?;
+        let right = self.tree.register(address, RIGHT)?;
+        self.push_left_path(right);
+        Some(order)
+    }
+}
+
+/// Returns the top `levels` levels of each side of the book of the market account `data`,
+/// reading only the nodes needed. The data may be truncated after `book_data_len` bytes.
+pub fn load_top_of_book(data: &[u8], levels: u64) -> Option<Ladder> {
+    Some(Ladder {
+        bids: aggregate_levels(RawBookIter::new(data, Side::Bid)?, levels),
+        asks: aggregate_levels(RawBookIter::new(data, Side::Ask)?, levels),
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
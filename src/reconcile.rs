@@ -0,0 +1,131 @@
+use crate::enums::Side;
+use crate::events::PhoenixTransactionEvents;
+use crate::market::{FIFOOrderId, FIFORestingOrder};
+use crate::snapshot::MarketSnapshot;
+use crate::trader_index::TraderIndexMap;
+use std::collections::BTreeMap;
+
+/// A difference between a book derived by replaying events and the book it should match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The order rests in the expected book but not in the replayed book.
+    MissingOrder {
+        side: Side,
+        order_id: FIFOOrderId,
+        expected: FIFORestingOrder,
+    },
+    /// The order rests in the replayed book but not in the expected book.
+    UnexpectedOrder {
+        side: Side,
+        order_id: FIFOOrderId,
+        replayed: FIFORestingOrder,
+    },
+    /// The order rests in both books with different contents.
+    OrderMismatch {
+        side: Side,
+        order_id: FIFOOrderId,
+        replayed: FIFORestingOrder,
+        expected: FIFORestingOrder,
+    },
+    /// The replayed market sequence number differs from the expected one.
+    SequenceNumberMismatch { replayed: u64, expected: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    /// The number of logs applied to the initial snapshot.
+    pub logs_applied: usize,
+
+    /// The number of logs skipped because they fell outside the range between the snapshots.
+    pub logs_skipped: usize,
+
+    /// The snapshot obtained by replaying the events onto the initial snapshot.
+    pub replayed: MarketSnapshot,
+
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReconciliationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Replays `events` onto `initial` and compares the result with `expected`, a later snapshot of
+/// the same market.
+///
+/// Only logs with market sequence numbers between the two snapshots are applied, so the stream
+/// may overlap either end. Trader balances are not derivable from events, so the replay uses
+/// the trader table of `expected` and only the books and sequence numbers are compared.
+pub fn reconcile(
+    initial: &MarketSnapshot,
+    events: &[PhoenixTransactionEvents],
+    expected: &MarketSnapshot,
+) -> ReconciliationReport {
+    let mut replayed = initial.clone();
+    replayed.traders = expected.traders.clone();
+    let trader_indices = TraderIndexMap::from_snapshot(&replayed);
+
+    let start = initial.header.market_sequence_number;
+    let end = expected.header.market_sequence_number;
+    let mut logs_applied = 0;
+    let mut logs_skipped = 0;
+    for batch in events {
+        if (start..end).contains(&batch.header.market_sequence_number) {
+            replayed.apply_log_with_trader_indices(&batch.header, &batch.events, &trader_indices);
+            logs_applied += 1;
+        } else {
+            logs_skipped += 1;
+        }
+    }
+
+    let mut divergences = vec![];
+    if replayed.header.market_sequence_number != end {
+        divergences.push(Divergence::SequenceNumberMismatch {
+            replayed: replayed.header.market_sequence_number,
+            expected: end,
+        });
+    }
+    for side in [Side::Bid, Side::Ask] {
+        let mut orders: BTreeMap<
+            FIFOOrderId,
+            (Option<FIFORestingOrder>, Option<FIFORestingOrder>),
+        > = BTreeMap::new();
+        for (order_id, order) in replayed.get_book(side) {
+            orders.entry(*order_id).or_default().0 = Some(*order);
+        }
+        for (order_id, order) in expected.get_book(side) {
+            orders.entry(*order_id).or_default().1 = Some(*order);
+        }
+        for (order_id, entry) in orders {
+            match entry {
+                (Some(replayed), Some(expected)) if replayed != expected => {
+                    divergences.push(Divergence::OrderMismatch {
+                        side,
+                        order_id,
+                        replayed,
+                        expected,
+                    })
+                }
+                (Some(replayed), None) => divergences.push(Divergence::UnexpectedOrder {
+                    side,
+                    order_id,
+                    replayed,
+                }),
+                (None, Some(expected)) => divergences.push(Divergence::MissingOrder {
+                    side,
+                    order_id,
+                    expected,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    ReconciliationReport {
+        logs_applied,
+        logs_skipped,
+        replayed,
+        divergences,
+    }
+}
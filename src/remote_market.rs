@@ -0,0 +1,137 @@
+use crate::cached_market::CachedMarketView;
+use crate::market::{Ladder, LadderOrder, Market, MarketHeader, TraderState};
+use crate::snapshot::MarketSnapshot;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_sdk::pubkey::Pubkey;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RemoteMarketError {
+    #[error("RPC error: {0}")]
+    Rpc(#[from] Box<ClientError>),
+    #[error("Market account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("Account {0} is not a market with supported size params")]
+    InvalidMarket(Pubkey),
+}
+
+/// The market data fetched at a given slot.
+struct FetchedMarket {
+    fetched_at: Instant,
+    slot: u64,
+    view: CachedMarketView,
+}
+
+/// A read-only view of a market that fetches the market account over RPC when its cached copy is
+/// older than the cache TTL.
+///
+/// This is meant for scripts and analytics: every query may block on an RPC request, and
+/// queries made within the TTL are answered from the same copy of the account.
+pub struct RemoteMarket {
+    client: RpcClient,
+    market: Pubkey,
+    cache_ttl: Duration,
+    fetched: RefCell<Option<FetchedMarket>>,
+}
+
+impl RemoteMarket {
+    /// Default time a fetched copy of the market is reused for.
+    pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(1);
+
+    pub fn new(client: RpcClient, market: Pubkey) -> Self {
+        Self {
+            client,
+            market,
+            cache_ttl: Self::DEFAULT_CACHE_TTL,
+            fetched: RefCell::new(None),
+        }
+    }
+
+    /// Sets how long a fetched copy of the market is reused. A TTL of zero fetches the account
+    /// on every query.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    pub fn market(&self) -> &Pubkey {
+        &self.market
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// Fetches the market account, regardless of the age of the cached copy.
+    pub fn refresh(&self) -> Result<(), RemoteMarketError> {
+        let response = self
+            .client
+            .get_account_with_commitment(&self.market, self.client.commitment())
+            .map_err(Box::new)?;
+        let account = response
+            .value
+            .ok_or(RemoteMarketError::AccountNotFound(self.market))?;
+        let view = CachedMarketView::new(account.data)
+            .ok_or(RemoteMarketError::InvalidMarket(self.market))?;
+        *self.fetched.borrow_mut() = Some(FetchedMarket {
+            fetched_at: Instant::now(),
+            slot: response.context.slot,
+            view,
+        });
+        Ok(())
+    }
+
+    /// The slot at which the cached copy of the market was fetched, if any.
+    pub fn slot(&self) -> Option<u64> {
+        self.fetched.borrow().as_ref().map(|fetched| fetched.slot)
+    }
+
+    pub fn header(&self) -> Result<MarketHeader, RemoteMarketError> {
+        self.with_market(|_, view| *view.header())
+    }
+
+    pub fn get_ladder(&self, levels: u64) -> Result<Ladder, RemoteMarketError> {
+        self.with_market(|_, view| view.get_ladder(levels))
+    }
+
+    pub fn best_bid(&self) -> Result<Option<LadderOrder>, RemoteMarketError> {
+        self.with_market(|_, view| view.best_bid())
+    }
+
+    pub fn best_ask(&self) -> Result<Option<LadderOrder>, RemoteMarketError> {
+        self.with_market(|_, view| view.best_ask())
+    }
+
+    pub fn get_trader_state(
+        &self,
+        trader: &Pubkey,
+    ) -> Result<Option<TraderState>, RemoteMarketError> {
+        self.with_market(|_, view| view.get_trader_state(trader).copied())
+    }
+
+    pub fn get_snapshot(&self) -> Result<MarketSnapshot, RemoteMarketError> {
+        self.with_market(|slot, view| MarketSnapshot::from_market(view.header(), view, slot))
+    }
+
+    /// Runs `f` on the cached copy of the market and the slot it was fetched at, fetching the
+    /// account first if the copy is missing or expired.
+    fn with_market<T>(
+        &self,
+        f: impl FnOnce(u64, &CachedMarketView) -> T,
+    ) -> Result<T, RemoteMarketError> {
+        let expired = self
+            .fetched
+            .borrow()
+            .as_ref()
+            .is_none_or(|fetched| fetched.fetched_at.elapsed() >= self.cache_ttl);
+        if expired {
+            self.refresh()?;
+        }
+        let fetched = self.fetched.borrow();
+        let fetched = fetched.as_ref().expect("Market is fetched above");
+        Ok(f(fetched.slot, &fetched.view))
+    }
+}
@@ -0,0 +1,57 @@
+use crate::enums::Side;
+use serde::{Deserialize, Serialize};
+
+/// Values within this relative distance of a whole number are treated as that number, so that
+/// floating point error does not push an exact value to the neighbouring tick or lot.
+const SNAP_TOLERANCE: f64 = 1e-9;
+
+/// How to round a value that falls between two ticks or lots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoundingDirection {
+    #[default]
+    Down,
+    Up,
+    Nearest,
+}
+
+impl RoundingDirection {
+    /// The direction that rounds a price on `side` to the less aggressive tick: down for bids
+    /// and up for asks, so the order never becomes more aggressive than requested.
+    pub fn passive(side: Side) -> Self {
+        match side {
+            Side::Bid => RoundingDirection::Down,
+            Side::Ask => RoundingDirection::Up,
+        }
+    }
+
+    /// The direction that rounds a price on `side` to the more aggressive tick: up for bids
+    /// and down for asks, so the order is no less likely to fill than requested.
+    pub fn aggressive(side: Side) -> Self {
+        Self::passive(side.opposite())
+    }
+
+    /// Rounds `value` to a whole number. Values within floating point error of a whole number
+    /// snap to it regardless of direction.
+    pub fn round(self, value: f64) -> f64 {
+        let nearest = value.round();
+        if (value - nearest).abs() <= SNAP_TOLERANCE * nearest.abs().max(1.0) {
+            return nearest;
+        }
+        match self {
+            RoundingDirection::Down => value.floor(),
+            RoundingDirection::Up => value.ceil(),
+            RoundingDirection::Nearest => nearest,
+        }
+    }
+
+    /// Rounds `value` to a multiple of `increment`.
+    pub fn round_to_increment(self, value: f64, increment: f64) -> f64 {
+        self.round(value / increment) * increment
+    }
+}
+
+/// Rounds a price in fractional ticks to whole ticks for an order on `side`, to the less
+/// aggressive tick.
+pub fn round_ticks_for_side(side: Side, price_in_ticks: f64) -> u64 {
+    RoundingDirection::passive(side).round(price_in_ticks) as u64
+}
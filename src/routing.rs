@@ -0,0 +1,111 @@
+use crate::enums::Side;
+use crate::instructions::{create_new_order_instruction, swap_order_packet, SwapError};
+use crate::market::MarketHeader;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RouteError {
+    #[error("Mint {mint} is not traded on market {market}")]
+    MintNotInMarket { mint: Pubkey, market: Pubkey },
+    #[error("The route's input and output mints are the same")]
+    CircularRoute,
+    #[error("Invalid swap on market {market}: {error}")]
+    Swap { market: Pubkey, error: SwapError },
+}
+
+/// A market to swap through, along with its header.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteMarket<'a> {
+    pub market: &'a Pubkey,
+    pub header: &'a MarketHeader,
+}
+
+impl<'a> RouteMarket<'a> {
+    pub fn new(market: &'a Pubkey, header: &'a MarketHeader) -> Self {
+        Self { market, header }
+    }
+
+    /// Returns the side of a swap that takes `input_mint` in, and the mint it pays out.
+    fn swap_direction(&self, input_mint: &Pubkey) -> Result<(Side, Pubkey), RouteError> {
+        let base_mint = self.header.base_params.mint_key;
+        let quote_mint = self.header.quote_params.mint_key;
+        if *input_mint == base_mint {
+            Ok((Side::Ask, quote_mint))
+        } else if *input_mint == quote_mint {
+            Ok((Side::Bid, base_mint))
+        } else {
+            Err(RouteError::MintNotInMarket {
+                mint: *input_mint,
+                market: *self.market,
+            })
+        }
+    }
+
+    fn swap_instruction(
+        &self,
+        trader: &Pubkey,
+        side: Side,
+        in_amount_atoms: u64,
+        min_out_atoms: u64,
+    ) -> Result<Instruction, RouteError> {
+        let order_packet = swap_order_packet(self.header, side, in_amount_atoms, min_out_atoms)
+            .map_err(|error| RouteError::Swap {
+                market: *self.market,
+                error,
+            })?;
+        Ok(create_new_order_instruction(
+            self.market,
+            trader,
+            &self.header.base_params.mint_key,
+            &self.header.quote_params.mint_key,
+            &order_packet,
+        ))
+    }
+}
+
+/// Builds the instructions to swap `input_mint` into another token through two Phoenix markets
+/// that share an intermediate token (e.g. BONK -> USDC on BONK/USDC, then USDC -> SOL on SOL/USDC).
+///
+/// The first swap must produce at least `intermediate_amount_atoms`, all of which is spent by the
+/// second swap, which must in turn produce at least `min_amount_out_atoms`. The instructions are
+/// returned in the order they must execute: creation of the intermediate and output token
+/// accounts (idempotent, paid for by `trader`), followed by the two swaps.
+#[allow(clippy::too_many_arguments)]
+pub fn create_two_hop_swap_instructions(
+    trader: &Pubkey,
+    first: RouteMarket,
+    second: RouteMarket,
+    input_mint: &Pubkey,
+    amount_in_atoms: u64,
+    intermediate_amount_atoms: u64,
+    min_amount_out_atoms: u64,
+) -> Result<Vec<Instruction>, RouteError> {
+    let (first_side, intermediate_mint) = first.swap_direction(input_mint)?;
+    let (second_side, output_mint) = second.swap_direction(&intermediate_mint)?;
+    if output_mint == *input_mint {
+        return Err(RouteError::CircularRoute);
+    }
+    Ok(vec![
+        create_associated_token_account_idempotent(
+            trader,
+            trader,
+            &intermediate_mint,
+            &spl_token::id(),
+        ),
+        create_associated_token_account_idempotent(trader, trader, &output_mint, &spl_token::id()),
+        first.swap_instruction(
+            trader,
+            first_side,
+            amount_in_atoms,
+            intermediate_amount_atoms,
+        )?,
+        second.swap_instruction(
+            trader,
+            second_side,
+            intermediate_amount_atoms,
+            min_amount_out_atoms,
+        )?,
+    ])
+}
@@ -0,0 +1,218 @@
+//! Flattened rows of market events for inserting into databases.
+//!
+//! Each row denormalizes the context of its log (market, slot, signature, sequence number) and
+//! uses column-friendly types: pubkeys and signatures as base58 strings, integers as `i64`, and
+//! decimal amounts as `f64`.
+//!
+//! Order sequence numbers are the `order_sequence_number` of the order's id, not the log's
+//! `market_sequence_number`. Bid order sequence numbers have their bits inverted on chain, so
+//! they are inverted back here to fit in an `i64`; use `side` to tell bids and asks apart, and
+//! invert bids again to recover the on-chain order id.
+
+use crate::enums::Side;
+use crate::events::{AuditLogHeader, MarketEvent};
+use crate::market_metadata::MarketMetadata;
+use serde::{Deserialize, Serialize};
+
+fn side_name(side: Side) -> String {
+    match side {
+        Side::Bid => "bid".to_string(),
+        Side::Ask => "ask".to_string(),
+    }
+}
+
+/// Returns the side and the uninverted sequence number of an order.
+fn order_side_and_sequence_number(order_sequence_number: u64) -> (String, i64) {
+    let side = Side::from_order_sequence_number(order_sequence_number);
+    let sequence_number = match side {
+        Side::Bid => !order_sequence_number,
+        Side::Ask => order_sequence_number,
+    };
+    (side_name(side), sequence_number as i64)
+}
+
+/// A fill of a resting order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FillRow {
+    pub signature: String,
+    pub market: String,
+    pub slot: i64,
+    pub timestamp: i64,
+    pub market_sequence_number: i64,
+    pub event_index: i32,
+    pub taker: String,
+    pub maker: String,
+    /// The side of the resting order that was filled.
+    pub side: String,
+    pub order_sequence_number: i64,
+    pub price_in_ticks: i64,
+    pub price: f64,
+    pub base_lots_filled: i64,
+    pub base_lots_remaining: i64,
+    pub base_amount_filled: f64,
+    pub quote_amount_filled: f64,
+}
+
+/// A limit order placed on the book.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaceRow {
+    pub signature: String,
+    pub market: String,
+    pub slot: i64,
+    pub timestamp: i64,
+    pub market_sequence_number: i64,
+    pub event_index: i32,
+    pub maker: String,
+    pub side: String,
+    pub order_sequence_number: i64,
+    /// The client order id, as a decimal string since it does not fit in an `i64`.
+    pub client_order_id: String,
+    pub price_in_ticks: i64,
+    pub price: f64,
+    pub base_lots_placed: i64,
+    pub base_amount_placed: f64,
+}
+
+/// A resting order that was reduced or cancelled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReduceRow {
+    pub signature: String,
+    pub market: String,
+    pub slot: i64,
+    pub timestamp: i64,
+    pub market_sequence_number: i64,
+    pub event_index: i32,
+    pub maker: String,
+    pub side: String,
+    pub order_sequence_number: i64,
+    pub price_in_ticks: i64,
+    pub price: f64,
+    pub base_lots_removed: i64,
+    pub base_lots_remaining: i64,
+    pub base_amount_removed: f64,
+}
+
+/// The rows of the fill, place and reduce events of one or more logs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventRows {
+    pub fills: Vec<FillRow>,
+    pub places: Vec<PlaceRow>,
+    pub reduces: Vec<ReduceRow>,
+}
+
+impl EventRows {
+    /// Maps the events of a single log, emitted by the transaction with `signature`, to rows.
+    /// Events other than fills, places and reduces are skipped.
+    pub fn from_log(
+        signature: &str,
+        header: &AuditLogHeader,
+        events: &[MarketEvent],
+        metadata: &MarketMetadata,
+    ) -> Self {
+        let mut rows = Self::default();
+        rows.extend_from_log(signature, header, events, metadata);
+        rows
+    }
+
+    /// Appends the rows of the events of a single log.
+    pub fn extend_from_log(
+        &mut self,
+        signature: &str,
+        header: &AuditLogHeader,
+        events: &[MarketEvent],
+        metadata: &MarketMetadata,
+    ) {
+        let market = header.market.to_string();
+        let signer = header.signer.to_string();
+        let slot = header.slot as i64;
+        let market_sequence_number = header.market_sequence_number as i64;
+        for event in events {
+            match *event {
+                MarketEvent::Fill {
+                    index,
+                    maker_id,
+                    order_sequence_number,
+                    price_in_ticks,
+                    base_lots_filled,
+                    base_lots_remaining,
+                } => {
+                    let (side, order_sequence_number) =
+                        order_side_and_sequence_number(order_sequence_number);
+                    let price = metadata.ticks_to_float_price(price_in_ticks);
+                    let base_amount_filled = metadata.raw_to_ui_size(base_lots_filled);
+                    self.fills.push(FillRow {
+                        signature: signature.to_string(),
+                        market: market.clone(),
+                        slot,
+                        timestamp: header.timestamp,
+                        market_sequence_number,
+                        event_index: index as i32,
+                        taker: signer.clone(),
+                        maker: maker_id.to_string(),
+                        side,
+                        order_sequence_number,
+                        price_in_ticks: price_in_ticks as i64,
+                        price,
+                        base_lots_filled: base_lots_filled as i64,
+                        base_lots_remaining: base_lots_remaining as i64,
+                        base_amount_filled,
+                        quote_amount_filled: price * base_amount_filled,
+                    });
+                }
+                MarketEvent::Place {
+                    index,
+                    order_sequence_number,
+                    client_order_id,
+                    price_in_ticks,
+                    base_lots_placed,
+                } => {
+                    let (side, order_sequence_number) =
+                        order_side_and_sequence_number(order_sequence_number);
+                    self.places.push(PlaceRow {
+                        signature: signature.to_string(),
+                        market: market.clone(),
+                        slot,
+                        timestamp: header.timestamp,
+                        market_sequence_number,
+                        event_index: index as i32,
+                        maker: signer.clone(),
+                        side,
+                        order_sequence_number,
+                        client_order_id: client_order_id.to_string(),
+                        price_in_ticks: price_in_ticks as i64,
+                        price: metadata.ticks_to_float_price(price_in_ticks),
+                        base_lots_placed: base_lots_placed as i64,
+                        base_amount_placed: metadata.raw_to_ui_size(base_lots_placed),
+                    });
+                }
+                MarketEvent::Reduce {
+                    index,
+                    order_sequence_number,
+                    price_in_ticks,
+                    base_lots_removed,
+                    base_lots_remaining,
+                } => {
+                    let (side, order_sequence_number) =
+                        order_side_and_sequence_number(order_sequence_number);
+                    self.reduces.push(ReduceRow {
+                        signature: signature.to_string(),
+                        market: market.clone(),
+                        slot,
+                        timestamp: header.timestamp,
+                        market_sequence_number,
+                        event_index: index as i32,
+                        maker: signer.clone(),
+                        side,
+                        order_sequence_number,
+                        price_in_ticks: price_in_ticks as i64,
+                        price: metadata.ticks_to_float_price(price_in_ticks),
+                        base_lots_removed: base_lots_removed as i64,
+                        base_lots_remaining: base_lots_remaining as i64,
+                        base_amount_removed: metadata.raw_to_ui_size(base_lots_removed),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::accounts::{MARKET_HEADER_DISCRIMINANT, SEAT_DISCRIMINANT};
+use crate::market::Seat;
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+/// Byte offset of the discriminant in every Phoenix account.
+pub const DISCRIMINANT_OFFSET: usize = 0;
+
+fn discriminant_filter(discriminant: u64) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        DISCRIMINANT_OFFSET,
+        &discriminant.to_le_bytes(),
+    ))
+}
+
+fn seat_filters(offset: usize, key: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        discriminant_filter(SEAT_DISCRIMINANT),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, key.as_ref())),
+    ]
+}
+
+/// Filters a `getProgramAccounts` scan of the Phoenix program to market accounts.
+pub fn market_filters() -> Vec<RpcFilterType> {
+    vec![discriminant_filter(MARKET_HEADER_DISCRIMINANT)]
+}
+
+/// Filters a `getProgramAccounts` scan of the Phoenix program to seat accounts.
+pub fn all_seats_filters() -> Vec<RpcFilterType> {
+    vec![discriminant_filter(SEAT_DISCRIMINANT)]
+}
+
+/// Filters a `getProgramAccounts` scan of the Phoenix program to the seats of `trader`, on
+/// every market.
+pub fn seats_for_trader_filters(trader: &Pubkey) -> Vec<RpcFilterType> {
+    seat_filters(Seat::TRADER_OFFSET, trader)
+}
+
+/// Filters a `getProgramAccounts` scan of the Phoenix program to the seats of `market`.
+pub fn seats_for_market_filters(market: &Pubkey) -> Vec<RpcFilterType> {
+    seat_filters(Seat::MARKET_OFFSET, market)
+}
@@ -0,0 +1,93 @@
+use crate::enums::Side;
+use crate::instructions::{CondensedOrder, MultipleOrderPacket};
+use crate::market::Ladder;
+
+/// What to do with a post-only order that would cross the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossingPolicy {
+    /// Move the order to the most aggressive price that does not cross: one tick below the best
+    /// ask for bids, one tick above the best bid for asks. Orders that cannot be moved to a
+    /// valid price are dropped.
+    AmendToBestNonCrossing,
+    /// Remove the order from the packet.
+    Drop,
+}
+
+/// A change made to a single order of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteAdjustment {
+    pub side: Side,
+    pub original_price_in_ticks: u64,
+    pub size_in_base_lots: u64,
+    /// The price the order was moved to, or None if it was dropped.
+    pub adjusted_price_in_ticks: Option<u64>,
+}
+
+/// A packet with its crossing orders adjusted, and the adjustments that were made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedQuotes {
+    pub packet: MultipleOrderPacket,
+    pub adjustments: Vec<QuoteAdjustment>,
+}
+
+/// Adjusts the orders of `packet` that would cross the best prices of `book` according to
+/// `policy`, so that the packet can be posted without being rejected or repriced by the program.
+/// Orders that do not cross are left unchanged, in their original order.
+pub fn sanitize_quotes(
+    packet: &MultipleOrderPacket,
+    book: &Ladder,
+    policy: CrossingPolicy,
+) -> SanitizedQuotes {
+    let mut adjustments = vec![];
+    let best_bid = book.bids.first().map(|level| level.price_in_ticks);
+    let best_ask = book.asks.first().map(|level| level.price_in_ticks);
+
+    // The most aggressive non-crossing price on each side, or None if no price is valid.
+    let bid_limit = best_ask.map(|ask| ask.checked_sub(1).filter(|&price| price > 0));
+    let ask_limit = best_bid.map(|bid| bid.checked_add(1));
+
+    let mut sanitize_side = |side: Side, orders: &[CondensedOrder], limit: Option<Option<u64>>| {
+        orders
+            .iter()
+            .filter_map(|order| {
+                let crosses = match (side, limit) {
+                    (_, None) => false,
+                    (Side::Bid, Some(limit)) => {
+                        limit.is_none_or(|limit| order.price_in_ticks > limit)
+                    }
+                    (Side::Ask, Some(limit)) => {
+                        limit.is_none_or(|limit| order.price_in_ticks < limit)
+                    }
+                };
+                if !crosses {
+                    return Some(*order);
+                }
+                let adjusted_price_in_ticks = match policy {
+                    CrossingPolicy::AmendToBestNonCrossing => limit.flatten(),
+                    CrossingPolicy::Drop => None,
+                };
+                adjustments.push(QuoteAdjustment {
+                    side,
+                    original_price_in_ticks: order.price_in_ticks,
+                    size_in_base_lots: order.size_in_base_lots,
+                    adjusted_price_in_ticks,
+                });
+                adjusted_price_in_ticks.map(|price_in_ticks| CondensedOrder {
+                    price_in_ticks,
+                    ..*order
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let bids = sanitize_side(Side::Bid, &packet.bids, bid_limit);
+    let asks = sanitize_side(Side::Ask, &packet.asks, ask_limit);
+
+    SanitizedQuotes {
+        packet: MultipleOrderPacket {
+            bids,
+            asks,
+            ..packet.clone()
+        },
+        adjustments,
+    }
+}
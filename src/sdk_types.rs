@@ -0,0 +1,303 @@
+//! Types matching the vocabulary and JSON shape (camelCase fields, base58 pubkeys) of the
+//! TypeScript SDK, so that Rust and TypeScript clients can exchange market data.
+
+use crate::market::{
+    aggregate_levels, FIFOOrderId, FIFORestingOrder, Ladder, Market, MarketHeader,
+    MarketSizeParams, TokenParams, TraderState,
+};
+use crate::market_metadata::MarketMetadata;
+use crate::snapshot::MarketSnapshot;
+use serde::{Deserialize, Serialize};
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// The slot and time of the cluster, as read from the clock sysvar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockData {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+impl From<&Clock> for ClockData {
+    fn from(clock: &Clock) -> Self {
+        Self {
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderId {
+    #[serde(with = "crate::u64_string")]
+    pub price_in_ticks: u64,
+    #[serde(with = "crate::u64_string")]
+    pub order_sequence_number: u64,
+}
+
+impl From<FIFOOrderId> for OrderId {
+    fn from(order_id: FIFOOrderId) -> Self {
+        Self {
+            price_in_ticks: order_id.price_in_ticks,
+            order_sequence_number: order_id.order_sequence_number,
+        }
+    }
+}
+
+impl From<OrderId> for FIFOOrderId {
+    fn from(order_id: OrderId) -> Self {
+        FIFOOrderId::new(order_id.price_in_ticks, order_id.order_sequence_number)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestingOrder {
+    #[serde(with = "crate::u64_string")]
+    pub trader_index: u64,
+    #[serde(with = "crate::u64_string")]
+    pub num_base_lots: u64,
+}
+
+impl From<FIFORestingOrder> for RestingOrder {
+    fn from(order: FIFORestingOrder) -> Self {
+        Self {
+            trader_index: order.trader_index,
+            num_base_lots: order.num_base_lots,
+        }
+    }
+}
+
+/// A trader's balances on a market, in lots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraderStateData {
+    #[serde(with = "crate::u64_string")]
+    pub quote_lots_locked: u64,
+    #[serde(with = "crate::u64_string")]
+    pub quote_lots_free: u64,
+    #[serde(with = "crate::u64_string")]
+    pub base_lots_locked: u64,
+    #[serde(with = "crate::u64_string")]
+    pub base_lots_free: u64,
+}
+
+impl From<TraderState> for TraderStateData {
+    fn from(state: TraderState) -> Self {
+        Self {
+            quote_lots_locked: state.quote_lots_locked.as_u64(),
+            quote_lots_free: state.quote_lots_free.as_u64(),
+            base_lots_locked: state.base_lots_locked.as_u64(),
+            base_lots_free: state.base_lots_free.as_u64(),
+        }
+    }
+}
+
+/// A trader's balances on a market, in quote units and raw base units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTraderState {
+    pub quote_units_locked: f64,
+    pub quote_units_free: f64,
+    pub base_units_locked: f64,
+    pub base_units_free: f64,
+}
+
+impl UiTraderState {
+    pub fn from_trader_state(state: &TraderState, metadata: &MarketMetadata) -> Self {
+        Self {
+            quote_units_locked: metadata.quote_lots_to_ui_amount(state.quote_lots_locked.as_u64()),
+            quote_units_free: metadata.quote_lots_to_ui_amount(state.quote_lots_free.as_u64()),
+            base_units_locked: metadata.raw_to_ui_size(state.base_lots_locked.as_u64()),
+            base_units_free: metadata.raw_to_ui_size(state.base_lots_free.as_u64()),
+        }
+    }
+}
+
+/// The size parameters of a market.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketSizeParamsData {
+    #[serde(with = "crate::u64_string")]
+    pub bids_size: u64,
+    #[serde(with = "crate::u64_string")]
+    pub asks_size: u64,
+    #[serde(with = "crate::u64_string")]
+    pub num_seats: u64,
+}
+
+impl From<MarketSizeParams> for MarketSizeParamsData {
+    fn from(params: MarketSizeParams) -> Self {
+        Self {
+            bids_size: params.bids_size,
+            asks_size: params.asks_size,
+            num_seats: params.num_seats,
+        }
+    }
+}
+
+/// The parameters of one of a market's tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenParamsData {
+    pub decimals: u32,
+    pub vault_bump: u32,
+    #[serde(with = "crate::pubkey_serde")]
+    pub mint_key: Pubkey,
+    #[serde(with = "crate::pubkey_serde")]
+    pub vault_key: Pubkey,
+}
+
+impl From<TokenParams> for TokenParamsData {
+    fn from(params: TokenParams) -> Self {
+        Self {
+            decimals: params.decimals,
+            vault_bump: params.vault_bump,
+            mint_key: params.mint_key,
+            vault_key: params.vault_key,
+        }
+    }
+}
+
+/// The header of a market account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketHeaderData {
+    #[serde(with = "crate::u64_string")]
+    pub discriminant: u64,
+    #[serde(with = "crate::u64_string")]
+    pub status: u64,
+    pub market_size_params: MarketSizeParamsData,
+    pub base_params: TokenParamsData,
+    #[serde(with = "crate::u64_string")]
+    pub base_lot_size: u64,
+    pub quote_params: TokenParamsData,
+    #[serde(with = "crate::u64_string")]
+    pub quote_lot_size: u64,
+    #[serde(with = "crate::u64_string")]
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    #[serde(with = "crate::pubkey_serde")]
+    pub authority: Pubkey,
+    #[serde(with = "crate::pubkey_serde")]
+    pub fee_recipient: Pubkey,
+    #[serde(with = "crate::u64_string")]
+    pub market_sequence_number: u64,
+    #[serde(with = "crate::pubkey_serde")]
+    pub successor: Pubkey,
+}
+
+impl From<&MarketHeader> for MarketHeaderData {
+    fn from(header: &MarketHeader) -> Self {
+        Self {
+            discriminant: header.discriminant,
+            status: header.status,
+            market_size_params: header.market_size_params.into(),
+            base_params: header.base_params.into(),
+            base_lot_size: header.get_base_lot_size(),
+            quote_params: header.quote_params.into(),
+            quote_lot_size: header.get_quote_lot_size(),
+            tick_size_in_quote_atoms_per_base_unit: header
+                .get_tick_size_in_quote_atoms_per_base_unit(),
+            authority: header.authority,
+            fee_recipient: header.fee_destination,
+            market_sequence_number: header.market_sequence_number,
+            successor: header.successor,
+        }
+    }
+}
+
+/// The state of a market: its parameters, book and traders, keyed the way the TypeScript SDK
+/// keys them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketData {
+    pub header: MarketHeaderData,
+    #[serde(with = "crate::u64_string")]
+    pub base_lots_per_base_unit: u64,
+    #[serde(with = "crate::u64_string")]
+    pub quote_lots_per_base_unit_per_tick: u64,
+    /// The sequence number of the next order placed on the market.
+    #[serde(with = "crate::u64_string")]
+    pub sequence_number: u64,
+    pub taker_fee_bps: u16,
+    pub bids: Vec<(OrderId, RestingOrder)>,
+    pub asks: Vec<(OrderId, RestingOrder)>,
+    #[serde(with = "crate::pubkey_serde::map")]
+    pub traders: HashMap<Pubkey, TraderStateData>,
+    #[serde(with = "crate::pubkey_serde::map")]
+    pub trader_pubkey_to_trader_index: HashMap<Pubkey, u32>,
+    pub trader_index_to_trader_pubkey: HashMap<u32, String>,
+}
+
+impl MarketData {
+    pub fn from_market(header: &MarketHeader, market: &dyn Market) -> Self {
+        Self::from_snapshot(&MarketSnapshot::from_market(header, market, 0))
+    }
+
+    pub fn from_snapshot(snapshot: &MarketSnapshot) -> Self {
+        let book = |orders: &[(FIFOOrderId, FIFORestingOrder)]| {
+            orders
+                .iter()
+                .map(|(order_id, order)| ((*order_id).into(), (*order).into()))
+                .collect()
+        };
+        Self {
+            header: (&snapshot.header).into(),
+            base_lots_per_base_unit: snapshot.base_lots_per_base_unit,
+            quote_lots_per_base_unit_per_tick: snapshot.tick_size_in_quote_lots_per_base_unit,
+            sequence_number: snapshot.order_sequence_number,
+            taker_fee_bps: snapshot.taker_fee_bps,
+            bids: book(&snapshot.bids),
+            asks: book(&snapshot.asks),
+            traders: snapshot
+                .traders
+                .iter()
+                .map(|trader| (trader.trader, trader.state.into()))
+                .collect(),
+            trader_pubkey_to_trader_index: snapshot
+                .traders
+                .iter()
+                .map(|trader| (trader.trader, trader.trader_index))
+                .collect(),
+            trader_index_to_trader_pubkey: snapshot
+                .traders
+                .iter()
+                .map(|trader| (trader.trader_index, trader.trader.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Returns the top `levels` levels of each side, in ticks and base lots.
+    pub fn get_ladder(&self, levels: u64) -> Ladder {
+        let orders = |orders: &[(OrderId, RestingOrder)]| {
+            let orders = orders.iter().map(|(order_id, order)| {
+                (
+                    FIFOOrderId::from(*order_id),
+                    FIFORestingOrder::new(order.trader_index, order.num_base_lots),
+                )
+            });
+            aggregate_levels(orders, levels)
+        };
+        Ladder {
+            bids: orders(&self.bids),
+            asks: orders(&self.asks),
+        }
+    }
+
+    pub fn get_trader_state(&self, trader: &Pubkey) -> Option<&TraderStateData> {
+        self.traders.get(trader)
+    }
+
+    pub fn get_trader_index(&self, trader: &Pubkey) -> Option<u32> {
+        self.trader_pubkey_to_trader_index.get(trader).copied()
+    }
+
+    pub fn get_trader_pubkey(&self, trader_index: u32) -> Option<Pubkey> {
+        self.trader_index_to_trader_pubkey
+            .get(&trader_index)
+            .and_then(|trader| trader.parse().ok())
+    }
+}
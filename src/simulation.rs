@@ -0,0 +1,483 @@
+use crate::enums::Side;
+use crate::fees::FeeSchedule;
+use crate::fill_math::fill_quote_lots;
+use crate::instructions::{CancelUpToParams, CondensedOrder, MultipleOrderPacket};
+use crate::market::{FIFOOrderId, FIFORestingOrder, Market, MarketHeader};
+use crate::order_packet::OrderPacket;
+use crate::units::{BaseLots, QuoteLots, Ticks};
+use solana_sdk::pubkey::Pubkey;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Returns true if an order on `side` at `price_in_ticks` would match against the resting
+/// orders on the opposite side of the book.
+pub fn would_cross(market: &dyn Market, side: Side, price_in_ticks: u64) -> bool {
+    market
+        .get_book(side.opposite())
+        .iter()
+        .next()
+        .map(|(order_id, _)| side.crosses(price_in_ticks, order_id.price_in_ticks))
+        .unwrap_or(false)
+}
+
+/// Returns the number of base lots resting on the opposite side of the book that an order on
+/// `side` at `price_in_ticks` would be able to match against. Self trades and match limits are
+/// not taken into account.
+pub fn crossing_depth(market: &dyn Market, side: Side, price_in_ticks: u64) -> u64 {
+    market
+        .get_book(side.opposite())
+        .iter()
+        .take_while(|(order_id, _)| side.crosses(price_in_ticks, order_id.price_in_ticks))
+        .map(|(_, order)| order.num_base_lots)
+        .sum()
+}
+
+/// Returns the price that a post only order on `side` at `price_in_ticks` would rest at when
+/// `reject_post_only` is false.
+///
+/// If the order does not cross, its price is unchanged. Otherwise it is amended to the best
+/// non-crossing tick, one tick away from the best price on the opposite side. Returns None if
+/// there is no valid non-crossing tick (a bid against an ask resting at 1 tick).
+pub fn post_only_amended_price(
+    market: &dyn Market,
+    side: Side,
+    price_in_ticks: u64,
+) -> Option<u64> {
+    let best_opposite_price = match market.get_book(side.opposite()).iter().next() {
+        Some((order_id, _)) => order_id.price_in_ticks,
+        None => return Some(price_in_ticks),
+    };
+    if !side.crosses(price_in_ticks, best_opposite_price) {
+        return Some(price_in_ticks);
+    }
+    let amended_price = match side {
+        Side::Bid => best_opposite_price.checked_sub(1)?,
+        Side::Ask => best_opposite_price.checked_add(1)?,
+    };
+    if amended_price == 0 {
+        None
+    } else {
+        Some(amended_price)
+    }
+}
+
+/// Returns true if an order from `trader` on `side` at `price_in_ticks` crosses any of the
+/// trader's own resting orders.
+///
+/// This is conservative: it does not account for the order being fully filled by other makers
+/// before reaching the trader's own orders.
+pub fn would_self_trade(
+    market: &dyn Market,
+    trader: &Pubkey,
+    side: Side,
+    price_in_ticks: u64,
+) -> bool {
+    let trader_index = match market.get_trader_index(trader) {
+        Some(index) => index as u64,
+        None => return false,
+    };
+    market
+        .get_book(side.opposite())
+        .iter()
+        .take_while(|(order_id, _)| side.crosses(price_in_ticks, order_id.price_in_ticks))
+        .any(|(_, order)| order.trader_index == trader_index)
+}
+
+/// The orders and funds that a cancel instruction would release.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CancelPreview {
+    /// The ids of the orders that would be cancelled, in the order they would be cancelled.
+    pub order_ids: Vec<FIFOOrderId>,
+
+    /// The base lots unlocked by cancelling asks.
+    pub base_lots_released: u64,
+
+    /// The quote lots unlocked by cancelling bids.
+    pub quote_lots_released: u64,
+}
+
+/// Evaluates `CancelUpToParams` for `trader` against the current book without modifying it,
+/// returning the orders that the `CancelUpTo` instruction would cancel.
+///
+/// Orders are searched from the top of the book. The search stops once `num_orders_to_search`
+/// orders have been visited, `num_orders_to_cancel` orders have been found, or an order less
+/// aggressive than `tick_limit` is reached.
+pub fn preview_cancel_up_to(
+    market: &dyn Market,
+    trader: &Pubkey,
+    params: &CancelUpToParams,
+) -> CancelPreview {
+    let mut preview = CancelPreview::default();
+    let trader_index = match market.get_trader_index(trader) {
+        Some(index) => index as u64,
+        None => return preview,
+    };
+    let side = params.side;
+    let book = market.get_book(side);
+    let num_orders_to_search = params
+        .num_orders_to_search
+        .map(|n| n as usize)
+        .unwrap_or(usize::MAX);
+    let num_orders_to_cancel = params
+        .num_orders_to_cancel
+        .map(|n| n as usize)
+        .unwrap_or(usize::MAX);
+    for (order_id, order) in book.iter().take(num_orders_to_search) {
+        if preview.order_ids.len() >= num_orders_to_cancel {
+            break;
+        }
+        if let Some(tick_limit) = params.tick_limit {
+            if side.is_price_more_aggressive(tick_limit, order_id.price_in_ticks) {
+                break;
+            }
+        }
+        if order.trader_index != trader_index {
+            continue;
+        }
+        preview.order_ids.push(*order_id);
+        match side {
+            Side::Bid => {
+                preview.quote_lots_released += fill_quote_lots(
+                    order_id.price_in_ticks,
+                    order.num_base_lots,
+                    market.get_tick_size_in_quote_lots_per_base_unit().as_u64(),
+                    market.get_base_lots_per_base_unit().as_u64(),
+                    Side::Ask,
+                )
+            }
+            Side::Ask => preview.base_lots_released += order.num_base_lots,
+        }
+    }
+    preview
+}
+
+/// An amount of a single token, expressed in lots, atoms, and UI units.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenAmount {
+    pub lots: u64,
+    pub atoms: u64,
+    pub ui_amount: f64,
+}
+
+/// The funds a trader could withdraw from a market.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WithdrawableBalances {
+    pub base: TokenAmount,
+    pub quote: TokenAmount,
+}
+
+/// Returns the balances `trader` could withdraw after cancelling all of their resting orders:
+/// their current free funds plus the funds locked in their orders. Returns None if the trader
+/// is not registered on the market or a balance overflows when converted to atoms.
+pub fn withdrawable_balances_after_cancel_all(
+    market: &dyn Market,
+    header: &MarketHeader,
+    trader: &Pubkey,
+) -> Option<WithdrawableBalances> {
+    let trader_state = market.get_trader_state(trader)?;
+    let base_lots = trader_state.total_base_lots();
+    let quote_lots = trader_state.total_quote_lots();
+    let base_atoms = header.base_lots_to_base_atoms(base_lots)?.as_u64();
+    let quote_atoms = header.quote_lots_to_quote_atoms(quote_lots)?.as_u64();
+    Some(WithdrawableBalances {
+        base: TokenAmount {
+            lots: base_lots.as_u64(),
+            atoms: base_atoms,
+            ui_amount: header.base_atoms_to_base_units_as_float(base_atoms),
+        },
+        quote: TokenAmount {
+            lots: quote_lots.as_u64(),
+            atoms: quote_atoms,
+            ui_amount: header.quote_atoms_to_quote_units_as_float(quote_atoms),
+        },
+    })
+}
+
+/// Returns the (base lots, quote lots) needed to place every order in a `MultipleOrderPacket`.
+/// Asks lock their size in base lots, and bids lock the quote lots needed to buy their size at
+/// their price, rounded up.
+///
+/// The tick and lot sizes are read from the loaded market, which records them exactly.
+pub fn required_funds(packet: &MultipleOrderPacket, market: &dyn Market) -> (u64, u64) {
+    let base_lots = packet.asks.iter().map(|ask| ask.size_in_base_lots).sum();
+    let quote_lots = packet
+        .bids
+        .iter()
+        .map(|bid| quote_lots_required(market, bid.price_in_ticks, bid.size_in_base_lots))
+        .sum();
+    (base_lots, quote_lots)
+}
+
+/// Returns the base lots and quote lots needed to submit an `OrderPacket`, or None if the
+/// amount cannot be known ahead of time (a market buy sized in base lots).
+///
+/// Orders that can cross include the taker fee, charged in quote lots on top of the notional
+/// for bids.
+pub fn required_funds_for_order_packet(
+    packet: &OrderPacket,
+    market: &dyn Market,
+    fee_schedule: &FeeSchedule,
+) -> Option<(BaseLots, QuoteLots)> {
+    let quote_lots_for_bid = |price_in_ticks: Ticks, num_base_lots: BaseLots| {
+        quote_lots_required(market, price_in_ticks.as_u64(), num_base_lots.as_u64())
+    };
+    let funds = match *packet {
+        OrderPacket::PostOnly {
+            side,
+            price_in_ticks,
+            num_base_lots,
+            ..
+        } => match side {
+            Side::Bid => (0, quote_lots_for_bid(price_in_ticks, num_base_lots)),
+            Side::Ask => (num_base_lots.as_u64(), 0),
+        },
+        OrderPacket::Limit {
+            side,
+            price_in_ticks,
+            num_base_lots,
+            ..
+        } => match side {
+            Side::Bid => (
+                0,
+                with_taker_fee(
+                    quote_lots_for_bid(price_in_ticks, num_base_lots),
+                    fee_schedule,
+                ),
+            ),
+            Side::Ask => (num_base_lots.as_u64(), 0),
+        },
+        OrderPacket::ImmediateOrCancel {
+            side,
+            price_in_ticks,
+            num_base_lots,
+            num_quote_lots,
+            ..
+        } => match side {
+            Side::Bid if num_quote_lots > QuoteLots::ZERO => (0, num_quote_lots.as_u64()),
+            Side::Bid => (
+                0,
+                with_taker_fee(
+                    quote_lots_for_bid(price_in_ticks?, num_base_lots),
+                    fee_schedule,
+                ),
+            ),
+            Side::Ask => (num_base_lots.as_u64(), 0),
+        },
+    };
+    Some((BaseLots::new(funds.0), QuoteLots::new(funds.1)))
+}
+
+/// Returns the quote lots needed to buy `num_base_lots` at `price_in_ticks`, rounded up.
+fn quote_lots_required(market: &dyn Market, price_in_ticks: u64, num_base_lots: u64) -> u64 {
+    fill_quote_lots(
+        price_in_ticks,
+        num_base_lots,
+        market.get_tick_size_in_quote_lots_per_base_unit().as_u64(),
+        market.get_base_lots_per_base_unit().as_u64(),
+        Side::Bid,
+    )
+}
+
+/// Adds the taker fee to an amount of quote lots.
+fn with_taker_fee(quote_lots: u64, fee_schedule: &FeeSchedule) -> u64 {
+    quote_lots + fee_schedule.taker_fee(quote_lots)
+}
+
+/// Why an order in a `MultipleOrderPacket` would not be placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchRejectReason {
+    /// The order crosses the book and the packet sets `reject_post_only`.
+    CrossesBook,
+    /// The order crosses the book and there is no non-crossing tick to amend it to.
+    NoValidAmendedPrice,
+    /// The side of the book is full and the order is no more aggressive than the least
+    /// aggressive resting order.
+    BookFull,
+}
+
+/// The outcome of a single order in a `MultipleOrderPacket`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOrderOutcome {
+    Placed {
+        order_id: FIFOOrderId,
+        /// The price the order was submitted at, if it was amended to avoid crossing the book.
+        amended_from: Option<u64>,
+    },
+    Rejected(BatchRejectReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOrderResult {
+    pub side: Side,
+    pub order: CondensedOrder,
+    pub outcome: BatchOrderOutcome,
+}
+
+/// A resting order evicted to make room for an order of the batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvictedOrder {
+    pub order_id: FIFOOrderId,
+    pub maker: Pubkey,
+    pub num_base_lots: u64,
+}
+
+/// The result of placing a `MultipleOrderPacket` against the current book.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultipleOrderSimulation {
+    /// The outcome of every order in the packet, in the order the orders would be processed.
+    pub results: Vec<BatchOrderResult>,
+    pub evicted: Vec<EvictedOrder>,
+    /// The market's order sequence number after the placed orders.
+    pub next_order_sequence_number: u64,
+}
+
+impl MultipleOrderSimulation {
+    /// Returns true if every order would be placed. A single rejected order fails the whole
+    /// instruction.
+    pub fn is_success(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| matches!(result.outcome, BatchOrderOutcome::Placed { .. }))
+    }
+
+    pub fn placed_order_ids(&self) -> Vec<FIFOOrderId> {
+        self.results
+            .iter()
+            .filter_map(|result| match result.outcome {
+                BatchOrderOutcome::Placed { order_id, .. } => Some(order_id),
+                BatchOrderOutcome::Rejected(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Simulates placing the post only orders of a `MultipleOrderPacket` for `trader` without
+/// modifying the market. Returns None if the trader is not registered on the market.
+///
+/// Bids are processed before asks, each side from its most aggressive order, so orders of the
+/// batch see the orders placed before them. Crossing orders are rejected or amended according to
+/// `reject_post_only`, and when a side of the book is full its least aggressive order is evicted
+/// for a more aggressive one. Rejected orders are skipped so that the rest of the batch is still
+/// evaluated, even though the program fails the whole instruction. Order expiry and funds are not
+/// taken into account.
+pub fn simulate_multiple_order_packet(
+    market: &dyn Market,
+    trader: &Pubkey,
+    packet: &MultipleOrderPacket,
+) -> Option<MultipleOrderSimulation> {
+    let trader_index = market.get_trader_index(trader)? as u64;
+    let load_book = |side| -> BTreeMap<FIFOOrderId, FIFORestingOrder> {
+        market
+            .get_book(side)
+            .iter()
+            .map(|(order_id, order)| (*order_id, *order))
+            .collect()
+    };
+    let mut bids = load_book(Side::Bid);
+    let mut asks = load_book(Side::Ask);
+    let mut simulation = MultipleOrderSimulation {
+        next_order_sequence_number: market.get_order_sequence_number(),
+        ..Default::default()
+    };
+
+    for (side, orders) in [(Side::Bid, &packet.bids), (Side::Ask, &packet.asks)] {
+        let capacity = market.get_book(side).capacity();
+        let mut orders = orders.clone();
+        match side {
+            Side::Bid => orders.sort_by_key(|order| Reverse(order.price_in_ticks)),
+            Side::Ask => orders.sort_by_key(|order| order.price_in_ticks),
+        }
+        for order in orders {
+            let (book, opposite_book) = match side {
+                Side::Bid => (&mut bids, &asks),
+                Side::Ask => (&mut asks, &bids),
+            };
+            let outcome = place_post_only(
+                book,
+                opposite_book,
+                capacity,
+                side,
+                &order,
+                packet.reject_post_only,
+                trader_index,
+                &mut simulation,
+                market,
+            );
+            simulation.results.push(BatchOrderResult {
+                side,
+                order,
+                outcome,
+            });
+        }
+    }
+    Some(simulation)
+}
+
+/// Places a single post only order of a batch on the simulated book.
+#[allow(clippy::too_many_arguments)]
+fn place_post_only(
+    book: &mut BTreeMap<FIFOOrderId, FIFORestingOrder>,
+    opposite_book: &BTreeMap<FIFOOrderId, FIFORestingOrder>,
+    capacity: usize,
+    side: Side,
+    order: &CondensedOrder,
+    reject_post_only: bool,
+    trader_index: u64,
+    simulation: &mut MultipleOrderSimulation,
+    market: &dyn Market,
+) -> BatchOrderOutcome {
+    let mut price_in_ticks = order.price_in_ticks;
+    let mut amended_from = None;
+    if let Some(best_opposite_price) = opposite_book
+        .keys()
+        .next()
+        .map(|order_id| order_id.price_in_ticks)
+        .filter(|best| side.crosses(price_in_ticks, *best))
+    {
+        if reject_post_only {
+            return BatchOrderOutcome::Rejected(BatchRejectReason::CrossesBook);
+        }
+        let amended_price = match side {
+            Side::Bid => best_opposite_price.checked_sub(1),
+            Side::Ask => best_opposite_price.checked_add(1),
+        };
+        match amended_price.filter(|price| *price > 0) {
+            Some(price) => {
+                amended_from = Some(price_in_ticks);
+                price_in_ticks = price;
+            }
+            None => return BatchOrderOutcome::Rejected(BatchRejectReason::NoValidAmendedPrice),
+        }
+    }
+
+    let sequence_number = simulation.next_order_sequence_number;
+    let order_id = FIFOOrderId::new(
+        price_in_ticks,
+        match side {
+            Side::Bid => !sequence_number,
+            Side::Ask => sequence_number,
+        },
+    );
+    if book.len() >= capacity {
+        match book.last_key_value().map(|(worst, _)| *worst) {
+            Some(worst) if order_id < worst => {
+                let evicted = book.remove(&worst).unwrap_or_default();
+                simulation.evicted.push(EvictedOrder {
+                    order_id: worst,
+                    maker: market.get_trader_id_from_index(evicted.trader_index as u32),
+                    num_base_lots: evicted.num_base_lots,
+                });
+            }
+            _ => return BatchOrderOutcome::Rejected(BatchRejectReason::BookFull),
+        }
+    }
+    book.insert(
+        order_id,
+        FIFORestingOrder::new(trader_index, order.size_in_base_lots),
+    );
+    simulation.next_order_sequence_number += 1;
+    BatchOrderOutcome::Placed {
+        order_id,
+        amended_from,
+    }
+}
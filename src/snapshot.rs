@@ -0,0 +1,353 @@
+use crate::enums::Side;
+use crate::events::{AuditLogHeader, MarketEvent};
+use crate::market::{
+    aggregate_levels, FIFOOrderId, FIFORestingOrder, Ladder, Market, MarketHeader, TraderState,
+};
+use crate::trader_index::TraderIndexMap;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// Magic bytes at the start of every snapshot file.
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"PHXSNAP\0";
+
+/// The snapshot format version written by this crate.
+///
+/// Version 2 added the trader index of each registered trader.
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Not a market snapshot")]
+    InvalidMagic,
+    #[error("Unsupported snapshot version {0}")]
+    UnsupportedVersion(u16),
+}
+
+/// A registered trader and their position in the market's trader tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct SnapshotTrader {
+    pub trader: Pubkey,
+    /// The index stored in `FIFORestingOrder::trader_index` for this trader's orders.
+    pub trader_index: u32,
+    pub state: TraderState,
+}
+
+/// An owned copy of a market's state at a given slot, decoupled from the on-chain account layout.
+///
+/// Orders are stored in book order (best price first, then time priority) and traders are
+/// stored in the order of the market's trader tree.
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct MarketSnapshot {
+    pub slot: u64,
+    pub header: MarketHeader,
+    pub base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub taker_fee_bps: u16,
+    pub order_sequence_number: u64,
+    pub bids: Vec<(FIFOOrderId, FIFORestingOrder)>,
+    pub asks: Vec<(FIFOOrderId, FIFORestingOrder)>,
+    pub traders: Vec<SnapshotTrader>,
+}
+
+/// The layout of version 1 snapshots, which stored traders without their trader indices.
+#[derive(BorshDeserialize)]
+struct MarketSnapshotV1 {
+    slot: u64,
+    header: MarketHeader,
+    base_lots_per_base_unit: u64,
+    tick_size_in_quote_lots_per_base_unit: u64,
+    taker_fee_bps: u16,
+    order_sequence_number: u64,
+    bids: Vec<(FIFOOrderId, FIFORestingOrder)>,
+    asks: Vec<(FIFOOrderId, FIFORestingOrder)>,
+    traders: Vec<(Pubkey, TraderState)>,
+}
+
+impl From<MarketSnapshotV1> for MarketSnapshot {
+    /// Version 1 snapshots did not record trader indices, so they are rebuilt by numbering the
+    /// traders from 1 in trader tree order. This matches the market only if no seat was ever
+    /// evicted and traders registered in key order, so orders in upgraded snapshots may be
+    /// attributed to the wrong trader.
+    fn from(snapshot: MarketSnapshotV1) -> Self {
+        Self {
+            slot: snapshot.slot,
+            header: snapshot.header,
+            base_lots_per_base_unit: snapshot.base_lots_per_base_unit,
+            tick_size_in_quote_lots_per_base_unit: snapshot.tick_size_in_quote_lots_per_base_unit,
+            taker_fee_bps: snapshot.taker_fee_bps,
+            order_sequence_number: snapshot.order_sequence_number,
+            bids: snapshot.bids,
+            asks: snapshot.asks,
+            traders: snapshot
+                .traders
+                .into_iter()
+                .zip(1..)
+                .map(|((trader, state), trader_index)| SnapshotTrader {
+                    trader,
+                    trader_index,
+                    state,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl MarketSnapshot {
+    pub fn from_market(header: &MarketHeader, market: &dyn Market, slot: u64) -> Self {
+        let book = |side| {
+            market
+                .get_book(side)
+                .iter()
+                .map(|(order_id, order)| (*order_id, *order))
+                .collect()
+        };
+        Self {
+            slot,
+            header: *header,
+            base_lots_per_base_unit: market.get_base_lots_per_base_unit().as_u64(),
+            tick_size_in_quote_lots_per_base_unit: market
+                .get_tick_size_in_quote_lots_per_base_unit()
+                .as_u64(),
+            taker_fee_bps: market.get_taker_bps(),
+            order_sequence_number: market.get_order_sequence_number(),
+            bids: book(Side::Bid),
+            asks: book(Side::Ask),
+            traders: market
+                .get_registered_traders()
+                .iter()
+                .map(|(trader, state)| SnapshotTrader {
+                    trader: *trader,
+                    trader_index: market.get_trader_index(trader).unwrap_or_default(),
+                    state: *state,
+                })
+                .collect(),
+        }
+    }
+
+    /// Decodes a snapshot from the raw data of a market account.
+    pub fn from_account_data(data: &[u8], slot: u64) -> Option<Self> {
+        let header_size = std::mem::size_of::<MarketHeader>();
+        if data.len() < header_size {
+            return None;
+        }
+        let (header_bytes, market_bytes) = data.split_at(header_size);
+        let header = bytemuck::try_from_bytes::<MarketHeader>(header_bytes).ok()?;
+        let market = crate::dispatch::load_with_dispatch(&header.market_size_params, market_bytes)?;
+        Some(Self::from_market(header, market.inner, slot))
+    }
+
+    pub fn get_book(&self, side: Side) -> &[(FIFOOrderId, FIFORestingOrder)] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    pub fn get_trader_state(&self, trader: &Pubkey) -> Option<&TraderState> {
+        self.traders
+            .iter()
+            .find(|t| t.trader == *trader)
+            .map(|t| &t.state)
+    }
+
+    pub fn get_trader_index(&self, trader: &Pubkey) -> Option<u32> {
+        self.traders
+            .iter()
+            .find(|t| t.trader == *trader)
+            .map(|t| t.trader_index)
+    }
+
+    pub fn get_trader_id_from_index(&self, trader_index: u32) -> Option<Pubkey> {
+        self.traders
+            .iter()
+            .find(|t| t.trader_index == trader_index)
+            .map(|t| t.trader)
+    }
+
+    /// Returns every order on one side of the book in priority order, with the key of the
+    /// trader that placed it, or None if the trader is not in the snapshot.
+    pub fn get_full_book(
+        &self,
+        side: Side,
+    ) -> Vec<(FIFOOrderId, FIFORestingOrder, Option<Pubkey>)> {
+        let makers: HashMap<u64, Pubkey> = self
+            .traders
+            .iter()
+            .map(|t| (t.trader_index as u64, t.trader))
+            .collect();
+        self.get_book(side)
+            .iter()
+            .map(|(order_id, order)| (*order_id, *order, makers.get(&order.trader_index).copied()))
+            .collect()
+    }
+
+    pub fn get_ladder(&self, levels: u64) -> Ladder {
+        Ladder {
+            bids: aggregate_levels(self.bids.iter().map(|(k, v)| (k, v)), levels),
+            asks: aggregate_levels(self.asks.iter().map(|(k, v)| (k, v)), levels),
+        }
+    }
+
+    /// Applies the book changes described by a single event logged under `header`. Orders
+    /// placed by traders unknown to the snapshot are attributed to trader index 0.
+    ///
+    /// This searches the trader table for every placed order; when replaying many events, use
+    /// `apply_event_with_trader_indices` instead.
+    pub fn apply_event(&mut self, header: &AuditLogHeader, event: &MarketEvent) {
+        let trader_index = match event {
+            MarketEvent::Place { .. } => self.get_trader_index(&header.signer),
+            _ => None,
+        };
+        self.apply_event_for_trader_index(event, trader_index.unwrap_or_default());
+    }
+
+    /// Applies a single event like `apply_event`, looking up the signer's trader index in
+    /// `trader_indices`.
+    pub fn apply_event_with_trader_indices(
+        &mut self,
+        header: &AuditLogHeader,
+        event: &MarketEvent,
+        trader_indices: &TraderIndexMap,
+    ) {
+        let trader_index = trader_indices
+            .get_trader_index(&header.signer)
+            .unwrap_or_default();
+        self.apply_event_for_trader_index(event, trader_index);
+    }
+
+    /// Applies every event of a single log and advances the slot and market sequence number
+    /// past it.
+    pub fn apply_log(&mut self, header: &AuditLogHeader, events: &[MarketEvent]) {
+        let trader_index = self.get_trader_index(&header.signer).unwrap_or_default();
+        for event in events {
+            self.apply_event_for_trader_index(event, trader_index);
+        }
+        self.advance_past(header);
+    }
+
+    /// Applies every event of a single log like `apply_log`, looking up the signer's trader
+    /// index in `trader_indices`.
+    pub fn apply_log_with_trader_indices(
+        &mut self,
+        header: &AuditLogHeader,
+        events: &[MarketEvent],
+        trader_indices: &TraderIndexMap,
+    ) {
+        let trader_index = trader_indices
+            .get_trader_index(&header.signer)
+            .unwrap_or_default();
+        for event in events {
+            self.apply_event_for_trader_index(event, trader_index);
+        }
+        self.advance_past(header);
+    }
+
+    /// Applies an event, attributing placed orders to `trader_index`.
+    fn apply_event_for_trader_index(&mut self, event: &MarketEvent, trader_index: u32) {
+        match *event {
+            MarketEvent::Place {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_placed,
+                ..
+            } => {
+                self.insert_order(
+                    FIFOOrderId::new(price_in_ticks, order_sequence_number),
+                    FIFORestingOrder::new(trader_index as u64, base_lots_placed),
+                );
+            }
+            MarketEvent::Fill {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_remaining,
+                ..
+            }
+            | MarketEvent::Reduce {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_remaining,
+                ..
+            } => self.set_order_size(
+                FIFOOrderId::new(price_in_ticks, order_sequence_number),
+                base_lots_remaining,
+            ),
+            MarketEvent::Evict {
+                order_sequence_number,
+                price_in_ticks,
+                ..
+            } => self.set_order_size(FIFOOrderId::new(price_in_ticks, order_sequence_number), 0),
+            _ => {}
+        }
+    }
+
+    fn advance_past(&mut self, header: &AuditLogHeader) {
+        self.slot = self.slot.max(header.slot);
+        self.header.market_sequence_number = self
+            .header
+            .market_sequence_number
+            .max(header.market_sequence_number + 1);
+    }
+
+    fn book_mut(&mut self, side: Side) -> &mut Vec<(FIFOOrderId, FIFORestingOrder)> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn insert_order(&mut self, order_id: FIFOOrderId, order: FIFORestingOrder) {
+        let book = self.book_mut(Side::from_order_sequence_number(
+            order_id.order_sequence_number,
+        ));
+        match book.binary_search_by(|(id, _)| id.cmp(&order_id)) {
+            Ok(i) => book[i].1 = order,
+            Err(i) => book.insert(i, (order_id, order)),
+        }
+    }
+
+    fn set_order_size(&mut self, order_id: FIFOOrderId, num_base_lots: u64) {
+        let book = self.book_mut(Side::from_order_sequence_number(
+            order_id.order_sequence_number,
+        ));
+        if let Ok(i) = book.binary_search_by(|(id, _)| id.cmp(&order_id)) {
+            if num_base_lots == 0 {
+                book.remove(i);
+            } else {
+                book[i].1.num_base_lots = num_base_lots;
+            }
+        }
+    }
+
+    /// Writes the snapshot as a magic prefix, a little-endian format version, and the
+    /// Borsh-encoded snapshot.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), SnapshotError> {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        self.serialize(writer)?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by `write_to`. Version 1 snapshots are upgraded on read, with
+    /// their trader indices rebuilt in trader tree order.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, SnapshotError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version)?;
+        let mut body = vec![];
+        reader.read_to_end(&mut body)?;
+        match u16::from_le_bytes(version) {
+            1 => Ok(MarketSnapshotV1::try_from_slice(&body)?.into()),
+            2 => Ok(Self::try_from_slice(&body)?),
+            version => Err(SnapshotError::UnsupportedVersion(version)),
+        }
+    }
+}
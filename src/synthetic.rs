@@ -0,0 +1,99 @@
+use crate::market::{Ladder, LadderOrder};
+use crate::market_metadata::MarketMetadata;
+use serde::{Deserialize, Serialize};
+
+/// A level of a synthetic ladder, in UI units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticLevel {
+    /// The price, in units of the denominating market's base token per unit of the priced
+    /// market's base token.
+    pub price: f64,
+
+    /// The size available at this price, in units of the priced market's base token.
+    pub size: f64,
+}
+
+/// A ladder for a pair that is not listed directly, built by routing through two markets that
+/// share a quote token. For example, a JTO/SOL ladder built from JTO/USDC and SOL/USDC.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticLadder {
+    pub bids: Vec<SyntheticLevel>,
+    pub asks: Vec<SyntheticLevel>,
+}
+
+impl SyntheticLadder {
+    /// Combines the ladder of the market being priced (e.g. JTO/USDC) with the ladder of the
+    /// market it is priced in (e.g. SOL/USDC).
+    ///
+    /// Selling the synthetic pair sells into `priced`'s bids and buys from `denominating`'s asks,
+    /// and buying it does the reverse, so each synthetic level is sized by whichever leg runs
+    /// out of quote notional first. Fees are not included. Returns None if the markets do not
+    /// share a quote token or have the same base token.
+    pub fn from_ladders(
+        priced: (&Ladder, &MarketMetadata),
+        denominating: (&Ladder, &MarketMetadata),
+    ) -> Option<Self> {
+        let (priced_ladder, priced_metadata) = priced;
+        let (denominating_ladder, denominating_metadata) = denominating;
+        if priced_metadata.quote_mint != denominating_metadata.quote_mint
+            || priced_metadata.base_mint == denominating_metadata.base_mint
+        {
+            return None;
+        }
+        let to_ui = |metadata: &MarketMetadata, levels: &[LadderOrder]| {
+            levels
+                .iter()
+                .map(|level| {
+                    (
+                        metadata.ticks_to_float_price(level.price_in_ticks),
+                        metadata.raw_to_ui_size(level.size_in_base_lots),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        Some(Self {
+            bids: combine(
+                &to_ui(priced_metadata, &priced_ladder.bids),
+                &to_ui(denominating_metadata, &denominating_ladder.asks),
+            ),
+            asks: combine(
+                &to_ui(priced_metadata, &priced_ladder.asks),
+                &to_ui(denominating_metadata, &denominating_ladder.bids),
+            ),
+        })
+    }
+}
+
+/// Walks two legs of (price, size) levels in order, matching them by quote notional. Each
+/// output level has price `priced / denominating` and the size of the priced leg consumed.
+fn combine(priced: &[(f64, f64)], denominating: &[(f64, f64)]) -> Vec<SyntheticLevel> {
+    let mut levels: Vec<SyntheticLevel> = vec![];
+    let mut priced_iter = priced.iter().filter(|(p, s)| *p > 0.0 && *s > 0.0);
+    let mut denominating_iter = denominating.iter().filter(|(p, s)| *p > 0.0 && *s > 0.0);
+    let mut priced_level = priced_iter.next().map(|&(p, s)| (p, p * s));
+    let mut denominating_level = denominating_iter.next().map(|&(p, s)| (p, p * s));
+    while let (
+        Some((priced_price, priced_notional)),
+        Some((denominating_price, denominating_notional)),
+    ) = (priced_level, denominating_level)
+    {
+        let notional = priced_notional.min(denominating_notional);
+        let price = priced_price / denominating_price;
+        let size = notional / priced_price;
+        match levels.last_mut() {
+            Some(last) if last.price == price => last.size += size,
+            _ => levels.push(SyntheticLevel { price, size }),
+        }
+        priced_level = if priced_notional > notional {
+            Some((priced_price, priced_notional - notional))
+        } else {
+            priced_iter.next().map(|&(p, s)| (p, p * s))
+        };
+        denominating_level = if denominating_notional > notional {
+            Some((denominating_price, denominating_notional - notional))
+        } else {
+            denominating_iter.next().map(|&(p, s)| (p, p * s))
+        };
+    }
+    levels
+}
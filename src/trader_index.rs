@@ -0,0 +1,79 @@
+use crate::market::Market;
+use crate::snapshot::MarketSnapshot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// A two-way map between registered traders and their trader indices, for constant time lookups
+/// while replaying events.
+///
+/// The map is kept up to date with `insert` and `remove_trader` as traders are registered and
+/// evicted, instead of being rebuilt from the trader tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraderIndexMap {
+    indices: HashMap<Pubkey, u32>,
+    traders: HashMap<u32, Pubkey>,
+}
+
+impl TraderIndexMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_market(market: &dyn Market) -> Self {
+        let mut map = Self::new();
+        for (trader, _) in market.get_registered_traders().iter() {
+            if let Some(trader_index) = market.get_trader_index(trader) {
+                map.insert(*trader, trader_index);
+            }
+        }
+        map
+    }
+
+    pub fn from_snapshot(snapshot: &MarketSnapshot) -> Self {
+        let mut map = Self::new();
+        for trader in snapshot.traders.iter() {
+            map.insert(trader.trader, trader.trader_index);
+        }
+        map
+    }
+
+    pub fn get_trader_index(&self, trader: &Pubkey) -> Option<u32> {
+        self.indices.get(trader).copied()
+    }
+
+    pub fn get_trader_id_from_index(&self, trader_index: u32) -> Option<Pubkey> {
+        self.traders.get(&trader_index).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Maps `trader` to `trader_index`, removing any previous mapping of either, since a trader
+    /// index is reused once its trader is evicted.
+    pub fn insert(&mut self, trader: Pubkey, trader_index: u32) {
+        if let Some(previous_index) = self.indices.insert(trader, trader_index) {
+            self.traders.remove(&previous_index);
+        }
+        if let Some(previous_trader) = self.traders.insert(trader_index, trader) {
+            if previous_trader != trader {
+                self.indices.remove(&previous_trader);
+            }
+        }
+    }
+
+    /// Removes `trader` and returns their trader index, if they were in the map.
+    pub fn remove_trader(&mut self, trader: &Pubkey) -> Option<u32> {
+        let trader_index = self.indices.remove(trader)?;
+        self.traders.remove(&trader_index);
+        Some(trader_index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &u32)> {
+        self.indices.iter()
+    }
+}
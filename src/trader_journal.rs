@@ -0,0 +1,295 @@
+use crate::enums::Side;
+use crate::events::{MarketEvent, PhoenixTransactionEvents};
+use crate::instructions::DecodedPhoenixInstruction;
+use crate::order_packet::OrderPacket;
+use crate::sdk_types::OrderId;
+use crate::units::Ticks;
+use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+/// An order sent in an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalOrder {
+    pub side: Side,
+    /// The limit price, or None for a market order.
+    pub price_in_ticks: Option<u64>,
+    pub num_base_lots: u64,
+}
+
+/// What the events of the instruction showed had happened to its orders.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Acknowledgement {
+    pub slot: u64,
+    pub market_sequence_number: u64,
+    /// The ids of the orders that were placed on the book.
+    pub placed: Vec<OrderId>,
+    pub base_lots_filled: u64,
+    pub quote_lots_filled: u64,
+}
+
+/// An instruction that placed orders, and its acknowledgement once its events are observed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    #[serde(with = "crate::pubkey_serde")]
+    pub market: Pubkey,
+    pub client_order_id: u128,
+    /// The slot the instruction was sent at.
+    pub sent_slot: u64,
+    pub orders: Vec<JournalOrder>,
+    pub acknowledgement: Option<Acknowledgement>,
+}
+
+impl JournalEntry {
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledgement.is_some()
+    }
+}
+
+/// An order placed by the trader that does not correspond to any recorded instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrphanedOrder {
+    #[serde(with = "crate::pubkey_serde")]
+    pub market: Pubkey,
+    pub client_order_id: u128,
+    pub order_id: OrderId,
+    pub slot: u64,
+    pub market_sequence_number: u64,
+}
+
+/// Records the orders a trader sent and correlates them with the events later observed on
+/// chain, by market and client order id.
+///
+/// Instructions sharing a market and client order id are matched to events in the order they
+/// were recorded. The journal can be serialized to persist it across restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraderJournal {
+    #[serde(with = "crate::pubkey_serde")]
+    trader: Pubkey,
+    entries: Vec<JournalEntry>,
+    orphaned: Vec<OrphanedOrder>,
+}
+
+impl TraderJournal {
+    pub fn new(trader: Pubkey) -> Self {
+        Self {
+            trader,
+            entries: vec![],
+            orphaned: vec![],
+        }
+    }
+
+    pub fn trader(&self) -> &Pubkey {
+        &self.trader
+    }
+
+    /// Records a Phoenix instruction sent by the trader. Returns false if the instruction is not
+    /// a Phoenix instruction from the trader that places orders.
+    pub fn record_instruction(&mut self, instruction: &Instruction, sent_slot: u64) -> bool {
+        if instruction.program_id != crate::id() {
+            return false;
+        }
+        // Every instruction that places orders starts with the program, log authority, market
+        // and trader accounts.
+        let (market, trader) = match instruction.accounts.get(2..4) {
+            Some([market, trader]) => (market.pubkey, trader.pubkey),
+            _ => return false,
+        };
+        if trader != self.trader {
+            return false;
+        }
+        DecodedPhoenixInstruction::decode(&instruction.data)
+            .is_some_and(|decoded| self.record(market, &decoded, sent_slot))
+    }
+
+    /// Records a decoded instruction sent by the trader on `market`. Returns false if the
+    /// instruction does not place orders.
+    pub fn record(
+        &mut self,
+        market: Pubkey,
+        instruction: &DecodedPhoenixInstruction,
+        sent_slot: u64,
+    ) -> bool {
+        let (client_order_id, orders) = match placed_orders(instruction) {
+            Some(placed) => placed,
+            None => return false,
+        };
+        self.entries.push(JournalEntry {
+            market,
+            client_order_id,
+            sent_slot,
+            orders,
+            acknowledgement: None,
+        });
+        true
+    }
+
+    /// Correlates the events of an instruction with the recorded instructions. Events of other
+    /// signers and events that were already observed are ignored.
+    pub fn observe(&mut self, batch: &PhoenixTransactionEvents) {
+        let header = &batch.header;
+        if header.signer != self.trader {
+            return;
+        }
+        let client_order_id = match placed_orders(&batch.instruction) {
+            Some((client_order_id, _)) => client_order_id,
+            None => return,
+        };
+        let already_observed = self.entries.iter().any(|entry| {
+            entry.market == header.market
+                && entry
+                    .acknowledgement
+                    .as_ref()
+                    .is_some_and(|ack| ack.market_sequence_number == header.market_sequence_number)
+        }) || self.orphaned.iter().any(|orphan| {
+            orphan.market == header.market
+                && orphan.market_sequence_number == header.market_sequence_number
+        });
+        if already_observed {
+            return;
+        }
+
+        let mut acknowledgement = Acknowledgement {
+            slot: header.slot,
+            market_sequence_number: header.market_sequence_number,
+            placed: vec![],
+            base_lots_filled: 0,
+            quote_lots_filled: 0,
+        };
+        for event in batch.events.iter() {
+            match *event {
+                MarketEvent::Place {
+                    order_sequence_number,
+                    price_in_ticks,
+                    ..
+                } => acknowledgement.placed.push(OrderId {
+                    price_in_ticks,
+                    order_sequence_number,
+                }),
+                MarketEvent::FillSummary {
+                    total_base_lots_filled,
+                    total_quote_lots_filled,
+                    ..
+                } => {
+                    acknowledgement.base_lots_filled += total_base_lots_filled;
+                    acknowledgement.quote_lots_filled += total_quote_lots_filled;
+                }
+                _ => {}
+            }
+        }
+
+        match self.entries.iter_mut().find(|entry| {
+            !entry.is_acknowledged()
+                && entry.market == header.market
+                && entry.client_order_id == client_order_id
+        }) {
+            Some(entry) => entry.acknowledgement = Some(acknowledgement),
+            None => self
+                .orphaned
+                .extend(acknowledgement.placed.iter().map(|order_id| OrphanedOrder {
+                    market: header.market,
+                    client_order_id,
+                    order_id: *order_id,
+                    slot: header.slot,
+                    market_sequence_number: header.market_sequence_number,
+                })),
+        }
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Returns the recorded instructions whose events have not been observed.
+    pub fn unacknowledged(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().filter(|entry| !entry.is_acknowledged())
+    }
+
+    /// Returns the unacknowledged instructions sent more than `max_slot_age` slots before
+    /// `now_slot`, which have most likely been dropped.
+    pub fn stale(&self, now_slot: u64, max_slot_age: u64) -> impl Iterator<Item = &JournalEntry> {
+        self.unacknowledged()
+            .filter(move |entry| now_slot.saturating_sub(entry.sent_slot) > max_slot_age)
+    }
+
+    /// Returns the orders placed by the trader that no recorded instruction accounts for.
+    pub fn orphaned(&self) -> &[OrphanedOrder] {
+        &self.orphaned
+    }
+
+    /// Drops the acknowledged instructions and orphaned orders observed before `slot`, to bound
+    /// the size of a long running journal.
+    pub fn prune_before(&mut self, slot: u64) {
+        self.entries.retain(|entry| {
+            entry
+                .acknowledgement
+                .as_ref()
+                .is_none_or(|ack| ack.slot >= slot)
+        });
+        self.orphaned.retain(|orphan| orphan.slot >= slot);
+    }
+}
+
+/// Returns the client order id and orders of an instruction that places orders.
+fn placed_orders(instruction: &DecodedPhoenixInstruction) -> Option<(u128, Vec<JournalOrder>)> {
+    match instruction {
+        DecodedPhoenixInstruction::Swap(packet)
+        | DecodedPhoenixInstruction::SwapWithFreeFunds(packet)
+        | DecodedPhoenixInstruction::PlaceLimitOrder(packet)
+        | DecodedPhoenixInstruction::PlaceLimitOrderWithFreeFunds(packet) => {
+            let (client_order_id, order) = match *packet {
+                OrderPacket::PostOnly {
+                    side,
+                    price_in_ticks,
+                    num_base_lots,
+                    client_order_id,
+                    ..
+                }
+                | OrderPacket::Limit {
+                    side,
+                    price_in_ticks,
+                    num_base_lots,
+                    client_order_id,
+                    ..
+                } => (
+                    client_order_id,
+                    JournalOrder {
+                        side,
+                        price_in_ticks: Some(price_in_ticks.as_u64()),
+                        num_base_lots: num_base_lots.as_u64(),
+                    },
+                ),
+                OrderPacket::ImmediateOrCancel {
+                    side,
+                    price_in_ticks,
+                    num_base_lots,
+                    client_order_id,
+                    ..
+                } => (
+                    client_order_id,
+                    JournalOrder {
+                        side,
+                        price_in_ticks: price_in_ticks.map(Ticks::as_u64),
+                        num_base_lots: num_base_lots.as_u64(),
+                    },
+                ),
+            };
+            Some((client_order_id, vec![order]))
+        }
+        DecodedPhoenixInstruction::PlaceMultiplePostOnlyOrders(packet)
+        | DecodedPhoenixInstruction::PlaceMultiplePostOnlyOrdersWithFreeFunds(packet) => {
+            let orders = [(Side::Bid, &packet.bids), (Side::Ask, &packet.asks)]
+                .into_iter()
+                .flat_map(|(side, orders)| {
+                    orders.iter().map(move |order| JournalOrder {
+                        side,
+                        price_in_ticks: Some(order.price_in_ticks),
+                        num_base_lots: order.size_in_base_lots,
+                    })
+                })
+                .collect();
+            Some((packet.client_order_id.unwrap_or(0), orders))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,165 @@
+use crate::enums::Side;
+use crate::market::FIFOOrderId;
+use crate::market_metadata::MarketMetadata;
+use crate::price_source::PriceSource;
+use crate::snapshot::MarketSnapshot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+/// A trader's funds in a single token, summed over markets, in UI units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBalance {
+    pub mint: Pubkey,
+    /// Funds locked in resting orders.
+    pub locked: f64,
+    /// Funds deposited on markets but not locked in orders.
+    pub free: f64,
+}
+
+impl TokenBalance {
+    pub fn total(&self) -> f64 {
+        self.locked + self.free
+    }
+}
+
+/// A single resting order and its size in UI units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderExposure {
+    pub market: Pubkey,
+    pub order_id: FIFOOrderId,
+    pub side: Side,
+    /// The price in quote units per raw base unit.
+    pub price: f64,
+    /// The size in raw base units.
+    pub size: f64,
+    /// The value of the order in quote units of its market.
+    pub notional: f64,
+}
+
+/// The value of a trader's funds on a single market at its mark price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketValue {
+    pub market: Pubkey,
+    /// The mark price in quote units per raw base unit.
+    pub mark_price: f64,
+    /// Free and locked base funds, in raw base units.
+    pub base_units: f64,
+    /// Free and locked quote funds, in quote units.
+    pub quote_units: f64,
+}
+
+impl MarketValue {
+    /// The value of the base and quote funds, in quote units.
+    pub fn value(&self) -> f64 {
+        self.base_units * self.mark_price + self.quote_units
+    }
+}
+
+/// A trader's balances and resting orders across several markets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraderRisk {
+    pub trader: Pubkey,
+    /// The number of markets the trader has a seat on.
+    pub markets: usize,
+    /// Balances per token, ordered by mint.
+    pub balances: Vec<TokenBalance>,
+    pub open_orders: usize,
+    /// The order with the largest notional. Notionals of markets with different quote tokens
+    /// are compared as is.
+    pub largest_order: Option<OrderExposure>,
+    /// The value of the trader's funds on each market that has a mark price.
+    pub market_values: Vec<MarketValue>,
+}
+
+impl TraderRisk {
+    pub fn balance(&self, mint: &Pubkey) -> Option<&TokenBalance> {
+        self.balances.iter().find(|balance| balance.mint == *mint)
+    }
+}
+
+/// Aggregates the balances and orders of `trader` over snapshots of several markets, keyed by
+/// market address. Markets the trader has no seat on are skipped.
+pub fn trader_risk<'a>(
+    trader: &Pubkey,
+    snapshots: impl IntoIterator<Item = (&'a Pubkey, &'a MarketSnapshot)>,
+) -> TraderRisk {
+    trader_risk_with_prices(trader, snapshots, &|_: &Pubkey| None)
+}
+
+/// Same as `trader_risk`, also valuing the trader's funds on each market at the mark price of
+/// `prices`.
+pub fn trader_risk_with_prices<'a>(
+    trader: &Pubkey,
+    snapshots: impl IntoIterator<Item = (&'a Pubkey, &'a MarketSnapshot)>,
+    prices: &dyn PriceSource,
+) -> TraderRisk {
+    let mut risk = TraderRisk {
+        trader: *trader,
+        markets: 0,
+        balances: vec![],
+        open_orders: 0,
+        largest_order: None,
+        market_values: vec![],
+    };
+    let mut balances = BTreeMap::new();
+    for (market, snapshot) in snapshots {
+        let trader_index = match snapshot.get_trader_index(trader) {
+            Some(index) => index as u64,
+            None => continue,
+        };
+        let state = match snapshot.get_trader_state(trader) {
+            Some(state) => state,
+            None => continue,
+        };
+        let metadata = match MarketMetadata::from_snapshot(snapshot) {
+            Some(metadata) => metadata,
+            None => continue,
+        };
+        risk.markets += 1;
+        if let Some(mark_price) = prices.mark_price(market) {
+            risk.market_values.push(MarketValue {
+                market: *market,
+                mark_price,
+                base_units: metadata.raw_to_ui_size(state.total_base_lots().as_u64()),
+                quote_units: metadata.quote_lots_to_ui_amount(state.total_quote_lots().as_u64()),
+            });
+        }
+
+        let base = balances.entry(metadata.base_mint).or_insert((0.0, 0.0));
+        base.0 += metadata.raw_to_ui_size(state.base_lots_locked.as_u64());
+        base.1 += metadata.raw_to_ui_size(state.base_lots_free.as_u64());
+        let quote = balances.entry(metadata.quote_mint).or_insert((0.0, 0.0));
+        quote.0 += metadata.quote_lots_to_ui_amount(state.quote_lots_locked.as_u64());
+        quote.1 += metadata.quote_lots_to_ui_amount(state.quote_lots_free.as_u64());
+
+        for side in [Side::Bid, Side::Ask] {
+            for (order_id, order) in snapshot.get_book(side) {
+                if order.trader_index != trader_index {
+                    continue;
+                }
+                risk.open_orders += 1;
+                let price = metadata.ticks_to_float_price(order_id.price_in_ticks);
+                let size = metadata.raw_to_ui_size(order.num_base_lots);
+                let exposure = OrderExposure {
+                    market: *market,
+                    order_id: *order_id,
+                    side,
+                    price,
+                    size,
+                    notional: price * size,
+                };
+                if risk
+                    .largest_order
+                    .is_none_or(|largest| exposure.notional > largest.notional)
+                {
+                    risk.largest_order = Some(exposure);
+                }
+            }
+        }
+    }
+    risk.balances = balances
+        .into_iter()
+        .map(|(mint, (locked, free))| TokenBalance { mint, locked, free })
+        .collect();
+    risk
+}
@@ -0,0 +1,119 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+/// The maximum size of a serialized transaction, in bytes.
+pub const MAX_TRANSACTION_SIZE: usize = solana_sdk::packet::PACKET_DATA_SIZE;
+
+/// An account referenced by a set of instructions, with its strongest access requirements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// The deduplicated set of accounts referenced by a group of instructions destined for a
+/// single transaction. Accounts appear in order of first reference, with the payer first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionAccounts {
+    pub accounts: Vec<TransactionAccount>,
+}
+
+impl TransactionAccounts {
+    pub fn from_instructions(instructions: &[Instruction], payer: &Pubkey) -> Self {
+        let mut transaction_accounts = Self::default();
+        transaction_accounts.add(*payer, true, true);
+        for instruction in instructions {
+            for meta in instruction.accounts.iter() {
+                transaction_accounts.add(meta.pubkey, meta.is_signer, meta.is_writable);
+            }
+            transaction_accounts.add(instruction.program_id, false, false);
+        }
+        transaction_accounts
+    }
+
+    fn add(&mut self, pubkey: Pubkey, is_signer: bool, is_writable: bool) {
+        match self.accounts.iter_mut().find(|a| a.pubkey == pubkey) {
+            Some(account) => {
+                account.is_signer |= is_signer;
+                account.is_writable |= is_writable;
+            }
+            None => self.accounts.push(TransactionAccount {
+                pubkey,
+                is_signer,
+                is_writable,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn signers(&self) -> Vec<Pubkey> {
+        self.accounts
+            .iter()
+            .filter(|a| a.is_signer)
+            .map(|a| a.pubkey)
+            .collect()
+    }
+
+    /// Accounts that will be write-locked by the transaction.
+    pub fn writable(&self) -> Vec<Pubkey> {
+        self.accounts
+            .iter()
+            .filter(|a| a.is_writable)
+            .map(|a| a.pubkey)
+            .collect()
+    }
+
+    /// Accounts that will be read-locked by the transaction.
+    pub fn readonly(&self) -> Vec<Pubkey> {
+        self.accounts
+            .iter()
+            .filter(|a| !a.is_writable)
+            .map(|a| a.pubkey)
+            .collect()
+    }
+}
+
+/// Returns the size in bytes of a legacy transaction containing `instructions` and paid for by
+/// `payer`, including its signatures.
+pub fn estimate_transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let accounts = TransactionAccounts::from_instructions(instructions, payer);
+    let num_signers = accounts.signers().len();
+    let instructions_size: usize = instructions
+        .iter()
+        .map(|instruction| {
+            1 + compact_len(instruction.accounts.len())
+                + instruction.accounts.len()
+                + compact_len(instruction.data.len())
+                + instruction.data.len()
+        })
+        .sum();
+    compact_len(num_signers)
+        + 64 * num_signers
+        + 3
+        + compact_len(accounts.len())
+        + 32 * accounts.len()
+        + 32
+        + compact_len(instructions.len())
+        + instructions_size
+}
+
+/// Returns true if a transaction containing `instructions` fits within the transaction size limit.
+pub fn fits_in_transaction(instructions: &[Instruction], payer: &Pubkey) -> bool {
+    estimate_transaction_size(instructions, payer) <= MAX_TRANSACTION_SIZE
+}
+
+/// Returns the number of bytes used to encode a length as a compact-u16.
+fn compact_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
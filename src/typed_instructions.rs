@@ -0,0 +1,555 @@
+//! Strongly typed Phoenix instructions. Each instruction has a struct holding its accounts and
+//! parameters that converts into an `Instruction` and can be parsed back from one.
+
+use crate::instructions::{
+    CancelMultipleOrdersByIdParams, CancelUpToParams, DepositParams, MultipleOrderPacket,
+    PhoenixInstruction, ReduceOrderParams, WithdrawParams,
+};
+use crate::order_packet::OrderPacket;
+use crate::phoenix_log_authority;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum InstructionParseError {
+    #[error("Instruction is not for the Phoenix program")]
+    WrongProgram,
+    #[error("Expected a {expected:?} instruction, found tag {found:?}")]
+    WrongInstruction {
+        expected: PhoenixInstruction,
+        found: Option<u8>,
+    },
+    #[error("Expected {expected} accounts, found {found}")]
+    NotEnoughAccounts { expected: usize, found: usize },
+    #[error("Failed to deserialize instruction parameters")]
+    InvalidData,
+}
+
+fn check_len(accounts: &[AccountMeta], expected: usize) -> Result<(), InstructionParseError> {
+    if accounts.len() < expected {
+        return Err(InstructionParseError::NotEnoughAccounts {
+            expected,
+            found: accounts.len(),
+        });
+    }
+    Ok(())
+}
+
+macro_rules! account_meta {
+    (readonly, $pubkey:expr) => {
+        AccountMeta::new_readonly($pubkey, false)
+    };
+    (writable, $pubkey:expr) => {
+        AccountMeta::new($pubkey, false)
+    };
+    (signer, $pubkey:expr) => {
+        AccountMeta::new($pubkey, true)
+    };
+}
+
+macro_rules! shank_account {
+    (readonly, $name:expr) => {
+        ShankAccount {
+            name: $name,
+            writable: false,
+            signer: false,
+        }
+    };
+    (writable, $name:expr) => {
+        ShankAccount {
+            name: $name,
+            writable: true,
+            signer: false,
+        }
+    };
+    (signer, $name:expr) => {
+        ShankAccount {
+            name: $name,
+            writable: true,
+            signer: true,
+        }
+    };
+}
+
+/// An account as it should be annotated on `PhoenixInstruction`.
+#[derive(Debug, Clone, Copy)]
+struct ShankAccount {
+    name: &'static str,
+    writable: bool,
+    signer: bool,
+}
+
+/// The source of `PhoenixInstruction`, whose Shank `#[account]` annotations are the reference
+/// the account structs are checked against at compile time.
+const PHOENIX_INSTRUCTION_SOURCE: &[u8] = include_bytes!("instructions.rs");
+
+const fn starts_with_at(bytes: &[u8], at: usize, prefix: &[u8]) -> bool {
+    if at + prefix.len() > bytes.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if bytes[at + i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns the position of the first `needle` that starts in `bytes[from..to]`.
+const fn find(bytes: &[u8], from: usize, to: usize, needle: &[u8]) -> Option<usize> {
+    let mut at = from;
+    while at < to {
+        if starts_with_at(bytes, at, needle) {
+            return Some(at);
+        }
+        at += 1;
+    }
+    None
+}
+
+const fn skip_spaces(bytes: &[u8], mut at: usize) -> usize {
+    while at < bytes.len() && bytes[at] == b' ' {
+        at += 1;
+    }
+    at
+}
+
+const fn line_end(bytes: &[u8], mut at: usize) -> usize {
+    while at < bytes.len() && bytes[at] != b'\n' {
+        at += 1;
+    }
+    at
+}
+
+/// Returns the start of the line before the line starting at `line`, which must not be the
+/// first line.
+const fn previous_line_start(bytes: &[u8], line: usize) -> usize {
+    let mut at = line - 1;
+    while at > 0 && bytes[at - 1] != b'\n' {
+        at -= 1;
+    }
+    at
+}
+
+/// Returns true if the `#[account(...)]` annotation on the line starting at `line` has the given
+/// index, name and flags.
+const fn shank_annotation_matches(
+    source: &[u8],
+    line: usize,
+    index: usize,
+    account: ShankAccount,
+) -> bool {
+    let end = line_end(source, line);
+    let digits = skip_spaces(source, line) + b"#[account(".len();
+    let mut at = digits;
+    let mut annotated_index = 0;
+    while at < end && source[at].is_ascii_digit() {
+        annotated_index = annotated_index * 10 + (source[at] - b'0') as usize;
+        at += 1;
+    }
+    if at == digits || annotated_index != index {
+        return false;
+    }
+    let name = match find(source, at, end, b"name = \"") {
+        Some(name) => name + b"name = \"".len(),
+        None => return false,
+    };
+    let account_name = account.name.as_bytes();
+    find(source, at, name, b"writable").is_some() == account.writable
+        && find(source, at, name, b"signer").is_some() == account.signer
+        && starts_with_at(source, name, account_name)
+        && starts_with_at(source, name + account_name.len(), b"\"")
+}
+
+/// Returns true if the `#[account]` annotations of `variant` on `PhoenixInstruction` are exactly
+/// the Phoenix program, the log authority and then `accounts`, in order.
+const fn shank_accounts_match(variant: &str, accounts: &[ShankAccount]) -> bool {
+    let source = PHOENIX_INSTRUCTION_SOURCE;
+    let variant = variant.as_bytes();
+    let mut line = match find(source, 0, source.len(), b"pub enum PhoenixInstruction {") {
+        Some(line) => line,
+        None => return false,
+    };
+    loop {
+        line = line_end(source, line) + 1;
+        if line >= source.len() || source[line] == b'}' {
+            return false;
+        }
+        let start = skip_spaces(source, line);
+        if starts_with_at(source, start, variant)
+            && starts_with_at(source, start + variant.len(), b" = ")
+        {
+            break;
+        }
+    }
+
+    let mut first = line;
+    let mut count = 0;
+    while first > 0 {
+        let previous = previous_line_start(source, first);
+        if !starts_with_at(source, skip_spaces(source, previous), b"#[account(") {
+            break;
+        }
+        first = previous;
+        count += 1;
+    }
+    if count != accounts.len() + 2 {
+        return false;
+    }
+
+    let implied = [
+        shank_account!(readonly, "phoenix_program"),
+        shank_account!(readonly, "log_authority"),
+    ];
+    let mut index = 0;
+    let mut line = first;
+    while index < count {
+        let account = if index < 2 {
+            implied[index]
+        } else {
+            accounts[index - 2]
+        };
+        if !shank_annotation_matches(source, line, index, account) {
+            return false;
+        }
+        line = line_end(source, line) + 1;
+        index += 1;
+    }
+    true
+}
+
+/// Generates a struct holding the accounts of an instruction, in the order of the instruction's
+/// `#[account]` annotations on `PhoenixInstruction`. The Phoenix program and log authority, which
+/// are always the first two accounts, are implied. Each account is `readonly`, `writable`, or
+/// `signer` (writable and signing), and takes its index from its position.
+///
+/// Accounts listed after `fixed` always hold the given address, so they are not fields of the
+/// struct and are not read back when parsing.
+///
+/// `typed_instruction!` checks the accounts, by name, index and flags, against the Shank
+/// annotations of each instruction that uses them, so the build fails if the two drift apart.
+macro_rules! instruction_accounts {
+    (
+        $(#[$doc:meta])* $name:ident {
+            $($field:ident: $kind:ident),* $(,)?
+        }
+        $(fixed {
+            $($fixed_field:ident: $fixed_kind:ident = $address:expr),* $(,)?
+        })?
+    ) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name {
+            $(pub $field: Pubkey,)*
+        }
+
+        impl $name {
+            pub const LEN: usize =
+                2 + [$(stringify!($field)),* $($(, stringify!($fixed_field))*)?].len();
+
+            /// The accounts after the Phoenix program and log authority, as Shank annotates them.
+            const SHANK_ACCOUNTS: &'static [ShankAccount] = &[
+                $(shank_account!($kind, stringify!($field)),)*
+                $($(shank_account!($fixed_kind, stringify!($fixed_field)),)*)?
+            ];
+
+            pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+                vec![
+                    AccountMeta::new_readonly(crate::id(), false),
+                    AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+                    $(account_meta!($kind, self.$field),)*
+                    $($(account_meta!($fixed_kind, $address),)*)?
+                ]
+            }
+
+            pub fn from_account_metas(
+                accounts: &[AccountMeta],
+            ) -> Result<Self, InstructionParseError> {
+                check_len(accounts, Self::LEN)?;
+                let mut accounts = accounts[2..].iter();
+                Ok(Self {
+                    $($field: accounts.next().unwrap().pubkey,)*
+                })
+            }
+        }
+
+        impl TryFrom<&[AccountMeta]> for $name {
+            type Error = InstructionParseError;
+
+            fn try_from(accounts: &[AccountMeta]) -> Result<Self, Self::Error> {
+                Self::from_account_metas(accounts)
+            }
+        }
+    };
+}
+
+instruction_accounts!(
+    /// Accounts for instructions that only touch the trader's free funds on the market.
+    FreeFundsAccounts {
+        market: writable,
+        trader: signer,
+    }
+);
+
+instruction_accounts!(
+    /// Accounts for instructions that use the trader's free funds and require a seat.
+    SeatedFreeFundsAccounts {
+        market: writable,
+        trader: signer,
+        seat: readonly,
+    }
+);
+
+instruction_accounts!(
+    /// Accounts for instructions that transfer tokens between the trader and the market vaults.
+    TokenTransferAccounts {
+        market: writable,
+        trader: signer,
+        base_account: writable,
+        quote_account: writable,
+        base_vault: writable,
+        quote_vault: writable,
+        token_program: readonly,
+    }
+);
+
+instruction_accounts!(
+    /// Accounts for instructions that transfer tokens and require a seat.
+    SeatedTokenTransferAccounts {
+        market: writable,
+        trader: signer,
+        seat: readonly,
+        base_account: writable,
+        quote_account: writable,
+        base_vault: writable,
+        quote_vault: writable,
+        token_program: readonly,
+    }
+);
+
+instruction_accounts!(
+    /// Accounts for `RequestSeat`.
+    RequestSeatAccounts {
+        market: writable,
+        payer: signer,
+        seat: writable,
+    }
+    fixed {
+        system_program: readonly = system_program::id(),
+    }
+);
+
+pub type SwapAccounts = TokenTransferAccounts;
+pub type SwapWithFreeFundsAccounts = SeatedFreeFundsAccounts;
+pub type PlaceLimitOrderAccounts = SeatedTokenTransferAccounts;
+pub type PlaceLimitOrderWithFreeFundsAccounts = SeatedFreeFundsAccounts;
+pub type ReduceOrderAccounts = TokenTransferAccounts;
+pub type ReduceOrderWithFreeFundsAccounts = FreeFundsAccounts;
+pub type CancelAllOrdersAccounts = TokenTransferAccounts;
+pub type CancelAllOrdersWithFreeFundsAccounts = FreeFundsAccounts;
+pub type CancelUpToAccounts = TokenTransferAccounts;
+pub type CancelUpToWithFreeFundsAccounts = FreeFundsAccounts;
+pub type CancelMultipleOrdersByIdAccounts = TokenTransferAccounts;
+pub type CancelMultipleOrdersByIdWithFreeFundsAccounts = FreeFundsAccounts;
+pub type WithdrawFundsAccounts = TokenTransferAccounts;
+pub type DepositFundsAccounts = SeatedTokenTransferAccounts;
+pub type PlaceMultiplePostOnlyOrdersAccounts = SeatedTokenTransferAccounts;
+pub type PlaceMultiplePostOnlyOrdersWithFreeFundsAccounts = SeatedFreeFundsAccounts;
+
+/// Checks the program id and tag of `instruction` and returns the data after the tag.
+fn instruction_params(
+    instruction: &Instruction,
+    expected: PhoenixInstruction,
+) -> Result<&[u8], InstructionParseError> {
+    if instruction.program_id != crate::id() {
+        return Err(InstructionParseError::WrongProgram);
+    }
+    match instruction.data.split_first() {
+        Some((tag, params)) if *tag == expected as u8 => Ok(params),
+        found => Err(InstructionParseError::WrongInstruction {
+            expected,
+            found: found.map(|(tag, _)| *tag),
+        }),
+    }
+}
+
+macro_rules! typed_instruction {
+    ($(#[$doc:meta])* $name:ident, $variant:ident, $accounts:ty, $field:ident: $params:ty) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name {
+            pub accounts: $accounts,
+            pub $field: $params,
+        }
+
+        const _: () = assert!(
+            shank_accounts_match(stringify!($variant), <$accounts>::SHANK_ACCOUNTS),
+            concat!(
+                stringify!($accounts),
+                " does not match the Shank accounts of PhoenixInstruction::",
+                stringify!($variant)
+            )
+        );
+
+        impl From<$name> for Instruction {
+            fn from(ix: $name) -> Self {
+                Instruction {
+                    program_id: crate::id(),
+                    accounts: ix.accounts.to_account_metas(),
+                    data: [
+                        PhoenixInstruction::$variant.to_vec(),
+                        ix.$field.try_to_vec().unwrap(),
+                    ]
+                    .concat(),
+                }
+            }
+        }
+
+        impl TryFrom<&Instruction> for $name {
+            type Error = InstructionParseError;
+
+            fn try_from(instruction: &Instruction) -> Result<Self, Self::Error> {
+                let params = instruction_params(instruction, PhoenixInstruction::$variant)?;
+                Ok(Self {
+                    accounts: <$accounts>::from_account_metas(&instruction.accounts)?,
+                    $field: <$params>::try_from_slice(params)
+                        .map_err(|_| InstructionParseError::InvalidData)?,
+                })
+            }
+        }
+    };
+    ($(#[$doc:meta])* $name:ident, $variant:ident, $accounts:ty) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name {
+            pub accounts: $accounts,
+        }
+
+        const _: () = assert!(
+            shank_accounts_match(stringify!($variant), <$accounts>::SHANK_ACCOUNTS),
+            concat!(
+                stringify!($accounts),
+                " does not match the Shank accounts of PhoenixInstruction::",
+                stringify!($variant)
+            )
+        );
+
+        impl From<$name> for Instruction {
+            fn from(ix: $name) -> Self {
+                Instruction {
+                    program_id: crate::id(),
+                    accounts: ix.accounts.to_account_metas(),
+                    data: PhoenixInstruction::$variant.to_vec(),
+                }
+            }
+        }
+
+        impl TryFrom<&Instruction> for $name {
+            type Error = InstructionParseError;
+
+            fn try_from(instruction: &Instruction) -> Result<Self, Self::Error> {
+                instruction_params(instruction, PhoenixInstruction::$variant)?;
+                Ok(Self {
+                    accounts: <$accounts>::from_account_metas(&instruction.accounts)?,
+                })
+            }
+        }
+    };
+}
+
+typed_instruction!(
+    /// Send a swap (no limit orders allowed) order.
+    SwapIx, Swap, TokenTransferAccounts, packet: OrderPacket
+);
+typed_instruction!(
+    /// Send a swap (no limit orders allowed) order using only deposited funds.
+    SwapWithFreeFundsIx, SwapWithFreeFunds, SeatedFreeFundsAccounts, packet: OrderPacket
+);
+typed_instruction!(
+    /// Place a limit order on the book.
+    PlaceLimitOrderIx, PlaceLimitOrder, SeatedTokenTransferAccounts, packet: OrderPacket
+);
+typed_instruction!(
+    /// Place a limit order on the book using only deposited funds.
+    PlaceLimitOrderWithFreeFundsIx,
+    PlaceLimitOrderWithFreeFunds,
+    SeatedFreeFundsAccounts,
+    packet: OrderPacket
+);
+typed_instruction!(
+    /// Reduce the size of an existing order on the book.
+    ReduceOrderIx, ReduceOrder, TokenTransferAccounts, params: ReduceOrderParams
+);
+typed_instruction!(
+    /// Reduce the size of an existing order on the book, keeping the funds on the market.
+    ReduceOrderWithFreeFundsIx,
+    ReduceOrderWithFreeFunds,
+    FreeFundsAccounts,
+    params: ReduceOrderParams
+);
+typed_instruction!(
+    /// Cancel all orders.
+    CancelAllOrdersIx, CancelAllOrders, TokenTransferAccounts
+);
+typed_instruction!(
+    /// Cancel all orders, keeping the funds on the market.
+    CancelAllOrdersWithFreeFundsIx, CancelAllOrdersWithFreeFunds, FreeFundsAccounts
+);
+typed_instruction!(
+    /// Cancel all orders more aggressive than a specified price.
+    CancelUpToIx, CancelUpTo, TokenTransferAccounts, params: CancelUpToParams
+);
+typed_instruction!(
+    /// Cancel all orders more aggressive than a specified price, keeping the funds on the market.
+    CancelUpToWithFreeFundsIx,
+    CancelUpToWithFreeFunds,
+    FreeFundsAccounts,
+    params: CancelUpToParams
+);
+typed_instruction!(
+    /// Cancel multiple orders by id.
+    CancelMultipleOrdersByIdIx,
+    CancelMultipleOrdersById,
+    TokenTransferAccounts,
+    params: CancelMultipleOrdersByIdParams
+);
+typed_instruction!(
+    /// Cancel multiple orders by id, keeping the funds on the market.
+    CancelMultipleOrdersByIdWithFreeFundsIx,
+    CancelMultipleOrdersByIdWithFreeFunds,
+    FreeFundsAccounts,
+    params: CancelMultipleOrdersByIdParams
+);
+typed_instruction!(
+    /// Withdraw free funds from the market.
+    WithdrawFundsIx, WithdrawFunds, TokenTransferAccounts, params: WithdrawParams
+);
+typed_instruction!(
+    /// Deposit funds into the market.
+    DepositFundsIx, DepositFunds, SeatedTokenTransferAccounts, params: DepositParams
+);
+typed_instruction!(
+    /// Request a seat on the market.
+    RequestSeatIx, RequestSeat, RequestSeatAccounts
+);
+typed_instruction!(
+    /// Place multiple post only orders on the book.
+    PlaceMultiplePostOnlyOrdersIx,
+    PlaceMultiplePostOnlyOrders,
+    SeatedTokenTransferAccounts,
+    packet: MultipleOrderPacket
+);
+typed_instruction!(
+    /// Place multiple post only orders on the book using only deposited funds.
+    PlaceMultiplePostOnlyOrdersWithFreeFundsIx,
+    PlaceMultiplePostOnlyOrdersWithFreeFunds,
+    SeatedFreeFundsAccounts,
+    packet: MultipleOrderPacket
+);
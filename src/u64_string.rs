@@ -0,0 +1,15 @@
+//! Serde helpers that encode `u64`s as decimal strings, for use with `#[serde(with = "...")]`.
+//!
+//! JavaScript numbers lose precision above 2^53, so values such as sequence numbers and lot
+//! counts are exchanged as strings, matching the TypeScript SDK's `bignum` fields.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(D::Error::custom)
+}
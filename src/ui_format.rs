@@ -0,0 +1,183 @@
+//! Rendering of market quantities as decimal strings in human units.
+//!
+//! Values are formatted with exact integer arithmetic, so a quantity is never shown with
+//! floating point noise. Base amounts are shown in raw base units with the base token's
+//! decimals, quote amounts in quote units with the quote token's decimals, and prices in quote
+//! units per raw base unit with as many decimals as one tick needs.
+
+use crate::enums::Side;
+use crate::events::TakerFill;
+use crate::market::{LadderOrder, MarketHeader};
+use crate::market_metadata::MarketMetadata;
+use crate::units::{BaseAtoms, BaseLots, QuoteAtoms, QuoteLots, Ticks};
+
+/// Extra price decimals used when a tick does not have a terminating decimal expansion in quote
+/// units per raw base unit. Digits past this are truncated.
+const MAX_EXTRA_PRICE_DECIMALS: u32 = 6;
+
+/// The market parameters and formatting rules used to render quantities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketContext {
+    pub metadata: MarketMetadata,
+
+    /// Whether to remove trailing zeros after the decimal point (and the point itself if no
+    /// decimals remain). Otherwise every value is padded to the full precision of its unit.
+    pub trim_trailing_zeros: bool,
+}
+
+impl MarketContext {
+    /// Returns the context of a market whose base unit is `raw_base_units_per_base_unit` raw
+    /// base units, or None if the header is invalid (see `MarketMetadata::new`).
+    pub fn from_header(header: &MarketHeader, raw_base_units_per_base_unit: u32) -> Option<Self> {
+        MarketMetadata::new(header, raw_base_units_per_base_unit).map(Self::from_metadata)
+    }
+
+    pub fn from_metadata(metadata: MarketMetadata) -> Self {
+        Self {
+            metadata,
+            trim_trailing_zeros: true,
+        }
+    }
+
+    pub fn trim_trailing_zeros(mut self, trim_trailing_zeros: bool) -> Self {
+        self.trim_trailing_zeros = trim_trailing_zeros;
+        self
+    }
+
+    /// The number of decimals of base amounts.
+    pub fn base_decimals(&self) -> u32 {
+        self.metadata.base_decimals
+    }
+
+    /// The number of decimals of quote amounts.
+    pub fn quote_decimals(&self) -> u32 {
+        self.metadata.quote_decimals
+    }
+
+    /// The number of decimals of prices: enough to show one tick exactly, when possible.
+    pub fn price_decimals(&self) -> u32 {
+        self.metadata.quote_decimals
+            + terminating_decimals(self.metadata.raw_base_units_per_base_unit as u128)
+                .unwrap_or(MAX_EXTRA_PRICE_DECIMALS)
+    }
+
+    fn format_base_atoms(&self, base_atoms: u128) -> String {
+        self.format(
+            base_atoms,
+            10u128.pow(self.base_decimals()),
+            self.base_decimals(),
+        )
+    }
+
+    fn format_quote_atoms(&self, quote_atoms: u128) -> String {
+        self.format(
+            quote_atoms,
+            10u128.pow(self.quote_decimals()),
+            self.quote_decimals(),
+        )
+    }
+
+    fn format_ticks(&self, price_in_ticks: u64) -> String {
+        self.format(
+            price_in_ticks as u128 * self.metadata.tick_size_in_quote_atoms_per_base_unit as u128,
+            10u128.pow(self.quote_decimals()) * self.metadata.raw_base_units_per_base_unit as u128,
+            self.price_decimals(),
+        )
+    }
+
+    fn format(&self, numerator: u128, denominator: u128, decimals: u32) -> String {
+        let mut formatted = (numerator / denominator).to_string();
+        if decimals == 0 {
+            return formatted;
+        }
+        formatted.push('.');
+        let mut remainder = numerator % denominator;
+        for _ in 0..decimals {
+            remainder *= 10;
+            formatted.push(char::from(b'0' + (remainder / denominator) as u8));
+            remainder %= denominator;
+        }
+        if self.trim_trailing_zeros {
+            formatted.truncate(formatted.trim_end_matches('0').trim_end_matches('.').len());
+        }
+        formatted
+    }
+}
+
+/// Returns the number of decimals of `1 / denominator`, or None if it does not terminate.
+fn terminating_decimals(mut denominator: u128) -> Option<u32> {
+    let mut twos = 0;
+    let mut fives = 0;
+    while denominator > 0 && denominator.is_multiple_of(2) {
+        denominator /= 2;
+        twos += 1;
+    }
+    while denominator > 0 && denominator.is_multiple_of(5) {
+        denominator /= 5;
+        fives += 1;
+    }
+    (denominator == 1).then_some(twos.max(fives))
+}
+
+/// A quantity that can be rendered in human units.
+pub trait UiFormat {
+    fn to_ui_string(&self, context: &MarketContext) -> String;
+}
+
+impl UiFormat for Ticks {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        context.format_ticks(self.0)
+    }
+}
+
+impl UiFormat for BaseLots {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        context.format_base_atoms(self.0 as u128 * context.metadata.base_atoms_per_base_lot as u128)
+    }
+}
+
+impl UiFormat for QuoteLots {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        context
+            .format_quote_atoms(self.0 as u128 * context.metadata.quote_atoms_per_quote_lot as u128)
+    }
+}
+
+impl UiFormat for BaseAtoms {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        context.format_base_atoms(self.0 as u128)
+    }
+}
+
+impl UiFormat for QuoteAtoms {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        context.format_quote_atoms(self.0 as u128)
+    }
+}
+
+/// Renders as `size @ price`.
+impl UiFormat for LadderOrder {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        format!(
+            "{} @ {}",
+            BaseLots(self.size_in_base_lots).to_ui_string(context),
+            Ticks(self.price_in_ticks).to_ui_string(context)
+        )
+    }
+}
+
+/// Renders as `buy size @ price` or `sell size @ price`, from the taker's point of view.
+impl UiFormat for TakerFill {
+    fn to_ui_string(&self, context: &MarketContext) -> String {
+        let direction = match self.taker_side {
+            Side::Bid => "buy",
+            Side::Ask => "sell",
+        };
+        format!(
+            "{} {} @ {}",
+            direction,
+            BaseLots(self.base_lots_filled).to_ui_string(context),
+            Ticks(self.price_in_ticks).to_ui_string(context)
+        )
+    }
+}
@@ -0,0 +1,226 @@
+//! Typed wrappers for the integer quantities of a market, so that a count in one unit cannot be
+//! passed where another is expected.
+//!
+//! Quantities (`Ticks`, lots and atoms) support arithmetic with themselves. Rates, such as the
+//! tick size, are separate types without arithmetic. There is no `From<u64>`, so a bare `u64`
+//! is always converted into a unit explicitly with `new`.
+
+use crate::market::MarketHeader;
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+macro_rules! newtype {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[repr(transparent)]
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Zeroable,
+            Pod,
+            BorshDeserialize,
+            BorshSerialize,
+            Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub u64);
+
+        impl $name {
+            pub const ZERO: Self = Self(0);
+
+            pub const fn new(value: u64) -> Self {
+                Self(value)
+            }
+
+            pub const fn as_u64(self) -> u64 {
+                self.0
+            }
+        }
+
+        impl From<$name> for u64 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+macro_rules! unit {
+    ($(#[$doc:meta])* $name:ident) => {
+        newtype!($(#[$doc])* $name);
+
+        impl $name {
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            pub fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+        }
+
+        impl std::ops::Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+        }
+
+        impl std::ops::Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+        }
+
+        impl std::ops::AddAssign for $name {
+            fn add_assign(&mut self, other: Self) {
+                self.0 += other.0;
+            }
+        }
+
+        impl std::ops::SubAssign for $name {
+            fn sub_assign(&mut self, other: Self) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl std::iter::Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|value| value.0).sum())
+            }
+        }
+
+    };
+}
+
+unit!(
+    /// A price, in ticks.
+    Ticks
+);
+
+unit!(
+    /// A quantity of the base token, in base lots.
+    BaseLots
+);
+
+unit!(
+    /// A quantity of the quote token, in quote lots.
+    QuoteLots
+);
+
+unit!(
+    /// A quantity of the base token, in the token's smallest unit.
+    BaseAtoms
+);
+
+unit!(
+    /// A quantity of the quote token, in the token's smallest unit.
+    QuoteAtoms
+);
+
+newtype!(
+    /// The number of base lots in one base unit.
+    BaseLotsPerBaseUnit
+);
+
+newtype!(
+    /// The tick size, in quote lots per base unit per tick.
+    QuoteLotsPerBaseUnitPerTick
+);
+
+newtype!(
+    /// A price, in quote lots per base unit.
+    QuoteLotsPerBaseUnit
+);
+
+newtype!(
+    /// A price, in quote atoms per base unit.
+    QuoteAtomsPerBaseUnit
+);
+
+impl Ticks {
+    /// Converts the price to quote atoms per base unit, or None if the result overflows.
+    pub fn to_quote_atoms_per_base_unit(
+        self,
+        header: &MarketHeader,
+    ) -> Option<QuoteAtomsPerBaseUnit> {
+        self.0
+            .checked_mul(header.get_tick_size_in_quote_atoms_per_base_unit())
+            .map(QuoteAtomsPerBaseUnit)
+    }
+
+    /// Converts the price to quote lots per base unit, or None if the header has no quote lot
+    /// size or the result overflows.
+    pub fn to_quote_lots_per_base_unit(
+        self,
+        header: &MarketHeader,
+    ) -> Option<QuoteLotsPerBaseUnit> {
+        self.0
+            .checked_mul(header.get_tick_size_in_quote_lots_per_base_unit()?)
+            .map(QuoteLotsPerBaseUnit)
+    }
+}
+
+impl BaseLots {
+    /// Converts to base atoms, or None if the result overflows.
+    pub fn to_base_atoms(self, header: &MarketHeader) -> Option<BaseAtoms> {
+        header.base_lots_to_base_atoms(self)
+    }
+}
+
+impl QuoteLots {
+    /// Converts to quote atoms, or None if the result overflows.
+    pub fn to_quote_atoms(self, header: &MarketHeader) -> Option<QuoteAtoms> {
+        header.quote_lots_to_quote_atoms(self)
+    }
+}
+
+impl BaseAtoms {
+    /// Converts to base lots, rounding down to a whole lot, or None if the header has no base
+    /// lot size.
+    pub fn to_base_lots(self, header: &MarketHeader) -> Option<BaseLots> {
+        self.0.checked_div(header.get_base_lot_size()).map(BaseLots)
+    }
+
+    /// Converts to base units (e.g. lamports to SOL).
+    pub fn to_base_units(self, header: &MarketHeader) -> f64 {
+        header.base_atoms_to_base_units_as_float(self.0)
+    }
+}
+
+impl QuoteAtoms {
+    /// Converts to quote lots, rounding down to a whole lot, or None if the header has no quote
+    /// lot size.
+    pub fn to_quote_lots(self, header: &MarketHeader) -> Option<QuoteLots> {
+        self.0
+            .checked_div(header.get_quote_lot_size())
+            .map(QuoteLots)
+    }
+
+    /// Converts to quote units (e.g. 1_000_000 to 1 USDC).
+    pub fn to_quote_units(self, header: &MarketHeader) -> f64 {
+        header.quote_atoms_to_quote_units_as_float(self.0)
+    }
+}
@@ -0,0 +1,176 @@
+//! Checks that captured instruction and event bytes decode with this crate's types and
+//! re-encode to the exact same bytes, to detect layout drift after program upgrades.
+
+use crate::events::MarketEvent;
+use crate::instructions::{
+    CancelMultipleOrdersByIdParams, CancelUpToParams, DepositParams, MultipleOrderPacket,
+    PhoenixInstruction, ReduceOrderParams, WithdrawParams,
+};
+use crate::order_packet::OrderPacket;
+use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
+
+/// The first byte at which re-serialized data differs from the original.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTripMismatch {
+    /// Offset of the first differing byte, relative to the start of the checked data.
+    pub offset: usize,
+    pub original_len: usize,
+    pub reserialized_len: usize,
+    /// The original byte at `offset`, or None if the original data is shorter.
+    pub original: Option<u8>,
+    /// The re-serialized byte at `offset`, or None if the re-serialized data is shorter.
+    pub reserialized: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WireCompatError {
+    #[error("Data is empty")]
+    Empty,
+    #[error("Unknown instruction tag {0}")]
+    UnknownInstruction(u8),
+    #[error("Failed to deserialize {type_name} at offset {offset}: {message}")]
+    Deserialize {
+        type_name: &'static str,
+        offset: usize,
+        message: String,
+    },
+    #[error("{0} trailing bytes were not consumed")]
+    TrailingBytes(usize),
+    #[error("Re-serialized data differs at offset {}", .0.offset)]
+    Mismatch(RoundTripMismatch),
+    #[error("Deserialized {0} differs from the serialized value")]
+    ValueMismatch(&'static str),
+}
+
+/// Deserializes `bytes` as a `T`, requiring every byte to be consumed, and checks that
+/// re-serializing the value reproduces `bytes` exactly.
+pub fn verify_roundtrip<T: BorshSerialize + BorshDeserialize>(
+    bytes: &[u8],
+) -> Result<T, WireCompatError> {
+    verify_roundtrip_at(bytes, 0)
+}
+
+/// Checks that `value` serializes to bytes that deserialize back to an equal value and
+/// re-serialize to the same bytes.
+pub fn verify_value_roundtrip<T: BorshSerialize + BorshDeserialize + PartialEq>(
+    value: &T,
+) -> Result<(), WireCompatError> {
+    let bytes = value.try_to_vec().unwrap();
+    let decoded = verify_roundtrip::<T>(&bytes)?;
+    if decoded != *value {
+        return Err(WireCompatError::ValueMismatch(std::any::type_name::<T>()));
+    }
+    Ok(())
+}
+
+/// Checks raw Phoenix instruction data: the tag must be known, and its parameters (or, for
+/// `Log`, its events) must round-trip exactly. Returns the instruction tag.
+pub fn verify_instruction_data(data: &[u8]) -> Result<PhoenixInstruction, WireCompatError> {
+    let (tag, params) = data.split_first().ok_or(WireCompatError::Empty)?;
+    let instruction = PhoenixInstruction::try_from(*tag)
+        .map_err(|_| WireCompatError::UnknownInstruction(*tag))?;
+    match instruction {
+        PhoenixInstruction::Swap
+        | PhoenixInstruction::SwapWithFreeFunds
+        | PhoenixInstruction::PlaceLimitOrder
+        | PhoenixInstruction::PlaceLimitOrderWithFreeFunds => {
+            verify_roundtrip_at::<OrderPacket>(params, 1).map(drop)
+        }
+        PhoenixInstruction::ReduceOrder | PhoenixInstruction::ReduceOrderWithFreeFunds => {
+            verify_roundtrip_at::<ReduceOrderParams>(params, 1).map(drop)
+        }
+        PhoenixInstruction::CancelUpTo | PhoenixInstruction::CancelUpToWithFreeFunds => {
+            verify_roundtrip_at::<CancelUpToParams>(params, 1).map(drop)
+        }
+        PhoenixInstruction::CancelMultipleOrdersById
+        | PhoenixInstruction::CancelMultipleOrdersByIdWithFreeFunds => {
+            verify_roundtrip_at::<CancelMultipleOrdersByIdParams>(params, 1).map(drop)
+        }
+        PhoenixInstruction::WithdrawFunds => {
+            verify_roundtrip_at::<WithdrawParams>(params, 1).map(drop)
+        }
+        PhoenixInstruction::DepositFunds => {
+            verify_roundtrip_at::<DepositParams>(params, 1).map(drop)
+        }
+        PhoenixInstruction::PlaceMultiplePostOnlyOrders
+        | PhoenixInstruction::PlaceMultiplePostOnlyOrdersWithFreeFunds => {
+            verify_roundtrip_at::<MultipleOrderPacket>(params, 1).map(drop)
+        }
+        PhoenixInstruction::CancelAllOrders
+        | PhoenixInstruction::CancelAllOrdersWithFreeFunds
+        | PhoenixInstruction::RequestSeat => match params.len() {
+            0 => Ok(()),
+            trailing => Err(WireCompatError::TrailingBytes(trailing)),
+        },
+        PhoenixInstruction::Log => verify_events_at(params, 1).map(drop),
+    }?;
+    Ok(instruction)
+}
+
+/// Checks the data of a `Log` instruction, without its tag: a sequence of `MarketEvent`s that
+/// must each round-trip exactly. Returns the decoded events.
+pub fn verify_events(data: &[u8]) -> Result<Vec<MarketEvent>, WireCompatError> {
+    verify_events_at(data, 0)
+}
+
+fn verify_events_at(data: &[u8], base_offset: usize) -> Result<Vec<MarketEvent>, WireCompatError> {
+    let mut events = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        let mut remaining = &data[offset..];
+        let event =
+            <MarketEvent as BorshDeserialize>::deserialize(&mut remaining).map_err(|e| {
+                WireCompatError::Deserialize {
+                    type_name: std::any::type_name::<MarketEvent>(),
+                    offset: base_offset + offset,
+                    message: e.to_string(),
+                }
+            })?;
+        let len = data.len() - offset - remaining.len();
+        check_reserialized(&data[offset..offset + len], &event, base_offset + offset)?;
+        events.push(event);
+        offset += len;
+    }
+    Ok(events)
+}
+
+fn verify_roundtrip_at<T: BorshSerialize + BorshDeserialize>(
+    bytes: &[u8],
+    base_offset: usize,
+) -> Result<T, WireCompatError> {
+    let mut remaining = bytes;
+    let value = T::deserialize(&mut remaining).map_err(|e| WireCompatError::Deserialize {
+        type_name: std::any::type_name::<T>(),
+        offset: base_offset,
+        message: e.to_string(),
+    })?;
+    if !remaining.is_empty() {
+        return Err(WireCompatError::TrailingBytes(remaining.len()));
+    }
+    check_reserialized(bytes, &value, base_offset)?;
+    Ok(value)
+}
+
+fn check_reserialized<T: BorshSerialize>(
+    original: &[u8],
+    value: &T,
+    base_offset: usize,
+) -> Result<(), WireCompatError> {
+    let reserialized = value.try_to_vec().unwrap();
+    if reserialized == original {
+        return Ok(());
+    }
+    let offset = original
+        .iter()
+        .zip(reserialized.iter())
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| original.len().min(reserialized.len()));
+    Err(WireCompatError::Mismatch(RoundTripMismatch {
+        offset: base_offset + offset,
+        original_len: original.len(),
+        reserialized_len: reserialized.len(),
+        original: original.get(offset).copied(),
+        reserialized: reserialized.get(offset).copied(),
+    }))
+}